@@ -0,0 +1,19 @@
+use std::process::Command;
+
+// Capture the git commit and build profile so result files can be traced back to the exact code
+fn main() {
+    let git_commit = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    let profile = std::env::var("PROFILE").unwrap_or_else(|_| "unknown".to_string());
+
+    println!("cargo:rustc-env=FIREFLY_GIT_COMMIT={}", git_commit);
+    println!("cargo:rustc-env=FIREFLY_BUILD_PROFILE={}", profile);
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/refs");
+}
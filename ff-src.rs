@@ -1,4 +1,5 @@
 use rand::Rng;
+use std::cmp::Ordering;
 use std::f64::consts::PI;
 
 const N: usize = 20; // Number of fireflies
@@ -15,6 +16,16 @@ fn objective_function(x: &[f64]) -> f64 {
     x.iter().map(|&xi| xi * xi).sum()
 }
 
+// Order brightness values from best to worst (lower is better), NaN always last
+fn compare_brightness(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => a.total_cmp(&b),
+    }
+}
+
 // Distance between two fireflies - Cartesian Distance
 fn distance(x: &[f64], y: &[f64]) -> f64 {
     x.iter().zip(y.iter()).map(|(xi, yi)| (xi - yi).powi(2)).sum::<f64>().sqrt()
@@ -36,13 +47,13 @@ fn firefly_algorithm() {
     for _ in 0..number_of_simulations {
         // Sort fireflies based on brightness (lower is better)
         let mut indices: Vec<usize> = (0..N).collect();
-        indices.sort_by(|&i, &j| brightness[i].partial_cmp(&brightness[j]).unwrap());
+        indices.sort_by(|&i, &j| compare_brightness(brightness[i], brightness[j]));
         fireflies = indices.iter().map(|&i| fireflies[i]).collect();
         brightness = indices.iter().map(|&i| brightness[i]).collect();
 
         for i in 0..N {
             for j in 0..N {
-                if brightness[j] < brightness[i] {
+                if compare_brightness(brightness[j], brightness[i]) == Ordering::Less {
                     let r = distance(&fireflies[i], &fireflies[j]);
                     let beta = BETA0 * (-GAMMA * r * r).exp();
                     for d in 0..D {
@@ -57,7 +68,7 @@ fn firefly_algorithm() {
     }
 
     // Print best solution found
-    let best_index = brightness.iter().enumerate().min_by(|a, b| a.1.partial_cmp(b.1).unwrap()).unwrap().0;
+    let best_index = brightness.iter().enumerate().min_by(|a, b| compare_brightness(*a.1, *b.1)).unwrap().0;
     println!("Best solution: {:?}", fireflies[best_index]);
     println!("Best objective value: {}", brightness[best_index]);
 }
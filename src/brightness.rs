@@ -0,0 +1,95 @@
+use crate::compare_fitness;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// How a population search perceives the brightness of a firefly as light, which
+/// scales its attraction.
+///
+/// Every transformation measures a firefly's brightness above the dimmest firefly of
+/// the iteration: `identity` as is, `log` through `ln(1 + x)` so that large fitness
+/// gaps pull less than proportionally, and `rank` as the share of the population that
+/// is dimmer, which does not depend on the scale of the fitness at all.
+///
+/// ```
+/// use ff_wmn::BrightnessTransform;
+///
+/// let brightness = [8.0, 2.0, 4.0, 2.0];
+/// assert_eq!(BrightnessTransform::Identity.light(&brightness), [6.0, 0.0, 2.0, 0.0]);
+/// let rank: BrightnessTransform = "rank".parse().unwrap();
+/// assert_eq!(rank.light(&brightness), [1.0, 0.0, 2.0 / 3.0, 0.0]);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BrightnessTransform {
+    Identity,
+    Log,
+    Rank,
+}
+
+impl BrightnessTransform {
+    /// Light of every firefly of a population with the given brightness; fireflies
+    /// whose brightness is NaN give no light
+    pub fn light(self, brightness: &[f64]) -> Vec<f64> {
+        let dimmest = brightness
+            .iter()
+            .copied()
+            .filter(|value| !value.is_nan())
+            .fold(f64::INFINITY, f64::min);
+        let others = brightness.len().saturating_sub(1).max(1) as f64;
+        brightness
+            .iter()
+            .map(|&value| {
+                if value.is_nan() {
+                    return 0.0;
+                }
+                match self {
+                    BrightnessTransform::Identity => value - dimmest,
+                    BrightnessTransform::Log => (value - dimmest).ln_1p(),
+                    BrightnessTransform::Rank => {
+                        let dimmer = brightness
+                            .iter()
+                            .filter(|&&other| compare_fitness(value, other).is_gt())
+                            .count();
+                        dimmer as f64 / others
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+impl FromStr for BrightnessTransform {
+    type Err = String;
+
+    /// `identity`, `log` or `rank`
+    fn from_str(value: &str) -> Result<BrightnessTransform, String> {
+        match value.trim() {
+            "identity" => Ok(BrightnessTransform::Identity),
+            "log" => Ok(BrightnessTransform::Log),
+            "rank" => Ok(BrightnessTransform::Rank),
+            _ => Err(format!(
+                "unknown brightness transformation {}, expected identity, log or rank",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BrightnessTransform {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            BrightnessTransform::Identity => "identity",
+            BrightnessTransform::Log => "log",
+            BrightnessTransform::Rank => "rank",
+        })
+    }
+}
+
+// Written in scenario files the same way as on the command line
+impl<'de> Deserialize<'de> for BrightnessTransform {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<BrightnessTransform, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
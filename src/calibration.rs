@@ -0,0 +1,180 @@
+use crate::PathLoss;
+
+/// One site-survey measurement: the signal received `distance` away from a router
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RssiSample {
+    pub distance: f64,
+    /// Received signal strength in dBm
+    pub rssi: f64,
+    /// Transmit power of the router in dBm, the model's power when not recorded
+    pub tx_power: Option<f64>,
+}
+
+/// Path-loss exponent and reference loss fitted to survey measurements by least
+/// squares on `rssi - tx_power = -reference_loss - 10 · exponent · log10(distance)`.
+///
+/// ```
+/// use ff_wmn::PathLoss;
+/// use ff_wmn::calibration::{Calibration, RssiSample};
+///
+/// let samples: Vec<RssiSample> = [(1.0, -20.0), (10.0, -50.0), (100.0, -80.0)]
+///     .into_iter()
+///     .map(|(distance, rssi)| RssiSample { distance, rssi, tx_power: None })
+///     .collect();
+/// let calibration = Calibration::fit(&samples, &PathLoss::default()).unwrap();
+/// assert!((calibration.exponent - 3.0).abs() < 1e-9);
+/// assert!((calibration.reference_loss - 40.0).abs() < 1e-9);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Calibration {
+    pub reference_loss: f64,
+    pub exponent: f64,
+    /// Root mean square difference between the fitted and measured signal in dB
+    pub rmse: f64,
+    /// Share of the variance of the measured path loss the fit explains
+    pub r_squared: f64,
+    pub samples: usize,
+}
+
+impl Calibration {
+    /// Fit the model to `samples`, taking the transmit power of samples without one
+    /// from `model`. Distances below the reference distance of 1 count as 1, like in
+    /// [`PathLoss::received_power`].
+    pub fn fit(samples: &[RssiSample], model: &PathLoss) -> Result<Calibration, String> {
+        // Path loss against 10 · log10(distance): loss = reference_loss + exponent · x
+        let points: Vec<(f64, f64)> = samples
+            .iter()
+            .map(|sample| {
+                (
+                    10.0 * sample.distance.max(1.0).log10(),
+                    sample.tx_power.unwrap_or(model.tx_power) - sample.rssi,
+                )
+            })
+            .collect();
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_loss = points.iter().map(|(_, loss)| loss).sum::<f64>() / n;
+        let spread: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+        if points.len() < 2 || spread == 0.0 {
+            return Err("calibration needs samples at two or more distances".to_string());
+        }
+        let covariance: f64 = points
+            .iter()
+            .map(|(x, loss)| (x - mean_x) * (loss - mean_loss))
+            .sum();
+        let exponent = covariance / spread;
+        if !exponent.is_finite() || exponent <= 0.0 {
+            return Err(format!(
+                "the measured signal does not weaken with distance (exponent {:.3})",
+                exponent
+            ));
+        }
+        let reference_loss = mean_loss - exponent * mean_x;
+
+        let residual: f64 = points
+            .iter()
+            .map(|(x, loss)| (loss - reference_loss - exponent * x).powi(2))
+            .sum();
+        let total: f64 = points
+            .iter()
+            .map(|(_, loss)| (loss - mean_loss).powi(2))
+            .sum();
+        Ok(Calibration {
+            reference_loss,
+            exponent,
+            rmse: (residual / n).sqrt(),
+            r_squared: if total == 0.0 {
+                1.0
+            } else {
+                1.0 - residual / total
+            },
+            samples: points.len(),
+        })
+    }
+
+    /// `model` with the fitted exponent and reference loss
+    pub fn apply(&self, model: PathLoss) -> PathLoss {
+        PathLoss {
+            reference_loss: self.reference_loss,
+            exponent: self.exponent,
+            ..model
+        }
+    }
+}
+
+impl std::fmt::Display for Calibration {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "exponent {:.3}, reference loss {:.2} dB from {} samples (RMSE {:.2} dB, R² {:.3})",
+            self.exponent, self.reference_loss, self.samples, self.rmse, self.r_squared
+        )
+    }
+}
+
+/// Read survey samples from CSV with the columns distance, rssi and optionally
+/// tx_power; a header row may name them in any order
+pub fn parse_samples(contents: &str) -> Result<Vec<RssiSample>, String> {
+    let mut rows = contents
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .peekable();
+
+    // Column indices of distance, rssi and the optional transmit power
+    let mut columns = (0, 1, Some(2));
+    if let Some((_, header)) = rows.peek()
+        && header
+            .split(',')
+            .next()
+            .is_some_and(|field| field.trim().parse::<f64>().is_err())
+    {
+        let names: Vec<String> = header
+            .split(',')
+            .map(|name| name.trim().to_ascii_lowercase())
+            .collect();
+        let column = |name: &str| names.iter().position(|column| column == name);
+        columns = (
+            column("distance").ok_or("header has no distance column")?,
+            column("rssi").ok_or("header has no rssi column")?,
+            column("tx_power"),
+        );
+        rows.next();
+    }
+
+    let (distance, rssi, tx_power) = columns;
+    let mut samples = Vec::new();
+    for (line, row) in rows {
+        let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+        let field = |index: usize| -> Result<Option<f64>, String> {
+            match fields.get(index) {
+                None | Some(&"") => Ok(None),
+                Some(value) => value
+                    .parse::<f64>()
+                    .ok()
+                    .filter(|value| value.is_finite())
+                    .map(Some)
+                    .ok_or_else(|| format!("line {}: invalid number {}", line, value)),
+            }
+        };
+        let missing = |name: &str| format!("line {}: missing {}", line, name);
+        let sample = RssiSample {
+            distance: field(distance)?.ok_or_else(|| missing("distance"))?,
+            rssi: field(rssi)?.ok_or_else(|| missing("rssi"))?,
+            tx_power: tx_power.map(field).transpose()?.flatten(),
+        };
+        if sample.distance < 0.0 {
+            return Err(format!("line {}: negative distance", line));
+        }
+        samples.push(sample);
+    }
+    Ok(samples)
+}
+
+/// Read survey samples from a CSV file, see [`parse_samples`]
+pub fn load_samples(path: &str) -> Result<Vec<RssiSample>, String> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|err| format!("Unable to read survey {}: {}", path, err))?;
+    parse_samples(&contents).map_err(|err| format!("Invalid survey {}: {}", path, err))
+}
@@ -0,0 +1,291 @@
+use crate::DIMENSIONS;
+use rand::Rng;
+use serde_json::Value;
+
+/// Mesh client positions with a weight per client.
+///
+/// Coverage metrics count each covered client with its weight, so uniformly
+/// weighted clients (weight 1) reproduce the plain client counts.
+#[derive(Clone, Debug, Default)]
+pub struct ClientSet {
+    pub positions: Vec<[f64; DIMENSIONS]>,
+    pub weights: Vec<f64>,
+    /// File the clients were loaded from, None for generated clients
+    pub source: Option<String>,
+}
+
+impl ClientSet {
+    /// Clients of weight 1 at the given positions
+    pub fn new(positions: Vec<[f64; DIMENSIONS]>) -> ClientSet {
+        let weights = vec![1.0; positions.len()];
+        ClientSet {
+            positions,
+            weights,
+            source: None,
+        }
+    }
+
+    pub fn with_weights(
+        positions: Vec<[f64; DIMENSIONS]>,
+        weights: Vec<f64>,
+    ) -> Result<ClientSet, String> {
+        if weights.len() != positions.len() {
+            return Err(format!(
+                "{} client weights given for {} clients",
+                weights.len(),
+                positions.len()
+            ));
+        }
+        if let Some(position) = positions
+            .iter()
+            .position(|point| !point.iter().all(|coord| coord.is_finite()))
+        {
+            return Err(format!("client {} has a non-finite coordinate", position));
+        }
+        if let Some(position) = weights
+            .iter()
+            .position(|weight| !(weight.is_finite() && *weight >= 0.0))
+        {
+            return Err(format!(
+                "client {} has an invalid weight {}, expected a finite non-negative number",
+                position, weights[position]
+            ));
+        }
+        Ok(ClientSet {
+            positions,
+            weights,
+            source: None,
+        })
+    }
+
+    /// `count` clients of weight 1 placed uniformly at random within the bounds
+    pub fn random(
+        count: usize,
+        lower_bound: f64,
+        upper_bound: f64,
+        rng: &mut impl Rng,
+    ) -> ClientSet {
+        let mut positions = vec![[0.0; DIMENSIONS]; count];
+        for client in positions.iter_mut() {
+            for coord in client.iter_mut() {
+                *coord = rng.gen_range(lower_bound..upper_bound);
+            }
+        }
+        ClientSet::new(positions)
+    }
+
+    /// Load clients from a `.csv`, `.geojson` or `.json` file
+    pub fn load(path: &str) -> Result<ClientSet, String> {
+        let extension = std::path::Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("csv") => ClientSet::from_csv(path),
+            Some("geojson") => ClientSet::from_geojson(path),
+            _ => ClientSet::from_json(path),
+        }
+    }
+
+    /// Read `x,y[,weight]` rows, with an optional header naming the columns
+    pub fn from_csv(path: &str) -> Result<ClientSet, String> {
+        let contents = read(path)?;
+        ClientSet::parse_csv(&contents)
+            .map(|clients| clients.with_source(path))
+            .map_err(|err| format!("Invalid clients in {}: {}", path, err))
+    }
+
+    /// Read a JSON array of `[x, y]` or `{"x": .., "y": .., "weight": ..}` entries, a result
+    /// file's `mesh_clients` (and `mesh_client_weights`), or a GeoJSON feature collection
+    pub fn from_json(path: &str) -> Result<ClientSet, String> {
+        let data = read_json(path)?;
+        ClientSet::from_value(&data)
+            .map(|clients| clients.with_source(path))
+            .map_err(|err| format!("Invalid clients in {}: {}", path, err))
+    }
+
+    /// Read the Point and MultiPoint features of a GeoJSON feature collection, taking the
+    /// weight from each feature's `weight` property. Coordinates are used as planar
+    /// coordinates, so project longitude/latitude data first.
+    pub fn from_geojson(path: &str) -> Result<ClientSet, String> {
+        let data = read_json(path)?;
+        ClientSet::parse_geojson(&data)
+            .map(|clients| clients.with_source(path))
+            .map_err(|err| format!("Invalid clients in {}: {}", path, err))
+    }
+
+    /// Clients from a parsed JSON document, see [`ClientSet::from_json`]
+    pub fn from_value(data: &Value) -> Result<ClientSet, String> {
+        if data["type"] == "FeatureCollection" {
+            return ClientSet::parse_geojson(data);
+        }
+        if let Some(clients) = data.get("mesh_clients") {
+            let positions = serde_json::from_value(clients.clone())
+                .map_err(|err| format!("invalid mesh_clients: {}", err))?;
+            return match data.get("mesh_client_weights") {
+                Some(weights) if !weights.is_null() => ClientSet::with_weights(
+                    positions,
+                    serde_json::from_value(weights.clone())
+                        .map_err(|err| format!("invalid mesh_client_weights: {}", err))?,
+                ),
+                _ => Ok(ClientSet::new(positions)),
+            };
+        }
+        let entries = data
+            .as_array()
+            .ok_or("expected an array of clients, a result file or a GeoJSON feature collection")?;
+        let mut positions = Vec::with_capacity(entries.len());
+        let mut weights = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.iter().enumerate() {
+            let invalid = || {
+                format!(
+                    "client {} is not [x, y] or {{\"x\", \"y\", \"weight\"}}",
+                    index
+                )
+            };
+            if entry.is_array() {
+                positions.push(serde_json::from_value(entry.clone()).map_err(|_| invalid())?);
+                weights.push(1.0);
+            } else {
+                let x = entry["x"].as_f64().ok_or_else(invalid)?;
+                let y = entry["y"].as_f64().ok_or_else(invalid)?;
+                positions.push([x, y]);
+                weights.push(match &entry["weight"] {
+                    Value::Null => 1.0,
+                    weight => weight.as_f64().ok_or_else(invalid)?,
+                });
+            }
+        }
+        ClientSet::with_weights(positions, weights)
+    }
+
+    fn parse_csv(contents: &str) -> Result<ClientSet, String> {
+        let mut rows = contents
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .peekable();
+
+        // Column indices of x, y and the optional weight
+        let mut columns = (0, 1, Some(2));
+        if let Some((_, header)) = rows.peek()
+            && header
+                .split(',')
+                .next()
+                .is_some_and(|field| field.trim().parse::<f64>().is_err())
+        {
+            let names: Vec<String> = header
+                .split(',')
+                .map(|name| name.trim().to_ascii_lowercase())
+                .collect();
+            let column = |name: &str| names.iter().position(|column| column == name);
+            columns = (
+                column("x").ok_or("header has no x column")?,
+                column("y").ok_or("header has no y column")?,
+                column("weight"),
+            );
+            rows.next();
+        }
+
+        let (x, y, weight) = columns;
+        let mut positions = Vec::new();
+        let mut weights = Vec::new();
+        for (line, row) in rows {
+            let fields: Vec<&str> = row.split(',').map(str::trim).collect();
+            let field = |index: usize| -> Result<Option<f64>, String> {
+                match fields.get(index) {
+                    None | Some(&"") => Ok(None),
+                    Some(value) => value
+                        .parse()
+                        .map(Some)
+                        .map_err(|_| format!("line {}: invalid number {}", line, value)),
+                }
+            };
+            let missing = |name: &str| format!("line {}: missing {}", line, name);
+            positions.push([
+                field(x)?.ok_or_else(|| missing("x"))?,
+                field(y)?.ok_or_else(|| missing("y"))?,
+            ]);
+            weights.push(match weight {
+                Some(weight) => field(weight)?.unwrap_or(1.0),
+                None => 1.0,
+            });
+        }
+        ClientSet::with_weights(positions, weights)
+    }
+
+    fn parse_geojson(data: &Value) -> Result<ClientSet, String> {
+        let features = data["features"]
+            .as_array()
+            .ok_or("expected a GeoJSON FeatureCollection")?;
+        let mut positions = Vec::new();
+        let mut weights = Vec::new();
+        for (index, feature) in features.iter().enumerate() {
+            let weight = match &feature["properties"]["weight"] {
+                Value::Null => 1.0,
+                weight => weight
+                    .as_f64()
+                    .ok_or_else(|| format!("feature {} has a non-numeric weight", index))?,
+            };
+            let geometry = &feature["geometry"];
+            let points = match geometry["type"].as_str() {
+                Some("Point") => vec![geometry["coordinates"].clone()],
+                Some("MultiPoint") => geometry["coordinates"]
+                    .as_array()
+                    .cloned()
+                    .unwrap_or_default(),
+                _ => continue,
+            };
+            for point in points {
+                // GeoJSON positions may carry an altitude after the planar coordinates
+                let coordinates: Vec<f64> = serde_json::from_value(point)
+                    .map_err(|_| format!("feature {} has invalid coordinates", index))?;
+                let [x, y, ..] = coordinates[..] else {
+                    return Err(format!("feature {} has invalid coordinates", index));
+                };
+                positions.push([x, y]);
+                weights.push(weight);
+            }
+        }
+        ClientSet::with_weights(positions, weights)
+    }
+
+    /// Record where the clients came from, a path or URL
+    pub fn with_source(mut self, path: &str) -> ClientSet {
+        self.source = Some(path.to_string());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    pub fn total_weight(&self) -> f64 {
+        self.weights.iter().sum()
+    }
+
+    /// Share of the total weight that a covered weight such as NCMC makes up, 1 when
+    /// the clients weigh nothing
+    pub fn coverage_share(&self, covered: f64) -> f64 {
+        let total = self.total_weight();
+        if total == 0.0 { 1.0 } else { covered / total }
+    }
+
+    /// Whether any client has a weight other than 1
+    pub fn is_weighted(&self) -> bool {
+        self.weights.iter().any(|&weight| weight != 1.0)
+    }
+}
+
+fn read(path: &str) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|err| format!("Unable to read {}: {}", path, err))
+}
+
+fn read_json(path: &str) -> Result<Value, String> {
+    serde_json::from_str(&read(path)?).map_err(|err| format!("Unable to parse {}: {}", path, err))
+}
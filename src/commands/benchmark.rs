@@ -0,0 +1,97 @@
+use clap::{Args, ValueEnum};
+use ff_wmn::objective::{Objective, Rastrigin, Rosenbrock, Sphere};
+use ff_wmn::{AlphaSchedule, BrightnessTransform, FireflyCore, NonFinitePolicy, TieBreak};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum BenchmarkFunction {
+    Sphere,
+    Rastrigin,
+    Rosenbrock,
+}
+
+#[derive(Args)]
+pub struct BenchmarkArgs {
+    /// Function to minimize
+    #[arg(long, value_enum)]
+    function: BenchmarkFunction,
+    /// Number of dimensions of the function
+    #[arg(long, default_value_t = 2)]
+    dimensions: usize,
+    /// Number of fireflies
+    #[arg(long, default_value_t = 20)]
+    fireflies: usize,
+    /// Number of firefly iterations
+    #[arg(long, default_value_t = 100)]
+    iterations: usize,
+    /// Random step size
+    #[arg(long, default_value_t = 0.5)]
+    alpha: f64,
+    /// Decay of the random step size: constant, linear:<final fraction> or geometric:<factor>
+    #[arg(long, value_name = "SCHEDULE", default_value = "constant")]
+    alpha_schedule: AlphaSchedule,
+    /// Attractiveness at distance zero
+    #[arg(long, default_value_t = 1.0)]
+    beta0: f64,
+    /// Light absorption coefficient
+    #[arg(long, default_value_t = 1.0)]
+    gamma: f64,
+    /// Attract fireflies in proportion to their light: identity, log or rank
+    #[arg(long, value_name = "TRANSFORM")]
+    brightness: Option<BrightnessTransform>,
+    /// Solution kept as best when values tie: keep-incumbent or prefer-newer
+    #[arg(long, value_name = "POLICY", default_value = "keep-incumbent")]
+    tie_break: TieBreak,
+    /// Seed the random number generator so the benchmark can be reproduced exactly
+    #[arg(long)]
+    seed: Option<u64>,
+}
+
+// `benchmark --function rastrigin --dimensions 10`: check the firefly core on a
+// function with a known minimum
+pub fn benchmark_command(args: BenchmarkArgs) {
+    if args.dimensions == 0 || args.fireflies < 2 {
+        eprintln!("The benchmark needs at least one dimension and two fireflies");
+        std::process::exit(2);
+    }
+    if !args.alpha_schedule.is_valid() {
+        eprintln!(
+            "The alpha schedule {} needs a parameter in [0, 1]",
+            args.alpha_schedule
+        );
+        std::process::exit(2);
+    }
+    let mut objective: Box<dyn Objective> = match args.function {
+        BenchmarkFunction::Sphere => Box::new(Sphere::new(args.dimensions)),
+        BenchmarkFunction::Rastrigin => Box::new(Rastrigin::new(args.dimensions)),
+        BenchmarkFunction::Rosenbrock => Box::new(Rosenbrock::new(args.dimensions)),
+    };
+    let core = FireflyCore {
+        fireflies: args.fireflies,
+        iterations: args.iterations,
+        alpha: vec![args.alpha],
+        beta0: args.beta0,
+        gamma: args.gamma,
+        brightness: args.brightness,
+        tie_break: args.tie_break,
+        non_finite: NonFinitePolicy::Abort,
+        alpha_schedule: args.alpha_schedule,
+        early_stopping: None,
+        evaluation_interval: 1,
+    };
+    let mut rng = match args.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let result = core
+        .optimize(objective.as_mut(), &mut rng)
+        .unwrap_or_else(|err| {
+            eprintln!("Aborted: {}", err);
+            std::process::exit(1);
+        });
+
+    println!("Best value: {}", result.best_value);
+    println!("Best solution: {:?}", result.best);
+    println!("Evaluations: {}", result.evaluations);
+}
@@ -0,0 +1,50 @@
+use clap::Args;
+use ff_wmn::calibration::{self, Calibration};
+use ff_wmn::{FireflyConfig, Scenario};
+
+#[derive(Args)]
+pub struct CalibrateArgs {
+    /// CSV of survey samples: distance,rssi[,tx_power]
+    survey: String,
+    /// TOML scenario whose path-loss model to calibrate, for the transmit power and
+    /// sensitivity
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+}
+
+// `calibrate survey.csv`: fit the path-loss model to measured RSSI and print the
+// parameters to put in a scenario
+pub fn calibrate_command(args: CalibrateArgs) {
+    if let Err(message) = calibrate(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn calibrate(args: CalibrateArgs) -> Result<(), String> {
+    let CalibrateArgs { survey, scenario } = args;
+    let config = match scenario {
+        Some(path) => Scenario::load(&path)?.apply(FireflyConfig::default()),
+        None => FireflyConfig::default(),
+    };
+    let model = config.radio.path_loss.unwrap_or_default();
+    let calibration = Calibration::fit(&calibration::load_samples(&survey)?, &model)
+        .map_err(|err| format!("Unable to calibrate to {}: {}", survey, err))?;
+    let calibrated = calibration.apply(model);
+
+    println!("Fitted {}", calibration);
+    println!(
+        "Coverage range {:.2} at {} dBm transmit power and {} dBm sensitivity (was {:.2})",
+        calibrated.range(),
+        calibrated.tx_power,
+        calibrated.sensitivity,
+        model.range()
+    );
+    println!();
+    println!("[radio.path_loss]");
+    println!("tx_power = {:?}", calibrated.tx_power);
+    println!("reference_loss = {:?}", calibrated.reference_loss);
+    println!("exponent = {:?}", calibrated.exponent);
+    println!("sensitivity = {:?}", calibrated.sensitivity);
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use clap::Args;
+use ff_wmn::consensus::consensus;
+use ff_wmn::solution::points_from_json;
+use ff_wmn::{ClientSet, FireflyConfig, Scenario};
+use serde_json::{Value, json};
+
+#[derive(Args)]
+pub struct ConsensusArgs {
+    /// Result files of runs of the same scenario, e.g. with different seeds
+    #[arg(required = true, num_args = 2..)]
+    results: Vec<String>,
+    /// Distance within which a run's router counts as agreeing with the consensus;
+    /// half the communication distance by default
+    #[arg(long, value_name = "DISTANCE")]
+    tolerance: Option<f64>,
+    /// TOML scenario the runs were optimized with
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+    /// Save the consensus layout and per-router stability as JSON
+    #[arg(long, value_name = "PATH")]
+    output: Option<String>,
+}
+
+// `consensus <result>... [--tolerance <distance>]`: combine the layouts of several runs
+// into a consensus placement and show how stable every router is
+pub fn consensus_command(args: ConsensusArgs) {
+    if let Err(message) = run_consensus(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn run_consensus(args: ConsensusArgs) -> Result<(), String> {
+    let ConsensusArgs {
+        results,
+        tolerance,
+        scenario,
+        output,
+    } = args;
+    let config = match scenario {
+        Some(path) => Scenario::load(&path)?.apply(FireflyConfig::default()),
+        None => FireflyConfig::default(),
+    };
+    let tolerance = tolerance.unwrap_or(config.max_communication_distance / 2.0);
+
+    let mut runs = Vec::new();
+    for path in &results {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {}", path, err))?;
+        let data: Value = serde_json::from_str(&contents)
+            .map_err(|err| format!("Unable to parse {}: {}", path, err))?;
+        runs.push(data);
+    }
+    let layouts = runs
+        .iter()
+        .map(|data| points_from_json(data, "mesh_routers"))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some((path, layout)) = results
+        .iter()
+        .zip(&layouts)
+        .find(|(_, layout)| layout.len() != layouts[0].len())
+    {
+        return Err(format!(
+            "{} has {} routers but {} has {}",
+            path,
+            layout.len(),
+            results[0],
+            layouts[0].len()
+        ));
+    }
+
+    let consensus = consensus(&layouts, tolerance);
+    println!(
+        "Consensus of {} runs, clustered around {}",
+        layouts.len(),
+        results[consensus.reference]
+    );
+    println!("router,x,y,stability,spread");
+    for (i, router) in consensus.routers.iter().enumerate() {
+        println!(
+            "{},{},{},{:.3},{:.3}",
+            i, router.position[0], router.position[1], router.stability, router.spread
+        );
+    }
+    println!(
+        "Mean stability: {:.3} (tolerance {})",
+        consensus.stability(),
+        tolerance
+    );
+
+    if let Some(path) = output {
+        // The reference run's clients make the consensus usable wherever a result file is
+        let clients = ClientSet::from_value(&runs[consensus.reference])?;
+        let data = json!({
+            "runs": results,
+            "reference": results[consensus.reference],
+            "tolerance": tolerance,
+            "mean_stability": consensus.stability(),
+            "mesh_routers": consensus.layout(),
+            "mesh_clients": clients.positions,
+            "mesh_client_weights": clients.weights,
+            "routers": consensus
+                .routers
+                .iter()
+                .map(|router| json!({
+                    "position": router.position,
+                    "stability": router.stability,
+                    "spread": router.spread
+                }))
+                .collect::<Vec<_>>()
+        });
+        std::fs::write(&path, data.to_string())
+            .map_err(|err| format!("Unable to write {}: {}", path, err))?;
+        println!("Consensus saved to {}", path);
+    }
+    Ok(())
+}
@@ -0,0 +1,649 @@
+use clap::Args;
+use ff_wmn::heuristics::apply_heuristics;
+use ff_wmn::metrics::{ncmc, sgc};
+#[cfg(feature = "mqtt")]
+use ff_wmn::mqtt;
+use ff_wmn::radio::Radio;
+use ff_wmn::relocation::MOVE_TOLERANCE;
+use ff_wmn::solution::routers_from_json;
+use ff_wmn::{
+    ClientSet, ConnectivityGraph, DIMENSIONS, FireflyConfig, FireflyOptimizer, Relocation,
+    Scenario, Solution, distance, match_layouts,
+};
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+// Time allowed to connect to, send to or hear from a source or sink
+const HTTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Parse a duration such as 15m, 30s, 2h or 500ms; a bare number is in seconds
+fn parse_interval(value: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid interval {}, expected e.g. 15m, 30s, 2h or 500ms",
+            value
+        )
+    };
+    let value = value.trim();
+    let split = value
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.trim().parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(invalid()),
+    };
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|interval| !interval.is_zero())
+        .ok_or_else(invalid)
+}
+
+#[derive(Args)]
+pub struct DaemonArgs {
+    /// Where to fetch the mesh clients every cycle: an http:// URL serving JSON, a
+    /// client file (CSV, GeoJSON or JSON) that is re-read every cycle, or an mqtt://
+    /// topic whose latest message is used
+    #[arg(long, value_name = "URL|PATH")]
+    source: String,
+    /// Where to read the installed router layout every cycle, a JSON array of positions
+    /// or a result file, from an http:// URL, a file or an mqtt:// topic; without it
+    /// the previous plan is taken to be installed
+    #[arg(long, value_name = "URL|PATH")]
+    routers_source: Option<String>,
+    /// Time between the starts of two re-optimizations, e.g. 15m, 30s or 2h
+    #[arg(long, value_parser = parse_interval, default_value = "15m")]
+    interval: Duration,
+    /// Where to publish every plan: a file replaced atomically, an http:// URL to POST
+    /// it to, - for one JSON line per plan on standard output, or an mqtt:// topic,
+    /// with the position, radius and power of router i retained on <topic>/routers/i
+    #[arg(long, value_name = "URL|PATH|-", default_value = "firefly_plan.json")]
+    sink: String,
+    /// TOML scenario with the parameters of every re-optimization
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+    /// Fitness penalty per unit of mean router displacement from the previous plan
+    #[arg(long, value_name = "PENALTY", default_value_t = 0.5)]
+    relocation_penalty: f64,
+    /// Stop after this many cycles instead of running until interrupted
+    #[arg(long)]
+    cycles: Option<usize>,
+    /// Keep the metrics of the latest cycles in this file for dashboards, as CSV for
+    /// `.csv` paths and JSON Lines otherwise: coverage of the installed layout and of
+    /// the new plan, fitness, router moves and whether the plan was published
+    #[arg(long, value_name = "PATH")]
+    history: Option<String>,
+    /// Cycles the history keeps, dropping the oldest first
+    #[arg(long, value_name = "CYCLES", default_value_t = 2880)]
+    history_keep: usize,
+}
+
+// Location of an http:// resource
+struct HttpUrl {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl HttpUrl {
+    // None for anything but an http:// URL
+    fn parse(url: &str) -> Result<Option<HttpUrl>, String> {
+        if url.starts_with("https://") {
+            return Err(format!(
+                "{}: HTTPS is not supported, use http:// or a file",
+                url
+            ));
+        }
+        let Some(rest) = url.strip_prefix("http://") else {
+            return Ok(None);
+        };
+        let (authority, path) = match rest.find('/') {
+            Some(slash) => rest.split_at(slash),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("Invalid port in {}", url))?,
+            ),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(format!("No host in {}", url));
+        }
+        Ok(Some(HttpUrl {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        }))
+    }
+
+    // Send a request with an optional JSON body and return the body of a 2xx response.
+    // HTTP/1.0 keeps the response free of chunked encoding.
+    fn request(&self, method: &str, body: Option<&str>) -> Result<String, String> {
+        let url = format!("http://{}:{}{}", self.host, self.port, self.path);
+        let failed = |err: std::io::Error| format!("{} {} failed: {}", method, url, err);
+        let mut stream = TcpStream::connect((self.host.as_str(), self.port)).map_err(failed)?;
+        stream
+            .set_read_timeout(Some(HTTP_TIMEOUT))
+            .map_err(failed)?;
+        stream
+            .set_write_timeout(Some(HTTP_TIMEOUT))
+            .map_err(failed)?;
+        let mut request = format!(
+            "{} {} HTTP/1.0\r\nHost: {}\r\nAccept: application/json\r\n",
+            method, self.path, self.host
+        );
+        if let Some(body) = body {
+            request += &format!(
+                "Content-Type: application/json\r\nContent-Length: {}\r\n",
+                body.len()
+            );
+        }
+        request += "\r\n";
+        request += body.unwrap_or("");
+        stream.write_all(request.as_bytes()).map_err(failed)?;
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).map_err(failed)?;
+
+        let response = String::from_utf8_lossy(&response);
+        let (head, body) = response
+            .split_once("\r\n\r\n")
+            .ok_or_else(|| format!("{} {}: malformed response", method, url))?;
+        let status = head.lines().next().unwrap_or_default();
+        if !status
+            .split_whitespace()
+            .nth(1)
+            .is_some_and(|code| code.starts_with('2'))
+        {
+            return Err(format!("{} {}: {}", method, url, status));
+        }
+        Ok(body.to_string())
+    }
+}
+
+fn fetch_json(url: &HttpUrl, source: &str) -> Result<Value, String> {
+    let body = url.request("GET", None)?;
+    serde_json::from_str(&body).map_err(|err| format!("Unable to parse {}: {}", source, err))
+}
+
+fn fetch_clients(source: &str) -> Result<ClientSet, String> {
+    let Some(url) = HttpUrl::parse(source)? else {
+        return ClientSet::load(source);
+    };
+    ClientSet::from_value(&fetch_json(&url, source)?)
+        .map(|clients| clients.with_source(source))
+        .map_err(|err| format!("Invalid clients from {}: {}", source, err))
+}
+
+fn fetch_routers(source: &str) -> Result<Vec<[f64; DIMENSIONS]>, String> {
+    let data = match HttpUrl::parse(source)? {
+        Some(url) => fetch_json(&url, source)?,
+        None => {
+            let contents = std::fs::read_to_string(source)
+                .map_err(|err| format!("Unable to read {}: {}", source, err))?;
+            serde_json::from_str(&contents)
+                .map_err(|err| format!("Unable to parse {}: {}", source, err))?
+        }
+    };
+    routers_from_json(&data).map_err(|err| format!("{}: {}", source, err))
+}
+
+#[cfg(not(feature = "mqtt"))]
+fn no_mqtt(url: &str) -> Result<(), String> {
+    if url.starts_with("mqtt://") {
+        return Err(format!(
+            "{}: built without MQTT support, rebuild with --features mqtt",
+            url
+        ));
+    }
+    Ok(())
+}
+
+// Telemetry read at the start of every cycle
+enum Source<T> {
+    // Fetched anew from an http:// URL or a file
+    Fetch(String, fn(&str) -> Result<T, String>),
+    // The latest message on an MQTT topic
+    #[cfg(feature = "mqtt")]
+    Mqtt(mqtt::MqttTopic, mqtt::Subscription<T>),
+}
+
+impl<T: Send + 'static> Source<T> {
+    #[cfg_attr(not(feature = "mqtt"), allow(unused_variables))]
+    fn open(
+        source: String,
+        fetch: fn(&str) -> Result<T, String>,
+        parse: fn(&Value) -> Result<T, String>,
+    ) -> Result<Source<T>, String> {
+        #[cfg(feature = "mqtt")]
+        if let Some(topic) = mqtt::MqttTopic::parse(&source)? {
+            let subscription = mqtt::Subscription::new(&topic, parse);
+            return Ok(Source::Mqtt(topic, subscription));
+        }
+        #[cfg(not(feature = "mqtt"))]
+        no_mqtt(&source)?;
+        HttpUrl::parse(&source)?;
+        Ok(Source::Fetch(source, fetch))
+    }
+
+    // The current value, None when a topic had no new message. Without a previous
+    // value, waits for the first message on a topic.
+    #[cfg_attr(not(feature = "mqtt"), allow(unused_variables))]
+    fn read(&self, has_previous: bool) -> Result<Option<T>, String> {
+        match self {
+            Source::Fetch(source, fetch) => fetch(source).map(Some),
+            #[cfg(feature = "mqtt")]
+            Source::Mqtt(_, subscription) if has_previous => Ok(subscription.latest()),
+            #[cfg(feature = "mqtt")]
+            Source::Mqtt(topic, subscription) => {
+                subscription.wait(HTTP_TIMEOUT).map(Some).ok_or_else(|| {
+                    format!(
+                        "No message on {} within {} s",
+                        topic,
+                        HTTP_TIMEOUT.as_secs()
+                    )
+                })
+            }
+        }
+    }
+}
+
+// Where every plan goes
+enum Sink {
+    Stdout,
+    Http(HttpUrl),
+    // Replaced atomically
+    File(String),
+    // The plan and per-router recommendations as retained messages
+    #[cfg(feature = "mqtt")]
+    Mqtt(mqtt::MqttPublisher),
+}
+
+impl Sink {
+    fn open(sink: String) -> Result<Sink, String> {
+        if sink == "-" {
+            return Ok(Sink::Stdout);
+        }
+        #[cfg(feature = "mqtt")]
+        if let Some(topic) = mqtt::MqttTopic::parse(&sink)? {
+            return Ok(Sink::Mqtt(mqtt::MqttPublisher::new(&topic)));
+        }
+        #[cfg(not(feature = "mqtt"))]
+        no_mqtt(&sink)?;
+        Ok(match HttpUrl::parse(&sink)? {
+            Some(url) => Sink::Http(url),
+            None => Sink::File(sink),
+        })
+    }
+
+    fn publish(&self, solution: &Solution) -> Result<(), String> {
+        let plan = || solution.to_json().to_string();
+        match self {
+            Sink::Stdout => {
+                println!("{}", plan());
+                Ok(())
+            }
+            Sink::Http(url) => url.request("POST", Some(&plan())).map(drop),
+            Sink::File(path) => {
+                // Written next to the sink and renamed, so readers never see half a plan
+                let partial = format!("{}.partial", path);
+                std::fs::write(&partial, plan())
+                    .and_then(|()| std::fs::rename(&partial, path))
+                    .map_err(|err| format!("Unable to write the plan to {}: {}", path, err))
+            }
+            #[cfg(feature = "mqtt")]
+            Sink::Mqtt(publisher) => publisher.publish_plan(solution),
+        }
+    }
+}
+
+// Columns of CSV daemon histories, also the keys of JSON Lines records
+const HISTORY_COLUMNS: [&str; 11] = [
+    "time",
+    "cycle",
+    "clients",
+    "installed_coverage",
+    "installed_sgc",
+    "coverage",
+    "sgc",
+    "fitness",
+    "relocation_distance",
+    "routers_moved",
+    "published",
+];
+
+// Metrics of one daemon cycle
+struct CycleRecord {
+    // Seconds since the Unix epoch
+    time: u64,
+    cycle: usize,
+    clients: usize,
+    // Coverage and SGC of the installed layout for the new clients, before re-optimizing
+    installed: Option<(f64, usize)>,
+    coverage: f64,
+    sgc: usize,
+    fitness: f64,
+    relocation_distance: Option<f64>,
+    routers_moved: Option<usize>,
+    published: bool,
+}
+
+impl CycleRecord {
+    fn new(
+        cycle: usize,
+        installed: Option<&[[f64; DIMENSIONS]]>,
+        solution: &Solution,
+        config: &FireflyConfig,
+    ) -> CycleRecord {
+        let radio = Radio::new(config);
+        let total = solution.clients.total_weight();
+        let installed = installed.map(|routers| {
+            let covered = ncmc(routers, &solution.clients, &radio);
+            (
+                if total == 0.0 { 1.0 } else { covered / total },
+                sgc(&ConnectivityGraph::new(routers, &radio)),
+            )
+        });
+        // Routers are matched to the nearest installed ones, as the same router may
+        // end up anywhere in the plan
+        let routers_moved = config.relocation.as_ref().map(|relocation| {
+            let matching = match_layouts(&relocation.anchor, &solution.routers);
+            matching
+                .assignment
+                .iter()
+                .enumerate()
+                .filter(|&(from, &to)| {
+                    distance(&relocation.anchor[from], &solution.routers[to])
+                        > MOVE_TOLERANCE * config.max_communication_distance
+                })
+                .count()
+        });
+        CycleRecord {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            cycle,
+            clients: solution.clients.len(),
+            installed,
+            coverage: solution.coverage(),
+            sgc: solution.report.metrics.sgc,
+            fitness: solution.report.metrics.fitness,
+            relocation_distance: solution.report.metrics.relocation_distance,
+            routers_moved,
+            published: false,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        json!({
+            "time": self.time,
+            "cycle": self.cycle,
+            "clients": self.clients,
+            "installed_coverage": self.installed.map(|(coverage, _)| coverage),
+            "installed_sgc": self.installed.map(|(_, sgc)| sgc),
+            "coverage": self.coverage,
+            "sgc": self.sgc,
+            "fitness": self.fitness,
+            "relocation_distance": self.relocation_distance,
+            "routers_moved": self.routers_moved,
+            "published": self.published
+        })
+    }
+
+    // A CSV row in the order of HISTORY_COLUMNS, with empty fields for missing values
+    fn to_csv(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        [
+            self.time.to_string(),
+            self.cycle.to_string(),
+            self.clients.to_string(),
+            optional(self.installed.map(|(coverage, _)| coverage.to_string())),
+            optional(self.installed.map(|(_, sgc)| sgc.to_string())),
+            self.coverage.to_string(),
+            self.sgc.to_string(),
+            self.fitness.to_string(),
+            optional(
+                self.relocation_distance
+                    .map(|distance| distance.to_string()),
+            ),
+            optional(self.routers_moved.map(|moved| moved.to_string())),
+            self.published.to_string(),
+        ]
+        .join(",")
+    }
+}
+
+// The last cycles as CSV for `.csv` paths and JSON Lines otherwise, rewritten every
+// cycle so the file never holds more than `keep` of them. Records already in the file
+// are kept, so the history survives restarts.
+struct CycleHistory {
+    path: String,
+    csv: bool,
+    keep: usize,
+    lines: VecDeque<String>,
+}
+
+impl CycleHistory {
+    fn open(path: String, keep: usize) -> Result<CycleHistory, String> {
+        if keep == 0 {
+            return Err("The history must keep at least one cycle".to_string());
+        }
+        let csv = path.ends_with(".csv");
+        let mut lines: VecDeque<String> = match std::fs::read_to_string(&path) {
+            Ok(contents) => contents
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => VecDeque::new(),
+            Err(err) => return Err(format!("Unable to read {}: {}", path, err)),
+        };
+        // Never overwrite a file that is not a history
+        let foreign = || format!("{} exists and is not a daemon history", path);
+        if csv {
+            if lines
+                .pop_front()
+                .is_some_and(|header| header != HISTORY_COLUMNS.join(","))
+            {
+                return Err(foreign());
+            }
+        } else if !lines.iter().all(|line| {
+            serde_json::from_str::<Value>(line).is_ok_and(|record| record.get("cycle").is_some())
+        }) {
+            return Err(foreign());
+        }
+        let mut history = CycleHistory {
+            path,
+            csv,
+            keep,
+            lines,
+        };
+        history.trim();
+        Ok(history)
+    }
+
+    fn trim(&mut self) {
+        while self.lines.len() > self.keep {
+            self.lines.pop_front();
+        }
+    }
+
+    fn push(&mut self, record: &CycleRecord) -> Result<(), String> {
+        self.lines.push_back(if self.csv {
+            record.to_csv()
+        } else {
+            record.to_json().to_string()
+        });
+        self.trim();
+        let mut contents = String::new();
+        if self.csv {
+            contents += &HISTORY_COLUMNS.join(",");
+            contents.push('\n');
+        }
+        for line in &self.lines {
+            contents += line;
+            contents.push('\n');
+        }
+        // Replaced atomically like a file sink, so a dashboard never reads half of it
+        let partial = format!("{}.partial", self.path);
+        std::fs::write(&partial, contents)
+            .and_then(|()| std::fs::rename(&partial, &self.path))
+            .map_err(|err| format!("Unable to write the history to {}: {}", self.path, err))
+    }
+}
+
+// `daemon --source http://controller/clients --interval 15m`: re-optimize periodically
+// for the current clients, starting from and staying close to the previous plan
+pub fn daemon_command(args: DaemonArgs) {
+    if let Err(message) = run_daemon(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn run_daemon(args: DaemonArgs) -> Result<(), String> {
+    let DaemonArgs {
+        source,
+        routers_source,
+        interval,
+        sink,
+        scenario,
+        relocation_penalty,
+        cycles,
+        history,
+        history_keep,
+    } = args;
+    // Fail on a malformed source or sink before the first cycle
+    let source = Source::open(source, fetch_clients, ClientSet::from_value)?;
+    let routers_source = routers_source
+        .map(|routers_source| Source::open(routers_source, fetch_routers, routers_from_json))
+        .transpose()?;
+    let sink = Sink::open(sink)?;
+    let mut history = history
+        .map(|path| CycleHistory::open(path, history_keep))
+        .transpose()?;
+    let scenario = match scenario {
+        Some(path) => Scenario::load(&path)?,
+        None => Scenario::default(),
+    };
+    let mut config = scenario.clone().apply(FireflyConfig::default());
+    if let Some(calibration) = scenario.calibrate(&mut config)? {
+        eprintln!("Calibrated the path-loss model: {}", calibration);
+    }
+    for choice in apply_heuristics(&mut config, &scenario) {
+        eprintln!("Heuristic {}", choice);
+    }
+    config
+        .clone()
+        .relocation(vec![[0.0; DIMENSIONS]; config.routers], relocation_penalty)
+        .validate()
+        .map_err(|err| format!("Invalid configuration: {}", err))?;
+    // Every cycle starts from the previous plan
+    if !config.starts_from_layout() {
+        return Err(
+            "Invalid configuration: the daemon needs the router swarm without tiling to start from the previous plan"
+                .to_string(),
+        );
+    }
+
+    let mut clients: Option<ClientSet> = None;
+    // The installed layout: the latest reported one, or else the last published plan
+    let mut plan: Option<Vec<[f64; DIMENSIONS]>> = None;
+    let mut next_start = Instant::now();
+    for cycle in 0..cycles.unwrap_or(usize::MAX) {
+        std::thread::sleep(next_start.saturating_duration_since(Instant::now()));
+        let start = Instant::now();
+        next_start = start + interval;
+        match source.read(clients.is_some()) {
+            Ok(Some(fetched)) => clients = Some(fetched),
+            Ok(None) => {}
+            Err(message) => match &clients {
+                Some(_) => eprintln!("Warning: {}; keeping the previous clients", message),
+                None => {
+                    eprintln!("Warning: {}; no plan this cycle", message);
+                    continue;
+                }
+            },
+        }
+        let clients = clients.clone().expect("clients were fetched");
+        match routers_source
+            .as_ref()
+            .map(|routers| routers.read(plan.is_some()))
+        {
+            Some(Ok(Some(installed))) if installed.len() == config.routers => {
+                plan = Some(installed)
+            }
+            Some(Ok(Some(installed))) => eprintln!(
+                "Warning: ignoring an installed layout of {} routers instead of {}",
+                installed.len(),
+                config.routers
+            ),
+            Some(Err(message)) => eprintln!("Warning: {}; assuming the previous plan", message),
+            Some(Ok(None)) | None => {}
+        }
+
+        // Every cycle is seeded differently so a seeded daemon still explores
+        let mut cycle_config = FireflyConfig {
+            seed: config.seed.map(|seed| seed.wrapping_add(cycle as u64)),
+            ..config.clone()
+        };
+        cycle_config.relocation = plan.clone().map(|anchor| Relocation {
+            anchor,
+            penalty: relocation_penalty,
+        });
+        // Creating the iteration history can still fail
+        let mut optimizer = match FireflyOptimizer::new(cycle_config.clone()) {
+            Ok(optimizer) => optimizer.with_clients(clients),
+            Err(err) => {
+                eprintln!("Warning: {}; no plan this cycle", err);
+                continue;
+            }
+        };
+        if let Some(previous) = &plan {
+            // Checked before the first cycle, and every plan holds the configured routers
+            optimizer = optimizer
+                .with_initial_layout(previous.clone())
+                .expect("the search starts from the previous plan");
+        }
+        let solution = match optimizer.try_run() {
+            Ok(solution) => solution,
+            Err(non_finite) => {
+                eprintln!("Warning: cycle {} aborted: {}", cycle, non_finite);
+                continue;
+            }
+        };
+        let published = sink.publish(&solution);
+        if let Some(history) = &mut history {
+            let mut record = CycleRecord::new(cycle, plan.as_deref(), &solution, &cycle_config);
+            record.published = published.is_ok();
+            if let Err(message) = history.push(&record) {
+                eprintln!("Warning: {}", message);
+            }
+        }
+        if let Err(message) = published {
+            eprintln!("Warning: {}; keeping the previous plan", message);
+            continue;
+        }
+        let metrics = &solution.report.metrics;
+        eprintln!(
+            "Cycle {}: {} clients, fitness {}, coverage {:.1}%, routers moved {:.3} on average, {:.1} s",
+            cycle,
+            solution.clients.len(),
+            metrics.fitness,
+            solution.coverage() * 100.0,
+            metrics.relocation_distance.unwrap_or(0.0),
+            start.elapsed().as_secs_f64()
+        );
+        plan = Some(solution.routers);
+    }
+    Ok(())
+}
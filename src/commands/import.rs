@@ -0,0 +1,163 @@
+use clap::Args;
+use ff_wmn::topology::{LinkThreshold, MeshTopology};
+use ff_wmn::{DIMENSIONS, FireflyConfig};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Output of batctl o or iw dev <interface> station dump, one file per node; prefix
+    /// a station dump with the node's MAC address and = unless it includes iw dev info
+    #[arg(required = true, value_name = "[MAC=]FILE")]
+    dumps: Vec<String>,
+    /// CSV of the installed node positions: mac,x,y
+    #[arg(long, value_name = "FILE")]
+    positions: Option<String>,
+    /// Where to save the topology and, with positions, the router layout as JSON
+    #[arg(long, value_name = "PATH", default_value = "topology.json")]
+    output: String,
+    /// Also write a TOML scenario with the router count and the measured radii
+    #[arg(long, value_name = "FILE", requires = "positions")]
+    scenario_out: Option<String>,
+    /// Smallest batman-adv IV link quality, as a fraction of 255, of a usable link
+    #[arg(long, value_name = "FRACTION", default_value_t = LinkThreshold::default().quality)]
+    min_quality: f64,
+    /// Smallest received signal in dBm of a usable link
+    #[arg(long, value_name = "DBM", default_value_t = LinkThreshold::default().signal, allow_hyphen_values = true)]
+    min_signal: f64,
+    /// Radius of routers without a usable link to another positioned node, the
+    /// communication distance by default
+    #[arg(long, value_name = "DISTANCE")]
+    default_radius: Option<f64>,
+}
+
+// Node positions by MAC address, in file order
+fn load_positions(path: &str) -> Result<Vec<(String, [f64; DIMENSIONS])>, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|err| format!("Unable to read {}: {}", path, err))?;
+    let mut positions = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let invalid = || format!("{} line {}: expected mac,x,y", path, number + 1);
+        let [node, x, y] = fields.as_slice() else {
+            return Err(invalid());
+        };
+        match (x.parse(), y.parse()) {
+            (Ok(x), Ok(y)) => positions.push((node.to_ascii_lowercase(), [x, y])),
+            // A header
+            _ if number == 0 => {}
+            _ => return Err(invalid()),
+        }
+    }
+    Ok(positions)
+}
+
+// `import batctl-o.txt 02:..:01=stations.txt --positions nodes.csv`: turn dumps of a
+// live mesh into its router layout and measured radii
+pub fn import_command(args: ImportArgs) {
+    if let Err(message) = import(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn import(args: ImportArgs) -> Result<(), String> {
+    let ImportArgs {
+        dumps,
+        positions,
+        output,
+        scenario_out,
+        min_quality,
+        min_signal,
+        default_radius,
+    } = args;
+    let mut topology = MeshTopology::default();
+    for dump in &dumps {
+        let (node, path) = match dump.split_once('=') {
+            Some((node, path)) => (Some(node), path),
+            None => (None, dump.as_str()),
+        };
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read {}: {}", path, err))?;
+        topology.extend(
+            MeshTopology::parse(&contents, node).map_err(|err| format!("{}: {}", path, err))?,
+        );
+    }
+    println!(
+        "Imported {} links between {} nodes from {} dumps",
+        topology.links.len(),
+        topology.nodes().len(),
+        dumps.len()
+    );
+
+    let threshold = LinkThreshold {
+        quality: min_quality,
+        signal: min_signal,
+    };
+    let links: Vec<_> = topology
+        .links
+        .iter()
+        .map(|link| {
+            json!({
+                "from": link.from,
+                "to": link.to,
+                "quality": link.quality,
+                "throughput": link.throughput,
+                "signal": link.signal,
+                "usable": threshold.usable(link),
+            })
+        })
+        .collect();
+    let mut data = json!({ "links": links });
+
+    let positions = positions.map(|path| load_positions(&path)).transpose()?;
+    if let Some(positions) = positions {
+        let by_node: BTreeMap<String, [f64; DIMENSIONS]> = positions.iter().cloned().collect();
+        let measured = topology.radii(&by_node, &threshold);
+        let default_radius =
+            default_radius.unwrap_or(FireflyConfig::default().max_communication_distance);
+        for (node, _) in positions
+            .iter()
+            .filter(|(node, _)| !measured.contains_key(node))
+        {
+            println!(
+                "Warning: {} has no usable link to a positioned node, radius {} assumed",
+                node, default_radius
+            );
+        }
+        for node in topology.nodes() {
+            if !by_node.contains_key(node) {
+                println!("Warning: {} has no position and is left out", node);
+            }
+        }
+        let radii: Vec<f64> = positions
+            .iter()
+            .map(|(node, _)| measured.get(node).copied().unwrap_or(default_radius))
+            .collect();
+        data["nodes"] = positions.iter().map(|(node, _)| node.as_str()).collect();
+        let layout: Vec<[f64; DIMENSIONS]> =
+            positions.iter().map(|&(_, position)| position).collect();
+        data["mesh_routers"] = json!(layout);
+        data["router_radii"] = json!(radii);
+
+        if let Some(path) = &scenario_out {
+            let scenario = format!(
+                "# Imported from {}\nrouters = {}\n\n[radio]\nradii = {:?}\n",
+                dumps.join(", "),
+                positions.len(),
+                radii
+            );
+            std::fs::write(path, scenario)
+                .map_err(|err| format!("Unable to write {}: {}", path, err))?;
+            println!("Scenario with the measured radii saved to {}", path);
+        }
+    }
+
+    std::fs::write(&output, data.to_string())
+        .map_err(|err| format!("Unable to write {}: {}", output, err))?;
+    println!("Topology saved to {}", output);
+    Ok(())
+}
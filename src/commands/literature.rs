@@ -0,0 +1,203 @@
+use clap::Args;
+use ff_wmn::history::IterationRecord;
+use ff_wmn::{FireflyConfig, FireflyOptimizer, FitnessWeights, Solution};
+use std::fmt::Write as _;
+use std::path::Path;
+
+// Seeded repetitions of the setup, the usual count for comparing metaheuristics
+const RUNS: usize = 30;
+
+// A metric of the summary table and how to read it from a run
+type Column = (&'static str, fn(&Solution) -> f64);
+
+#[derive(Args)]
+pub struct LiteratureArgs {
+    /// Seeded repetitions of the setup
+    #[arg(long, default_value_t = RUNS)]
+    runs: usize,
+    /// Seed of the first repetition; the others follow it
+    #[arg(long, default_value_t = 0)]
+    seed: u64,
+    /// Directory to save the tables and figures in
+    #[arg(long, value_name = "DIR", default_value = "literature")]
+    output_dir: String,
+}
+
+// The WMN-FA setup the defaults come from, spelled out so that changing a default
+// never changes the comparison: 16 routers and 32 clients on a 32×32 area, a
+// communication distance of 4.5 and the 0.8/0.1/0.1 weights of SGC, NCMC and NCMCpR
+fn literature_config(seed: u64) -> FireflyConfig {
+    FireflyConfig::new()
+        .routers(16)
+        .clients(32)
+        .iterations(100)
+        .bounds(0.0, 32.0)
+        .max_communication_distance(4.5)
+        .weights(FitnessWeights {
+            sgc: 0.8,
+            ncmc: 0.1,
+            ncmcpr: 0.1,
+            overlap: 0.0,
+        })
+        .alpha(0.5)
+        .beta0(1.0)
+        .gamma(1.0)
+        .seed(seed)
+        .history_in_report(true)
+}
+
+// Mean, sample standard deviation, minimum and maximum
+fn summary(values: &[f64]) -> [f64; 4] {
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let variance = values
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / values.len().saturating_sub(1).max(1) as f64;
+    [
+        mean,
+        variance.sqrt(),
+        values.iter().copied().fold(f64::INFINITY, f64::min),
+        values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+    ]
+}
+
+// `literature --runs 30`: rerun the setup of the WMN-FA literature with seeded
+// repetitions and save the tables and figures to compare against it
+pub fn literature_command(args: LiteratureArgs) {
+    if let Err(message) = literature(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn literature(args: LiteratureArgs) -> Result<(), String> {
+    let LiteratureArgs {
+        runs,
+        seed,
+        output_dir,
+    } = args;
+    if runs == 0 {
+        return Err("The comparison needs at least one run".to_string());
+    }
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|err| format!("Unable to create {}: {}", output_dir, err))?;
+    let save = |name: &str, contents: &str| {
+        let path = Path::new(&output_dir).join(name);
+        std::fs::write(&path, contents)
+            .map_err(|err| format!("Unable to write {}: {}", path.display(), err))
+    };
+
+    println!(
+        "Running the WMN-FA literature setup {} times from seed {}",
+        runs, seed
+    );
+    let mut solutions: Vec<(u64, Solution)> = Vec::with_capacity(runs);
+    for run in 0..runs as u64 {
+        let config = literature_config(seed + run);
+        let solution = FireflyOptimizer::new(config)
+            .map_err(|err| format!("Invalid configuration: {}", err))?
+            .try_run()
+            .map_err(|err| format!("Aborted: {}", err))?;
+        solutions.push((seed + run, solution));
+    }
+
+    // Table of every run
+    let mut table = "run,seed,fitness,sgc,ncmc,ncmcpr,coverage\n".to_string();
+    for (run, (seed, solution)) in solutions.iter().enumerate() {
+        let metrics = &solution.report.metrics;
+        writeln!(
+            table,
+            "{},{},{},{},{},{},{}",
+            run,
+            seed,
+            metrics.fitness,
+            metrics.sgc,
+            metrics.ncmc,
+            metrics.ncmcpr,
+            solution.coverage()
+        )
+        .unwrap();
+    }
+    save("runs.csv", &table)?;
+
+    // Summary of the runs, as the mean ± standard deviation tables of the literature
+    let columns: [Column; 4] = [
+        ("Fitness", |solution| solution.report.metrics.fitness),
+        ("SGC", |solution| solution.report.metrics.sgc as f64),
+        ("NCMC", |solution| solution.report.metrics.ncmc),
+        ("NCMCpR", |solution| solution.report.metrics.ncmcpr),
+    ];
+    let mut summary_table = "metric,mean,std,min,max\n".to_string();
+    println!(
+        "  {:<8} {:>10} {:>10} {:>10} {:>10}",
+        "Metric", "Mean", "Std", "Min", "Max"
+    );
+    for (name, value) in columns {
+        let values: Vec<f64> = solutions
+            .iter()
+            .map(|(_, solution)| value(solution))
+            .collect();
+        let [mean, std, min, max] = summary(&values);
+        println!(
+            "  {:<8} {:>10.4} {:>10.4} {:>10.4} {:>10.4}",
+            name, mean, std, min, max
+        );
+        writeln!(summary_table, "{},{},{},{},{}", name, mean, std, min, max).unwrap();
+    }
+    save("summary.csv", &summary_table)?;
+
+    // SGC and NCMC of the best layout per iteration, averaged over the runs, for the
+    // convergence figures
+    let histories: Vec<_> = solutions
+        .iter()
+        .filter_map(|(_, solution)| solution.report.history.as_ref())
+        .collect();
+    let iterations = histories
+        .iter()
+        .map(|history| history.len())
+        .min()
+        .unwrap_or(0);
+    let mut convergence = "iteration,sgc,ncmc,best_fitness\n".to_string();
+    for iteration in 0..iterations {
+        let mean = |value: fn(&IterationRecord) -> f64| {
+            histories
+                .iter()
+                .map(|history| value(&history[iteration]))
+                .sum::<f64>()
+                / histories.len() as f64
+        };
+        writeln!(
+            convergence,
+            "{},{},{},{}",
+            iteration,
+            mean(|record| record.sgc as f64),
+            mean(|record| record.ncmc),
+            mean(|record| record.best_fitness)
+        )
+        .unwrap();
+    }
+    save("convergence.csv", &convergence)?;
+
+    // Placement figure of the best run
+    let (best_seed, best) = solutions
+        .iter()
+        .reduce(|best, candidate| {
+            if candidate.1.report.metrics.fitness > best.1.report.metrics.fitness {
+                candidate
+            } else {
+                best
+            }
+        })
+        .expect("at least one run");
+    save("placement.svg", &best.plot_svg())?;
+    println!(
+        "Best run: seed {}, fitness {}, SGC {}, NCMC {}",
+        best_seed, best.report.metrics.fitness, best.report.metrics.sgc, best.report.metrics.ncmc
+    );
+    println!(
+        "Saved runs.csv, summary.csv, convergence.csv and placement.svg to {}",
+        output_dir
+    );
+    Ok(())
+}
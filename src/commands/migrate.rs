@@ -0,0 +1,34 @@
+use clap::Args;
+use ff_wmn::migrate::{SCHEMA_VERSION, migrate};
+use serde_json::Value;
+
+#[derive(Args)]
+pub struct MigrateArgs {
+    /// Result file to upgrade
+    input: String,
+    /// Where to write the upgraded file instead of replacing the input
+    #[arg(long, value_name = "PATH")]
+    output: Option<String>,
+}
+
+// `migrate <input> [--output <path>]`: upgrade a result file, in place unless an output is given
+pub fn migrate_command(args: MigrateArgs) {
+    let MigrateArgs { input, output } = args;
+    let output = output.unwrap_or_else(|| input.clone());
+
+    let contents = std::fs::read_to_string(&input).expect("Unable to read file");
+    let mut data: Value = serde_json::from_str(&contents).expect("Unable to parse result file");
+    match migrate(&mut data) {
+        Ok(version) => {
+            std::fs::write(&output, data.to_string()).expect("Unable to write data");
+            println!(
+                "Migrated {} from schema version {} to {} ({})",
+                input, version, SCHEMA_VERSION, output
+            );
+        }
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,14 @@
+pub mod benchmark;
+pub mod calibrate;
+pub mod consensus;
+pub mod daemon;
+pub mod import;
+pub mod literature;
+pub mod migrate;
+pub mod power;
+pub mod probe;
+pub mod relocate;
+pub mod render;
+pub mod sleep;
+pub mod stats;
+pub mod timeline;
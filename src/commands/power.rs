@@ -0,0 +1,159 @@
+use clap::Args;
+use ff_wmn::power::{PowerPlan, PowerSettings, optimize_power};
+use ff_wmn::solution::routers_from_json;
+use ff_wmn::{ClientSet, FireflyConfig, Scenario};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use serde_json::{Value, json};
+
+#[derive(Args)]
+pub struct PowerArgs {
+    /// Result or imported topology file holding the fixed router positions
+    layout: String,
+    /// Clients to cover, the clients of the layout file by default
+    #[arg(long, value_name = "PATH")]
+    clients_file: Option<String>,
+    /// TOML scenario with the weights, radio model and search parameters
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+    /// Lowest transmit power in dBm
+    #[arg(long, value_name = "DBM", allow_hyphen_values = true)]
+    min_power: Option<f64>,
+    /// Highest transmit power in dBm, the path-loss model's power by default
+    #[arg(long, value_name = "DBM", allow_hyphen_values = true)]
+    max_power: Option<f64>,
+    /// Non-overlapping channels to assign; 1 plans power alone
+    #[arg(long, default_value_t = PowerSettings::default().channels)]
+    channels: usize,
+    /// Fitness penalty per disk area of coverage overlap between routers on one channel
+    #[arg(long, value_name = "WEIGHT", default_value_t = PowerSettings::default().interference_weight)]
+    interference_weight: f64,
+    /// Candidate power plans per iteration
+    #[arg(long, default_value_t = PowerSettings::default().fireflies)]
+    fireflies: usize,
+    /// Seed the random number generator so the plan can be reproduced exactly
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Where to save the power plan
+    #[arg(long, value_name = "PATH", default_value = "power_plan.json")]
+    output: String,
+}
+
+fn print_plan(name: &str, plan: &PowerPlan, clients: &ClientSet) {
+    let mean_power = plan.tx_power.iter().sum::<f64>() / plan.tx_power.len() as f64;
+    let total = clients.total_weight();
+    println!(
+        "  {:<9} {:>5} {:>9.1}% {:>12.3} {:>10.2} {:>12}",
+        name,
+        plan.sgc,
+        if total == 0.0 {
+            100.0
+        } else {
+            plan.ncmc / total * 100.0
+        },
+        plan.interference,
+        mean_power,
+        plan.fitness
+    );
+}
+
+// `power topology.json --channels 3`: keep the routers where they are and plan their
+// transmit power and channels instead
+pub fn power_command(args: PowerArgs) {
+    if let Err(message) = power(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn power(args: PowerArgs) -> Result<(), String> {
+    let PowerArgs {
+        layout,
+        clients_file,
+        scenario,
+        min_power,
+        max_power,
+        channels,
+        interference_weight,
+        fireflies,
+        seed,
+        output,
+    } = args;
+    let mut config = match &scenario {
+        Some(path) => {
+            let scenario = Scenario::load(path)?;
+            let mut config = scenario.clone().apply(FireflyConfig::default());
+            if let Some(calibration) = scenario.calibrate(&mut config)? {
+                println!("Calibrated the path-loss model: {}", calibration);
+            }
+            config
+        }
+        None => FireflyConfig::default(),
+    };
+    if seed.is_some() {
+        config.seed = seed;
+    }
+    config
+        .validate()
+        .map_err(|err| format!("Invalid configuration: {}", err))?;
+    let defaults = PowerSettings::default();
+    let settings = PowerSettings {
+        min_power: min_power.unwrap_or(defaults.min_power),
+        max_power: max_power.unwrap_or(config.radio.path_loss.unwrap_or_default().tx_power),
+        channels,
+        interference_weight,
+        fireflies,
+    };
+    settings
+        .validate()
+        .map_err(|err| format!("Invalid power search: {}", err))?;
+
+    let contents = std::fs::read_to_string(&layout)
+        .map_err(|err| format!("Unable to read {}: {}", layout, err))?;
+    let data: Value = serde_json::from_str(&contents)
+        .map_err(|err| format!("Unable to parse {}: {}", layout, err))?;
+    let routers = routers_from_json(&data).map_err(|err| format!("{}: {}", layout, err))?;
+    if routers.is_empty() {
+        return Err(format!("{} holds no routers", layout));
+    }
+    let clients = match clients_file {
+        Some(path) => ClientSet::load(&path)?,
+        None => ClientSet::from_value(&data)
+            .map_err(|err| format!("{}: {}; give --clients-file", layout, err))?,
+    };
+
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let (baseline, plan) = optimize_power(&config, &routers, &clients, &settings, &mut rng)
+        .map_err(|err| format!("Aborted: {}", err))?;
+
+    println!(
+        "Power plan for {} fixed routers and {} clients:",
+        routers.len(),
+        clients.len()
+    );
+    println!(
+        "  {:<9} {:>5} {:>10} {:>12} {:>10} {:>12}",
+        "", "SGC", "Coverage", "Interference", "Mean dBm", "Fitness"
+    );
+    print_plan("Full", &baseline, &clients);
+    print_plan("Planned", &plan, &clients);
+
+    let data = json!({
+        "mesh_routers": routers,
+        "settings": {
+            "min_power": settings.min_power,
+            "max_power": settings.max_power,
+            "channels": settings.channels,
+            "interference_weight": settings.interference_weight
+        },
+        "baseline": baseline.to_json(),
+        "plan": plan.to_json()
+    });
+    std::fs::write(&output, data.to_string())
+        .map_err(|err| format!("Unable to write {}: {}", output, err))?;
+    println!("Power plan saved to {}", output);
+    Ok(())
+}
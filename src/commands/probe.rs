@@ -0,0 +1,118 @@
+use clap::Args;
+use ff_wmn::solution::points_from_json;
+use ff_wmn::{ClientSet, FireflyConfig, Metrics, Scenario};
+use serde_json::Value;
+
+// Which coordinate of which router to sweep, e.g. `r3.x` for the x coordinate of router 3
+#[derive(Clone)]
+pub struct ProbeDimension {
+    router: usize,
+    axis: usize,
+}
+
+fn parse_dimension(value: &str) -> Result<ProbeDimension, String> {
+    let invalid = || format!("Invalid dimension {}, expected r<router>.<x|y>", value);
+    let (router, axis) = value
+        .strip_prefix('r')
+        .and_then(|rest| rest.split_once('.'))
+        .ok_or_else(invalid)?;
+    let router = router.parse().map_err(|_| invalid())?;
+    let axis = match axis {
+        "x" => 0,
+        "y" => 1,
+        _ => return Err(invalid()),
+    };
+    Ok(ProbeDimension { router, axis })
+}
+
+// Offsets relative to the current coordinate, e.g. `-2..2`
+fn parse_range(value: &str) -> Result<(f64, f64), String> {
+    let invalid = || format!("Invalid range {}, expected <from>..<to>", value);
+    let (from, to) = value.split_once("..").ok_or_else(invalid)?;
+    let from = from.trim().parse().map_err(|_| invalid())?;
+    let to = to.trim().parse().map_err(|_| invalid())?;
+    Ok((from, to))
+}
+
+#[derive(Args)]
+pub struct ProbeArgs {
+    /// Result file holding the layout to probe
+    #[arg(long, value_name = "FILE")]
+    layout: String,
+    /// Coordinate to sweep, e.g. r3.x for the x coordinate of router 3
+    #[arg(long, value_parser = parse_dimension)]
+    dimension: ProbeDimension,
+    /// Offsets relative to the current coordinate
+    #[arg(long, value_name = "FROM..TO", value_parser = parse_range, allow_hyphen_values = true, default_value = "-1..1")]
+    range: (f64, f64),
+    /// Number of evenly spaced offsets to evaluate
+    #[arg(long, default_value_t = 100)]
+    steps: usize,
+    /// TOML scenario the layout was optimized with
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+}
+
+// `probe --layout <result.json> --dimension r3.x --range -2..2 --steps 100`:
+// sweep one router coordinate and print the fitness curve as CSV
+pub fn probe_command(args: ProbeArgs) {
+    if let Err(message) = probe(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn probe(args: ProbeArgs) -> Result<(), String> {
+    let ProbeArgs {
+        layout,
+        dimension,
+        range,
+        steps,
+        scenario,
+    } = args;
+    if steps == 0 {
+        return Err("--steps must be at least 1".to_string());
+    }
+    let config = match scenario {
+        Some(path) => Scenario::load(&path)?.apply(FireflyConfig::default()),
+        None => FireflyConfig::default(),
+    };
+
+    let contents = std::fs::read_to_string(&layout)
+        .map_err(|err| format!("Unable to read {}: {}", layout, err))?;
+    let data: Value = serde_json::from_str(&contents)
+        .map_err(|err| format!("Unable to parse {}: {}", layout, err))?;
+    let mut routers = points_from_json(&data, "mesh_routers")?;
+    let clients = ClientSet::from_value(&data)?;
+    if dimension.router >= routers.len() {
+        return Err(format!(
+            "Router {} does not exist, the layout has {} routers",
+            dimension.router,
+            routers.len()
+        ));
+    }
+
+    let origin = routers[dimension.router][dimension.axis];
+    let (from, to) = range;
+    println!("offset,coordinate,fitness,sgc,ncmc,ncmcpr,overlap");
+    for step in 0..steps {
+        let offset = if steps == 1 {
+            from
+        } else {
+            from + (to - from) * step as f64 / (steps - 1) as f64
+        };
+        routers[dimension.router][dimension.axis] = origin + offset;
+        let metrics = Metrics::evaluate(&routers, &clients, &config, None);
+        println!(
+            "{},{},{},{},{},{},{}",
+            offset,
+            origin + offset,
+            metrics.fitness,
+            metrics.sgc,
+            metrics.ncmc,
+            metrics.ncmcpr,
+            metrics.overlap
+        );
+    }
+    Ok(())
+}
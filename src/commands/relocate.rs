@@ -0,0 +1,136 @@
+use clap::Args;
+use ff_wmn::relocation::relocation_plan;
+use ff_wmn::solution::routers_from_json;
+use ff_wmn::{ClientSet, DIMENSIONS, FireflyConfig, Scenario};
+use serde_json::{Value, json};
+
+#[derive(Args)]
+pub struct RelocateArgs {
+    /// Result or imported topology file holding the installed routers
+    installed: String,
+    /// Result file holding the re-optimized layout to move them to
+    target: String,
+    /// Clients to cover, the clients of the target file by default
+    #[arg(long, value_name = "PATH")]
+    clients_file: Option<String>,
+    /// TOML scenario with the communication distance and radio model
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+    /// Lowest share of the client weight to keep covered during the field work
+    #[arg(long, value_name = "SHARE", default_value_t = 0.9)]
+    coverage_floor: f64,
+    /// Where to save the relocation plan
+    #[arg(long, value_name = "PATH", default_value = "relocation_plan.json")]
+    output: String,
+}
+
+fn read_layout(path: &str) -> Result<(Value, Vec<[f64; DIMENSIONS]>), String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|err| format!("Unable to read {}: {}", path, err))?;
+    let data: Value = serde_json::from_str(&contents)
+        .map_err(|err| format!("Unable to parse {}: {}", path, err))?;
+    let routers = routers_from_json(&data).map_err(|err| format!("{}: {}", path, err))?;
+    Ok((data, routers))
+}
+
+// `relocate installed.json firefly_results.json --coverage-floor 0.8`: the order in
+// which to move installed routers to a re-optimized layout
+pub fn relocate_command(args: RelocateArgs) {
+    if let Err(message) = relocate(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn relocate(args: RelocateArgs) -> Result<(), String> {
+    let RelocateArgs {
+        installed,
+        target,
+        clients_file,
+        scenario,
+        coverage_floor,
+        output,
+    } = args;
+    if !(0.0..=1.0).contains(&coverage_floor) {
+        return Err(format!(
+            "The coverage floor must be a share in [0, 1], got {}",
+            coverage_floor
+        ));
+    }
+    let config = match &scenario {
+        Some(path) => Scenario::load(path)?.apply(FireflyConfig::default()),
+        None => FireflyConfig::default(),
+    };
+    config
+        .validate()
+        .map_err(|err| format!("Invalid configuration: {}", err))?;
+
+    let (_, installed_routers) = read_layout(&installed)?;
+    let (data, target_routers) = read_layout(&target)?;
+    if installed_routers.len() != target_routers.len() {
+        return Err(format!(
+            "{} holds {} routers but {} holds {}",
+            installed,
+            installed_routers.len(),
+            target,
+            target_routers.len()
+        ));
+    }
+    let clients = match clients_file {
+        Some(path) => ClientSet::load(&path)?,
+        None => ClientSet::from_value(&data)
+            .map_err(|err| format!("{}: {}; give --clients-file", target, err))?,
+    };
+
+    let plan = relocation_plan(
+        &config,
+        &installed_routers,
+        &target_routers,
+        &clients,
+        coverage_floor,
+    );
+    println!(
+        "Relocation plan for {} of {} routers, keeping {}% coverage:",
+        plan.moves.len(),
+        installed_routers.len(),
+        coverage_floor * 100.0
+    );
+    println!(
+        "  {:<5} {:>6} {:>17} {:>17} {:>9} {:>9} {:>9}",
+        "Step", "Router", "From", "To", "Distance", "During", "After"
+    );
+    let position = |router: &[f64; DIMENSIONS]| format!("({:.2}, {:.2})", router[0], router[1]);
+    for (step, relocation) in plan.moves.iter().enumerate() {
+        println!(
+            "  {:<5} {:>6} {:>17} {:>17} {:>9.2} {:>8.1}% {:>8.1}%",
+            step + 1,
+            relocation.router,
+            position(&relocation.from),
+            position(&relocation.to),
+            relocation.distance,
+            relocation.coverage_during * 100.0,
+            relocation.coverage_after * 100.0
+        );
+    }
+    println!(
+        "Total distance {:.2}; coverage {:.1}% before, never below {:.1}%",
+        plan.total_distance(),
+        plan.initial_coverage * 100.0,
+        plan.lowest_coverage() * 100.0
+    );
+    if plan.initial_coverage < coverage_floor {
+        println!("Warning: the installed layout covers less than the floor already");
+    } else if !plan.keeps_floor() {
+        println!("Warning: no order of the moves found keeps the coverage floor");
+    }
+
+    let data = json!({
+        "installed": installed_routers,
+        "mesh_routers": target_routers,
+        "plan": plan.to_json()
+    });
+    std::fs::write(&output, data.to_string())
+        .map_err(|err| format!("Unable to write {}: {}", output, err))?;
+    println!("Relocation plan saved to {}", output);
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use clap::Args;
+use ff_wmn::solution::points_from_json;
+use ff_wmn::{ClientSet, FireflyConfig, Plot, Scenario};
+use serde_json::Value;
+
+#[derive(Args)]
+pub struct RenderArgs {
+    /// Result file holding the layout and clients to draw
+    result: String,
+    /// Where to save the picture, as PNG for .png paths and as SVG otherwise
+    #[arg(long, value_name = "PATH")]
+    output: String,
+    /// TOML scenario the layout was optimized with, for the bounds and radio ranges
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+}
+
+// `render <result> --output plot.png`: draw a saved layout with its coverage, links and
+// clients without running the optimizer again
+pub fn render_command(args: RenderArgs) {
+    if let Err(message) = render(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn render(args: RenderArgs) -> Result<(), String> {
+    let RenderArgs {
+        result,
+        output,
+        scenario,
+    } = args;
+    let config = match scenario {
+        Some(path) => Scenario::load(&path)?.apply(FireflyConfig::default()),
+        None => FireflyConfig::default(),
+    };
+    config
+        .validate()
+        .map_err(|err| format!("Invalid configuration: {}", err))?;
+    let contents = std::fs::read_to_string(&result)
+        .map_err(|err| format!("Unable to read {}: {}", result, err))?;
+    let data: Value = serde_json::from_str(&contents)
+        .map_err(|err| format!("Unable to parse {}: {}", result, err))?;
+    let routers = points_from_json(&data, "mesh_routers")?;
+    let clients = ClientSet::from_value(&data)?;
+
+    let plot = Plot::new(&routers, &clients, &config);
+    plot.save(&output)
+        .map_err(|err| format!("Unable to write {}: {}", output, err))?;
+    println!(
+        "Plot of {} routers and {} clients saved to {}",
+        routers.len(),
+        clients.len(),
+        output
+    );
+    Ok(())
+}
@@ -0,0 +1,119 @@
+use clap::Args;
+use ff_wmn::sleep::sleep_schedule;
+use ff_wmn::solution::routers_from_json;
+use ff_wmn::{ClientSet, FireflyConfig, Scenario};
+use serde_json::{Value, json};
+
+#[derive(Args)]
+pub struct SleepArgs {
+    /// Result or imported topology file holding the placed routers
+    layout: String,
+    /// Clients to cover, the clients of the layout file by default
+    #[arg(long, value_name = "PATH")]
+    clients_file: Option<String>,
+    /// TOML scenario with the communication distance and radio model
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+    /// Lowest share of the client weight the awake routers must cover
+    #[arg(long, value_name = "SHARE", default_value_t = 0.9)]
+    coverage_floor: f64,
+    /// Where to save the sleep schedule
+    #[arg(long, value_name = "PATH", default_value = "sleep_schedule.json")]
+    output: String,
+}
+
+// `sleep firefly_results.json --coverage-floor 0.8`: which routers of a placed layout
+// can sleep through low demand, and in what order
+pub fn sleep_command(args: SleepArgs) {
+    if let Err(message) = sleep(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn sleep(args: SleepArgs) -> Result<(), String> {
+    let SleepArgs {
+        layout,
+        clients_file,
+        scenario,
+        coverage_floor,
+        output,
+    } = args;
+    if !(0.0..=1.0).contains(&coverage_floor) {
+        return Err(format!(
+            "The coverage floor must be a share in [0, 1], got {}",
+            coverage_floor
+        ));
+    }
+    let config = match &scenario {
+        Some(path) => Scenario::load(path)?.apply(FireflyConfig::default()),
+        None => FireflyConfig::default(),
+    };
+    config
+        .validate()
+        .map_err(|err| format!("Invalid configuration: {}", err))?;
+
+    let contents = std::fs::read_to_string(&layout)
+        .map_err(|err| format!("Unable to read {}: {}", layout, err))?;
+    let data: Value = serde_json::from_str(&contents)
+        .map_err(|err| format!("Unable to parse {}: {}", layout, err))?;
+    let routers = routers_from_json(&data).map_err(|err| format!("{}: {}", layout, err))?;
+    if routers.is_empty() {
+        return Err(format!("{} holds no routers", layout));
+    }
+    let clients = match clients_file {
+        Some(path) => ClientSet::load(&path)?,
+        None => ClientSet::from_value(&data)
+            .map_err(|err| format!("{}: {}; give --clients-file", layout, err))?,
+    };
+
+    let schedule = sleep_schedule(&config, &routers, &clients, coverage_floor);
+    println!(
+        "Sleep schedule for {} routers and {} clients, keeping {}% coverage:",
+        routers.len(),
+        clients.len(),
+        coverage_floor * 100.0
+    );
+    println!(
+        "  {:<6} {:>6} {:>6} {:>5} {:>9}",
+        "Step", "Router", "Awake", "SGC", "Coverage"
+    );
+    println!(
+        "  {:<6} {:>6} {:>6} {:>5} {:>8.1}%",
+        "-",
+        "-",
+        routers.len(),
+        schedule.full_sgc,
+        schedule.full_coverage * 100.0
+    );
+    for (step, sleeping) in schedule.steps.iter().enumerate() {
+        println!(
+            "  {:<6} {:>6} {:>6} {:>5} {:>8.1}%",
+            step + 1,
+            sleeping.router,
+            sleeping.awake,
+            sleeping.sgc,
+            sleeping.coverage * 100.0
+        );
+    }
+    if schedule.full_coverage < coverage_floor {
+        println!("The layout covers less than the floor with every router awake");
+    } else if schedule.steps.is_empty() {
+        println!("No router can sleep without breaking connectivity or the coverage floor");
+    } else {
+        println!(
+            "{} of {} routers can sleep",
+            schedule.steps.len(),
+            routers.len()
+        );
+    }
+
+    let data = json!({
+        "mesh_routers": routers,
+        "schedule": schedule.to_json()
+    });
+    std::fs::write(&output, data.to_string())
+        .map_err(|err| format!("Unable to write {}: {}", output, err))?;
+    println!("Sleep schedule saved to {}", output);
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use clap::Args;
+use ff_wmn::{ClientSet, DIMENSIONS, FireflyConfig, Scenario, distance};
+
+// Value at quantile `q` of an ascending sorted slice
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    sorted[((sorted.len() - 1) as f64 * q).round() as usize]
+}
+
+// Distance from every client to its nearest other client
+fn nearest_neighbor_distances(clients: &[[f64; DIMENSIONS]]) -> Vec<f64> {
+    clients
+        .iter()
+        .enumerate()
+        .map(|(i, client)| {
+            clients
+                .iter()
+                .enumerate()
+                .filter(|&(j, _)| j != i)
+                .map(|(_, other)| distance(client, other))
+                .fold(f64::INFINITY, f64::min)
+        })
+        .collect()
+}
+
+// Clients per grid cell, using cells as wide as the communication distance
+fn cell_counts(clients: &[[f64; DIMENSIONS]], config: &FireflyConfig) -> Vec<usize> {
+    let width = config.max_communication_distance;
+    let cells = (config.extent() / width).ceil() as usize;
+    let cell = |coord: f64| (((coord - config.lower_bound) / width) as usize).min(cells - 1);
+    let mut counts = vec![0; cells * cells];
+    for client in clients {
+        counts[cell(client[1]) * cells + cell(client[0])] += 1;
+    }
+    counts
+}
+
+// Routers needed to cover every client when greedily placing each router on the client
+// position that covers the most uncovered clients; an upper bound on the true minimum
+fn greedy_router_bound(clients: &[[f64; DIMENSIONS]], radius: f64) -> usize {
+    let mut covered = vec![false; clients.len()];
+    let mut routers = 0;
+    while covered.iter().any(|&covered| !covered) {
+        let best_site = clients
+            .iter()
+            .max_by_key(|site| {
+                clients
+                    .iter()
+                    .zip(&covered)
+                    .filter(|&(client, &covered)| !covered && distance(*site, client) <= radius)
+                    .count()
+            })
+            .expect("uncovered clients exist");
+        for (client, covered) in clients.iter().zip(covered.iter_mut()) {
+            if distance(best_site, client) <= radius {
+                *covered = true;
+            }
+        }
+        routers += 1;
+    }
+    routers
+}
+
+#[derive(Args)]
+pub struct StatsArgs {
+    /// Client file (CSV, GeoJSON or JSON), such as a result file
+    path: String,
+    /// TOML scenario providing the deployment area and communication distance
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+}
+
+// `stats <clients>`: summarize the mesh clients of a scenario before optimizing it
+pub fn stats_command(args: StatsArgs) {
+    let StatsArgs { path, scenario } = args;
+    let config = match scenario {
+        Some(scenario) => Scenario::load(&scenario)
+            .unwrap_or_else(|message| {
+                eprintln!("{}", message);
+                std::process::exit(2);
+            })
+            .apply(FireflyConfig::default()),
+        None => FireflyConfig::default(),
+    };
+    let client_set = ClientSet::load(&path).unwrap_or_else(|message| {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    });
+    let clients = &client_set.positions;
+    if clients.is_empty() {
+        eprintln!("{} has no mesh clients", path);
+        std::process::exit(2);
+    }
+
+    let area = config.extent().powi(DIMENSIONS as i32);
+    println!(
+        "Area: {} ({}..{} per axis)",
+        area, config.lower_bound, config.upper_bound
+    );
+    println!("Mesh clients: {}", clients.len());
+    if client_set.is_weighted() {
+        println!("Total client weight: {}", client_set.total_weight());
+    }
+    println!(
+        "Client density: {:.4} per unit area",
+        clients.len() as f64 / area
+    );
+
+    let counts = cell_counts(clients, &config);
+    let empty = counts.iter().filter(|&&count| count == 0).count();
+    println!(
+        "Clients per {0}x{0} cell: min {1}, max {2}, empty cells {3} of {4}",
+        config.max_communication_distance,
+        counts.iter().min().unwrap(),
+        counts.iter().max().unwrap(),
+        empty,
+        counts.len()
+    );
+
+    if clients.len() > 1 {
+        let mut nearest = nearest_neighbor_distances(clients);
+        nearest.sort_by(f64::total_cmp);
+        println!(
+            "Nearest-neighbor distance: min {:.3}, p25 {:.3}, median {:.3}, p75 {:.3}, max {:.3}, mean {:.3}",
+            nearest[0],
+            quantile(&nearest, 0.25),
+            quantile(&nearest, 0.5),
+            quantile(&nearest, 0.75),
+            nearest[nearest.len() - 1],
+            nearest.iter().sum::<f64>() / nearest.len() as f64
+        );
+    }
+
+    println!(
+        "Routers for full coverage (greedy bound): {}",
+        greedy_router_bound(clients, config.max_communication_distance)
+    );
+}
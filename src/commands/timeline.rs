@@ -0,0 +1,142 @@
+use clap::Args;
+use ff_wmn::heuristics::apply_heuristics;
+use ff_wmn::solution::routers_from_json;
+use ff_wmn::timeline::{Timeline, TimelinePhase};
+use ff_wmn::{FireflyConfig, Scenario};
+use serde_json::{Value, json};
+
+#[derive(Args)]
+pub struct TimelineArgs {
+    /// TOML file listing the events and the iteration each happens at
+    script: String,
+    /// TOML scenario with the deployment, clients and search parameters
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+    /// Result or imported topology file with the layout to start from
+    #[arg(long, value_name = "FILE")]
+    initial_layout: Option<String>,
+    /// Seed the random number generator so the timeline can be reproduced exactly
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Where to save the timeline report
+    #[arg(long, value_name = "PATH", default_value = "timeline.json")]
+    output: String,
+}
+
+fn print_phase(phase: &TimelinePhase) {
+    let impact = match &phase.impact {
+        Some(impact) => format!(
+            "{:>8.1}% {:>4}",
+            impact.coverage() * 100.0,
+            impact.metrics.sgc
+        ),
+        None => format!("{:>9} {:>4}", "-", "-"),
+    };
+    println!(
+        "  {:>5}..{:<5} {:>7} {:>7} {} {:>8.1}% {:>4} {:>10.4}  {}",
+        phase.start,
+        phase.end,
+        phase.active.len(),
+        phase.clients,
+        impact,
+        phase.result.coverage() * 100.0,
+        phase.result.metrics.sgc,
+        phase.result.metrics.fitness,
+        phase
+            .events
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+}
+
+// `timeline events.toml --scenario site.toml`: optimize while clients come and go,
+// routers fail and walls go up, and report how the layout adapts to every event
+pub fn timeline_command(args: TimelineArgs) {
+    if let Err(message) = timeline(args) {
+        eprintln!("{}", message);
+        std::process::exit(2);
+    }
+}
+
+fn timeline(args: TimelineArgs) -> Result<(), String> {
+    let TimelineArgs {
+        script,
+        scenario,
+        initial_layout,
+        seed,
+        output,
+    } = args;
+    let scenario = match &scenario {
+        Some(path) => Scenario::load(path)?,
+        None => Scenario::default(),
+    };
+    let clients = scenario.load_clients()?;
+    let mut config = scenario.clone().apply(FireflyConfig::default());
+    if let Some(calibration) = scenario.calibrate(&mut config)? {
+        println!("Calibrated the path-loss model: {}", calibration);
+    }
+    for choice in apply_heuristics(&mut config, &scenario) {
+        println!("Heuristic {}", choice);
+    }
+    if seed.is_some() {
+        config.seed = seed;
+    }
+    if let Some(clients) = &clients {
+        config.clients = clients.len();
+    }
+    config
+        .validate()
+        .map_err(|err| format!("Invalid configuration: {}", err))?;
+    let timeline = Timeline::load(&script)?;
+    timeline
+        .validate(&config)
+        .map_err(|err| format!("Invalid timeline {}: {}", script, err))?;
+
+    let initial = match &initial_layout {
+        Some(path) => {
+            let contents = std::fs::read_to_string(path)
+                .map_err(|err| format!("Unable to read {}: {}", path, err))?;
+            let data: Value = serde_json::from_str(&contents)
+                .map_err(|err| format!("Unable to parse {}: {}", path, err))?;
+            let layout = routers_from_json(&data).map_err(|err| format!("{}: {}", path, err))?;
+            if layout.len() != config.routers {
+                return Err(format!(
+                    "{} holds {} routers, not the configured {}",
+                    path,
+                    layout.len(),
+                    config.routers
+                ));
+            }
+            Some(layout)
+        }
+        None => None,
+    };
+
+    let phases = timeline
+        .run(&config, clients, initial)
+        .map_err(|err| format!("Aborted: {}", err))?;
+    println!(
+        "Timeline of {} events over {} iterations:",
+        timeline.events.len(),
+        phases.last().map_or(0, |phase| phase.end)
+    );
+    println!(
+        "  {:<12} {:>7} {:>7} {:>9} {:>4} {:>9} {:>4} {:>10}  Events",
+        "Iterations", "Routers", "Clients", "Impact", "SGC", "Coverage", "SGC", "Fitness"
+    );
+    for phase in &phases {
+        print_phase(phase);
+    }
+
+    let data = json!({
+        "timeline": script,
+        "seed": config.seed,
+        "phases": phases.iter().map(TimelinePhase::to_json).collect::<Vec<_>>()
+    });
+    std::fs::write(&output, data.to_string())
+        .map_err(|err| format!("Unable to write {}: {}", output, err))?;
+    println!("Timeline saved to {}", output);
+    Ok(())
+}
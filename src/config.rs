@@ -0,0 +1,711 @@
+use crate::brightness::BrightnessTransform;
+use crate::fixed_point::MAX_UNITS;
+use crate::goal::Goal;
+use crate::history::HistoryConfig;
+use crate::multiresolution::Multiresolution;
+use crate::pareto::ArchivePruning;
+use crate::radio::RadioModel;
+use crate::rate::RateModel;
+use crate::relocation::Relocation;
+use crate::robustness::Robustness;
+use crate::schedule::{AlphaSchedule, EarlyStopping};
+use crate::solution::RunInfo;
+use crate::termination::Termination;
+use crate::tiling::Tiling;
+use crate::{DIMENSIONS, NonFinitePolicy, TieBreak};
+use std::fmt;
+use std::time::Duration;
+
+/// Weights of the fitness components
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FitnessWeights {
+    pub sgc: f64,
+    pub ncmc: f64,
+    pub ncmcpr: f64,
+    /// Penalty per disk area of coverage overlap, zero to ignore overlap
+    pub overlap: f64,
+}
+
+impl Default for FitnessWeights {
+    fn default() -> FitnessWeights {
+        FitnessWeights {
+            sgc: 0.8,
+            ncmc: 0.1,
+            ncmcpr: 0.1,
+            overlap: 0.0,
+        }
+    }
+}
+
+impl FitnessWeights {
+    /// Weighted sum of the fitness components, minus the overlap penalty
+    pub fn fitness(&self, sgc: f64, ncmc: f64, ncmcpr: f64, overlap: f64) -> f64 {
+        (self.sgc * sgc) + (self.ncmc * ncmc) + (self.ncmcpr * ncmcpr) - (self.overlap * overlap)
+    }
+}
+
+/// How the fireflies map onto router layouts
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SearchMode {
+    /// Every router is a firefly attracted to the other routers of one layout
+    #[default]
+    RouterSwarm,
+    /// Every firefly is a whole layout, moved by the shared [`FireflyCore`](crate::FireflyCore)
+    Population { fireflies: usize },
+}
+
+/// A configuration value outside its valid range
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConfigError(pub(crate) String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Parameters of an optimization run.
+///
+/// Start from [`FireflyConfig::new`], which holds the defaults of the
+/// original 16-router, 32-client, 32×32 scenario, and chain the setters to
+/// change them. The values are checked when the config is handed to
+/// [`FireflyOptimizer::new`](crate::FireflyOptimizer::new).
+#[derive(Clone, Debug)]
+pub struct FireflyConfig {
+    pub routers: usize,
+    pub clients: usize,
+    pub iterations: usize,
+    /// Random step size per dimension
+    pub alpha: [f64; DIMENSIONS],
+    /// How alpha decays over the iterations
+    pub alpha_schedule: AlphaSchedule,
+    /// Spare the router covering the most client weight from the random step; the
+    /// population search never moves its brightest layout anyway
+    pub elitism: bool,
+    /// Stop before the last iteration once the best fitness stops improving
+    pub early_stopping: Option<EarlyStopping>,
+    /// Stop before the last iteration once this rule holds for the best layout
+    pub termination: Option<Termination>,
+    /// Evaluate the layouts every this many iterations and in the last one; in between
+    /// they move by their last known fitness
+    pub evaluation_interval: usize,
+    /// Attractiveness at distance zero
+    pub beta0: f64,
+    /// Light absorption coefficient
+    pub gamma: f64,
+    /// Scale the attraction between the layouts of a population search by their light,
+    /// see [`BrightnessTransform`]; None attracts by distance alone
+    pub brightness: Option<BrightnessTransform>,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+    pub max_communication_distance: f64,
+    pub weights: FitnessWeights,
+    /// Search toward target SGC and coverage shares instead of maximizing the weighted sum
+    pub goal: Option<Goal>,
+    /// Per-router radii, minimum router separation and path-loss coverage
+    pub radio: RadioModel,
+    /// Penalize moving routers away from an installed layout
+    pub relocation: Option<Relocation>,
+    /// Report the data rate every covered client gets from its best router
+    pub rates: Option<RateModel>,
+    /// Score layouts against random router failures instead of intact
+    pub robustness: Option<Robustness>,
+    /// Which layout stays best when fitness values tie
+    pub tie_break: TieBreak,
+    /// Estimate SGC from a sample of seed routers during the search
+    pub approx_metrics: bool,
+    /// Damp moves into the boundary instead of clamping them
+    pub soft_bounds: bool,
+    /// Snap the final router coordinates to multiples of this precision
+    pub snap: Option<f64>,
+    /// Move the routers of the router swarm on a grid of this precision with integer
+    /// arithmetic, for seeded runs that are bit-identical across platforms
+    pub fixed_point: Option<f64>,
+    /// Lower the SGC fidelity whenever an iteration takes longer than this
+    pub iteration_budget: Option<Duration>,
+    /// Fitness-sharing radius as a fraction of the domain diagonal
+    pub sharing_radius: Option<f64>,
+    pub search: SearchMode,
+    /// Rank the layouts of a population search by Pareto dominance over SGC, NCMC and
+    /// the penalized coverage overlap, separation violations and relocation, instead of
+    /// by the weighted fitness
+    pub pareto: bool,
+    /// Layouts kept on the Pareto front
+    pub pareto_archive: usize,
+    /// How the Pareto front is thinned out before it reaches `pareto_archive` layouts
+    pub pareto_pruning: ArchivePruning,
+    /// Optimize tiles of the area separately before a stitching pass over the whole area
+    pub tiling: Option<Tiling>,
+    /// Solve down-scaled versions of the problem first and refine their layout
+    pub multiresolution: Option<Multiresolution>,
+    /// Repair or abort when a firefly's position or fitness turns NaN or infinite
+    pub non_finite: NonFinitePolicy,
+    /// Seed of the random number generator, for runs that can be reproduced exactly
+    pub seed: Option<u64>,
+    /// Per-iteration convergence statistics to record
+    pub history: HistoryConfig,
+    pub run: RunInfo,
+}
+
+impl Default for FireflyConfig {
+    fn default() -> FireflyConfig {
+        FireflyConfig {
+            routers: 16,
+            clients: 32,
+            iterations: 100,
+            alpha: [0.5; DIMENSIONS],
+            alpha_schedule: AlphaSchedule::default(),
+            elitism: false,
+            early_stopping: None,
+            termination: None,
+            evaluation_interval: 1,
+            beta0: 1.0,
+            gamma: 1.0,
+            brightness: None,
+            lower_bound: 0.0,
+            upper_bound: 32.0,
+            max_communication_distance: 4.5,
+            weights: FitnessWeights::default(),
+            goal: None,
+            radio: RadioModel::default(),
+            relocation: None,
+            rates: None,
+            robustness: None,
+            tie_break: TieBreak::default(),
+            approx_metrics: false,
+            soft_bounds: false,
+            snap: None,
+            fixed_point: None,
+            iteration_budget: None,
+            sharing_radius: None,
+            search: SearchMode::default(),
+            pareto: false,
+            pareto_archive: crate::pareto::ARCHIVE_SIZE,
+            pareto_pruning: ArchivePruning::default(),
+            tiling: None,
+            multiresolution: None,
+            non_finite: NonFinitePolicy::default(),
+            seed: None,
+            history: HistoryConfig::default(),
+            run: RunInfo::default(),
+        }
+    }
+}
+
+impl FireflyConfig {
+    pub fn new() -> FireflyConfig {
+        FireflyConfig::default()
+    }
+
+    pub fn routers(mut self, routers: usize) -> FireflyConfig {
+        self.routers = routers;
+        self
+    }
+
+    pub fn clients(mut self, clients: usize) -> FireflyConfig {
+        self.clients = clients;
+        self
+    }
+
+    pub fn iterations(mut self, iterations: usize) -> FireflyConfig {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Same random step size in every dimension
+    pub fn alpha(mut self, alpha: f64) -> FireflyConfig {
+        self.alpha = [alpha; DIMENSIONS];
+        self
+    }
+
+    /// Random step size per dimension, for anisotropic deployment areas
+    pub fn alpha_per_dimension(mut self, alpha: [f64; DIMENSIONS]) -> FireflyConfig {
+        self.alpha = alpha;
+        self
+    }
+
+    pub fn alpha_schedule(mut self, schedule: AlphaSchedule) -> FireflyConfig {
+        self.alpha_schedule = schedule;
+        self
+    }
+
+    pub fn elitism(mut self, elitism: bool) -> FireflyConfig {
+        self.elitism = elitism;
+        self
+    }
+
+    /// Stop once the best fitness has not improved by more than `tolerance` for
+    /// `patience` iterations
+    pub fn early_stopping(mut self, patience: usize, tolerance: f64) -> FireflyConfig {
+        self.early_stopping = Some(EarlyStopping {
+            patience,
+            tolerance,
+        });
+        self
+    }
+
+    /// Stop once `termination` holds, see [`Termination`]
+    pub fn termination(mut self, termination: Termination) -> FireflyConfig {
+        self.termination = Some(termination);
+        self
+    }
+
+    /// Move the fireflies `interval` iterations between evaluations, trading accuracy for
+    /// throughput on expensive objectives; early stopping and stopping rules only see
+    /// the evaluated iterations
+    pub fn evaluation_interval(mut self, interval: usize) -> FireflyConfig {
+        self.evaluation_interval = interval;
+        self
+    }
+
+    pub fn beta0(mut self, beta0: f64) -> FireflyConfig {
+        self.beta0 = beta0;
+        self
+    }
+
+    pub fn gamma(mut self, gamma: f64) -> FireflyConfig {
+        self.gamma = gamma;
+        self
+    }
+
+    /// Attract the layouts of a population search in proportion to their light, see
+    /// [`BrightnessTransform`]
+    pub fn brightness(mut self, transform: BrightnessTransform) -> FireflyConfig {
+        self.brightness = Some(transform);
+        self
+    }
+
+    /// Lower and upper bound of every coordinate
+    pub fn bounds(mut self, lower_bound: f64, upper_bound: f64) -> FireflyConfig {
+        self.lower_bound = lower_bound;
+        self.upper_bound = upper_bound;
+        self
+    }
+
+    pub fn max_communication_distance(mut self, distance: f64) -> FireflyConfig {
+        self.max_communication_distance = distance;
+        self
+    }
+
+    pub fn weights(mut self, weights: FitnessWeights) -> FireflyConfig {
+        self.weights = weights;
+        self
+    }
+
+    pub fn radio(mut self, radio: RadioModel) -> FireflyConfig {
+        self.radio = radio;
+        self
+    }
+
+    /// Lower the fitness by `penalty` per unit of mean router displacement from `anchor`
+    pub fn relocation(mut self, anchor: Vec<[f64; DIMENSIONS]>, penalty: f64) -> FireflyConfig {
+        self.relocation = Some(Relocation { anchor, penalty });
+        self
+    }
+
+    /// Report client data rates from a rate-vs-distance model, see [`RateModel`]
+    pub fn rates(mut self, rates: RateModel) -> FireflyConfig {
+        self.rates = Some(rates);
+        self
+    }
+
+    /// Minimize the distance to target SGC and coverage shares, see [`Goal`]
+    pub fn goal(mut self, goal: Goal) -> FireflyConfig {
+        self.goal = Some(goal);
+        self
+    }
+
+    /// Score layouts against router failures during the search
+    pub fn robustness(mut self, robustness: Robustness) -> FireflyConfig {
+        self.robustness = Some(robustness);
+        self
+    }
+
+    pub fn tie_break(mut self, tie_break: TieBreak) -> FireflyConfig {
+        self.tie_break = tie_break;
+        self
+    }
+
+    pub fn approx_metrics(mut self, approx_metrics: bool) -> FireflyConfig {
+        self.approx_metrics = approx_metrics;
+        self
+    }
+
+    pub fn soft_bounds(mut self, soft_bounds: bool) -> FireflyConfig {
+        self.soft_bounds = soft_bounds;
+        self
+    }
+
+    pub fn snap(mut self, precision: f64) -> FireflyConfig {
+        self.snap = Some(precision);
+        self
+    }
+
+    /// Keep router positions on a grid of `precision` during the search, see
+    /// [`FixedPoint`](crate::fixed_point::FixedPoint)
+    pub fn fixed_point(mut self, precision: f64) -> FireflyConfig {
+        self.fixed_point = Some(precision);
+        self
+    }
+
+    pub fn iteration_budget(mut self, budget: Duration) -> FireflyConfig {
+        self.iteration_budget = Some(budget);
+        self
+    }
+
+    pub fn sharing_radius(mut self, fraction: f64) -> FireflyConfig {
+        self.sharing_radius = Some(fraction);
+        self
+    }
+
+    /// Search a population of whole layouts instead of a swarm of routers
+    pub fn population(mut self, fireflies: usize) -> FireflyConfig {
+        self.search = SearchMode::Population { fireflies };
+        self
+    }
+
+    /// Return the Pareto front of a population search instead of a single weighted optimum
+    pub fn pareto(mut self, pareto: bool) -> FireflyConfig {
+        self.pareto = pareto;
+        self
+    }
+
+    pub fn pareto_archive(mut self, layouts: usize) -> FireflyConfig {
+        self.pareto_archive = layouts;
+        self
+    }
+
+    pub fn pareto_pruning(mut self, pruning: ArchivePruning) -> FireflyConfig {
+        self.pareto_pruning = pruning;
+        self
+    }
+
+    /// Split the area into `tiles` × `tiles` tiles optimized in parallel, then stitch
+    /// their layouts together for `stitch_iterations` iterations
+    pub fn tiling(mut self, tiles: usize, stitch_iterations: usize) -> FireflyConfig {
+        self.tiling = Some(Tiling {
+            tiles,
+            stitch_iterations,
+        });
+        self
+    }
+
+    /// Start from the layouts of coarser problems, see [`Multiresolution`]
+    pub fn multiresolution(mut self, multiresolution: Multiresolution) -> FireflyConfig {
+        self.multiresolution = Some(multiresolution);
+        self
+    }
+
+    pub fn non_finite(mut self, policy: NonFinitePolicy) -> FireflyConfig {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Make the run reproducible: the same seed and configuration give the same result
+    pub fn seed(mut self, seed: u64) -> FireflyConfig {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Stream the convergence history to a CSV (`.csv`) or JSON Lines file
+    pub fn history_file(mut self, path: &str) -> FireflyConfig {
+        self.history.file = Some(path.to_string());
+        self
+    }
+
+    /// Append the convergence history to the result file
+    pub fn history_in_report(mut self, in_report: bool) -> FireflyConfig {
+        self.history.in_report = in_report;
+        self
+    }
+
+    /// Record the router positions of every iteration in the history, not only its statistics
+    pub fn history_positions(mut self, positions: bool) -> FireflyConfig {
+        self.history.positions = positions;
+        self
+    }
+
+    /// Record the alpha, beta and gamma every iteration applied in the history, after the
+    /// alpha schedule and niching
+    pub fn history_parameters(mut self, parameters: bool) -> FireflyConfig {
+        self.history.parameters = parameters;
+        self
+    }
+
+    pub fn name(mut self, name: &str) -> FireflyConfig {
+        self.run.name = Some(name.to_string());
+        self
+    }
+
+    pub fn tag(mut self, key: &str, value: &str) -> FireflyConfig {
+        self.run.tags.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Width of the deployment area along every axis
+    pub fn extent(&self) -> f64 {
+        self.upper_bound - self.lower_bound
+    }
+
+    /// Whether the search can start from a given layout, which only the router swarm
+    /// without tiling does
+    pub fn starts_from_layout(&self) -> bool {
+        self.search == SearchMode::RouterSwarm && self.tiling.is_none()
+    }
+
+    /// Length of the deployment area's diagonal
+    pub fn domain_diagonal(&self) -> f64 {
+        self.extent() * (DIMENSIONS as f64).sqrt()
+    }
+
+    /// The part of the fitness scoring the service of a layout, before the separation
+    /// and relocation penalties: the weighted sum of SGC, NCMC and NCMCpR or, with a
+    /// goal, the negated achievement of the SGC and `coverage` shares, minus the
+    /// overlap penalty
+    pub fn service_fitness(
+        &self,
+        sgc: f64,
+        ncmc: f64,
+        ncmcpr: f64,
+        coverage: f64,
+        overlap: f64,
+    ) -> f64 {
+        match &self.goal {
+            Some(goal) => {
+                -goal.achievement(sgc / self.routers as f64, coverage)
+                    - self.weights.overlap * overlap
+            }
+            None => self.weights.fitness(sgc, ncmc, ncmcpr, overlap),
+        }
+    }
+
+    /// Check that every value is in its valid range
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let error = |message: String| Err(ConfigError(message));
+        // Written so that NaN fails both checks
+        let positive = |value: f64| value > 0.0;
+        let non_negative = |value: f64| value >= 0.0;
+        if self.routers == 0 {
+            return error("at least one router is required".to_string());
+        }
+        if !(self.lower_bound.is_finite() && self.upper_bound.is_finite())
+            || self.lower_bound >= self.upper_bound
+        {
+            return error(format!(
+                "invalid bounds {}..{}",
+                self.lower_bound, self.upper_bound
+            ));
+        }
+        if !self.alpha.iter().all(|&alpha| non_negative(alpha)) {
+            return error(format!("alpha must not be negative, got {:?}", self.alpha));
+        }
+        if !self.alpha_schedule.is_valid() {
+            return error(format!(
+                "the alpha schedule {} needs a parameter in [0, 1]",
+                self.alpha_schedule
+            ));
+        }
+        if let Some(stopping) = self.early_stopping {
+            if stopping.patience == 0 {
+                return error("early stopping needs a patience of at least one".to_string());
+            }
+            if !non_negative(stopping.tolerance) {
+                return error("the early-stopping tolerance must not be negative".to_string());
+            }
+        }
+        if let Some(termination) = &self.termination {
+            if let Err(message) = termination.validate() {
+                return error(message);
+            }
+            if self.pareto {
+                return error(
+                    "a stopping rule needs a single best layout; stop a Pareto search by patience"
+                        .to_string(),
+                );
+            }
+            if self.seed.is_some() && termination.has_time_limit() {
+                return error(
+                    "a time limit depends on wall-clock time and cannot be combined with a seed"
+                        .to_string(),
+                );
+            }
+        }
+        if self.evaluation_interval == 0 {
+            return error("the evaluation interval must be at least one iteration".to_string());
+        }
+        if self.evaluation_interval > 1 && self.pareto {
+            return error("a Pareto search evaluates every iteration".to_string());
+        }
+        if !non_negative(self.weights.overlap) {
+            return error("the overlap penalty must not be negative".to_string());
+        }
+        if !non_negative(self.beta0) || !non_negative(self.gamma) {
+            return error("beta0 and gamma must not be negative".to_string());
+        }
+        if !positive(self.max_communication_distance) {
+            return error("the maximum communication distance must be positive".to_string());
+        }
+        if !self.radio.radii.iter().all(|&radius| positive(radius)) {
+            return error("router radii must be positive".to_string());
+        }
+        if !non_negative(self.radio.min_separation) || !non_negative(self.radio.separation_penalty)
+        {
+            return error(
+                "the minimum separation and its penalty must not be negative".to_string(),
+            );
+        }
+        if let Some(path_loss) = &self.radio.path_loss {
+            if !(path_loss.tx_power.is_finite()
+                && path_loss.reference_loss.is_finite()
+                && path_loss.sensitivity.is_finite())
+            {
+                return error("path-loss powers and losses must be finite".to_string());
+            }
+            if !positive(path_loss.exponent) {
+                return error("the path-loss exponent must be positive".to_string());
+            }
+        }
+        if !self
+            .radio
+            .walls
+            .iter()
+            .all(|wall| wall.from.iter().chain(&wall.to).all(|c| c.is_finite()))
+        {
+            return error("wall ends must be finite".to_string());
+        }
+        if let Some(relocation) = &self.relocation {
+            if relocation.anchor.len() != self.routers {
+                return error(format!(
+                    "the relocation anchor has {} routers, expected {}",
+                    relocation.anchor.len(),
+                    self.routers
+                ));
+            }
+            if !relocation
+                .anchor
+                .as_flattened()
+                .iter()
+                .all(|coord| coord.is_finite())
+            {
+                return error("the relocation anchor must have finite coordinates".to_string());
+            }
+            if !(non_negative(relocation.penalty) && relocation.penalty.is_finite()) {
+                return error("the relocation penalty must not be negative".to_string());
+            }
+        }
+        if !self.snap.is_none_or(positive) {
+            return error("the snap precision must be positive".to_string());
+        }
+        if let Some(precision) = self.fixed_point {
+            if !(precision > 0.0 && precision.is_finite()) {
+                return error("the fixed-point precision must be positive".to_string());
+            }
+            if self.lower_bound.abs().max(self.upper_bound.abs()) / precision > MAX_UNITS {
+                return error(format!(
+                    "a fixed-point precision of {} is too fine for the bounds",
+                    precision
+                ));
+            }
+            // The bounds are rounded inward to whole units
+            if (self.lower_bound / precision).ceil() > (self.upper_bound / precision).floor() {
+                return error(format!(
+                    "a fixed-point precision of {} leaves no position between the bounds {} and {}",
+                    precision, self.lower_bound, self.upper_bound
+                ));
+            }
+            if self.search != SearchMode::RouterSwarm {
+                return error("fixed-point positions need the router swarm".to_string());
+            }
+        }
+        if let Some(Err(message)) = self.robustness.map(|robustness| robustness.validate()) {
+            return error(message);
+        }
+        if let Some(goal) = self.goal {
+            if let Err(message) = goal.validate() {
+                return error(message);
+            }
+            if self.pareto {
+                return error("a goal and a Pareto front are alternatives".to_string());
+            }
+        }
+        if !self.sharing_radius.is_none_or(positive) {
+            return error("the sharing radius must be positive".to_string());
+        }
+        if self.seed.is_some() && self.iteration_budget.is_some() {
+            return error(
+                "an iteration budget depends on wall-clock time and cannot be combined with a seed"
+                    .to_string(),
+            );
+        }
+        if self.brightness.is_some() {
+            if self.search == SearchMode::RouterSwarm {
+                return error(
+                    "a brightness transformation needs a population of layouts".to_string(),
+                );
+            }
+            if self.pareto {
+                return error(
+                    "a Pareto search ranks layouts by dominance, not by brightness".to_string(),
+                );
+            }
+        }
+        if self.pareto {
+            if self.search == SearchMode::RouterSwarm {
+                return error("a Pareto search needs a population of layouts".to_string());
+            }
+            if self.pareto_archive == 0 {
+                return error("the Pareto front must keep at least one layout".to_string());
+            }
+            if let Err(message) = self.pareto_pruning.validate() {
+                return error(message);
+            }
+        }
+        if let Some(tiling) = self.tiling {
+            if tiling.tiles == 0 {
+                return error("tiling needs at least one tile per axis".to_string());
+            }
+            if self.search != SearchMode::RouterSwarm {
+                return error("tiling needs the router swarm".to_string());
+            }
+        }
+        if let Some(multiresolution) = self.multiresolution {
+            if multiresolution.levels == 0 {
+                return error("multiresolution needs at least one coarse level".to_string());
+            }
+            if !(multiresolution.client_fraction > 0.0 && multiresolution.client_fraction <= 1.0) {
+                return error(format!(
+                    "the coarse client fraction must be in (0, 1], got {}",
+                    multiresolution.client_fraction
+                ));
+            }
+            if !(multiresolution.radius_factor >= 1.0 && multiresolution.radius_factor.is_finite())
+            {
+                return error(format!(
+                    "the coarse radius factor must be at least 1, got {}",
+                    multiresolution.radius_factor
+                ));
+            }
+            if self.search != SearchMode::RouterSwarm || self.tiling.is_some() {
+                return error(
+                    "multiresolution needs the router swarm and cannot be combined with tiling"
+                        .to_string(),
+                );
+            }
+        }
+        if let SearchMode::Population { fireflies } = self.search {
+            if fireflies < 2 {
+                return error("population search needs at least two fireflies".to_string());
+            }
+            if self.soft_bounds || self.iteration_budget.is_some() || self.sharing_radius.is_some()
+            {
+                return error(
+                    "soft bounds, iteration budgets and fitness sharing need the router swarm"
+                        .to_string(),
+                );
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use crate::{DIMENSIONS, distance, layout_distance, match_layouts};
+
+// Rounds of re-matching every layout against the consensus before giving up on convergence
+const MAX_REFINEMENTS: usize = 10;
+
+/// One router of a consensus layout
+#[derive(Clone, Debug)]
+pub struct ConsensusRouter {
+    /// Medoid of the routers matched to this placement across runs
+    pub position: [f64; DIMENSIONS],
+    /// Fraction of runs that placed a router within the tolerance of `position`
+    pub stability: f64,
+    /// Mean distance from the matched routers to `position`
+    pub spread: f64,
+    /// The router every run matched to this placement, in run order
+    pub members: Vec<[f64; DIMENSIONS]>,
+}
+
+/// Placement agreed on by several runs of the same scenario
+#[derive(Clone, Debug)]
+pub struct Consensus {
+    pub routers: Vec<ConsensusRouter>,
+    /// Run whose layout seeded the clustering, the one closest to all others
+    pub reference: usize,
+}
+
+impl Consensus {
+    /// Positions of the consensus routers
+    pub fn layout(&self) -> Vec<[f64; DIMENSIONS]> {
+        self.routers.iter().map(|router| router.position).collect()
+    }
+
+    /// Mean stability over all routers
+    pub fn stability(&self) -> f64 {
+        if self.routers.is_empty() {
+            return 1.0;
+        }
+        self.routers
+            .iter()
+            .map(|router| router.stability)
+            .sum::<f64>()
+            / self.routers.len() as f64
+    }
+}
+
+/// Cluster the matched routers of several layouts and take each cluster's medoid.
+///
+/// Routers are interchangeable, so every layout is matched router by router against
+/// the current consensus, starting from the layout closest to all others, and the
+/// medoids are recomputed until the matching stops changing. A router counts as stable
+/// in a run when that run's matched router lies within `tolerance` of the medoid.
+///
+/// # Panics
+///
+/// When `layouts` is empty or the layouts have different router counts.
+pub fn consensus(layouts: &[Vec<[f64; DIMENSIONS]>], tolerance: f64) -> Consensus {
+    assert!(!layouts.is_empty(), "consensus needs at least one layout");
+    let reference = (0..layouts.len())
+        .min_by(|&i, &j| {
+            let total = |i: usize| -> f64 {
+                layouts
+                    .iter()
+                    .map(|other| layout_distance(&layouts[i], other))
+                    .sum()
+            };
+            total(i).total_cmp(&total(j))
+        })
+        .expect("layouts are not empty");
+
+    let mut center = layouts[reference].clone();
+    let mut clusters = Vec::new();
+    for _ in 0..MAX_REFINEMENTS {
+        // clusters[k][run] is the router of `run` matched to consensus router `k`
+        let mut next = vec![Vec::with_capacity(layouts.len()); center.len()];
+        for layout in layouts {
+            let matching = match_layouts(&center, layout);
+            for (cluster, &router) in next.iter_mut().zip(&matching.assignment) {
+                cluster.push(layout[router]);
+            }
+        }
+        let converged = next == clusters;
+        clusters = next;
+        center = clusters.iter().map(|cluster| medoid(cluster)).collect();
+        if converged {
+            break;
+        }
+    }
+
+    let routers = center
+        .into_iter()
+        .zip(clusters)
+        .map(|(position, members)| {
+            let distances: Vec<f64> = members
+                .iter()
+                .map(|member| distance(member, &position))
+                .collect();
+            ConsensusRouter {
+                position,
+                stability: distances.iter().filter(|&&d| d <= tolerance).count() as f64
+                    / members.len() as f64,
+                spread: distances.iter().sum::<f64>() / members.len() as f64,
+                members,
+            }
+        })
+        .collect();
+    Consensus { routers, reference }
+}
+
+// Member with the smallest total distance to all others
+fn medoid(points: &[[f64; DIMENSIONS]]) -> [f64; DIMENSIONS] {
+    let total = |point: &[f64; DIMENSIONS]| -> f64 {
+        points.iter().map(|other| distance(point, other)).sum()
+    };
+    *points
+        .iter()
+        .min_by(|a, b| total(a).total_cmp(&total(b)))
+        .expect("clusters are not empty")
+}
@@ -0,0 +1,60 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Magic bytes and format version at the start of every evaluation log
+const MAGIC: &[u8; 4] = b"FFEV";
+const FORMAT_VERSION: u32 = 1;
+
+/// Compact binary log of every evaluated layout and its fitness.
+///
+/// Layout (all little-endian): the magic bytes, format version, router count and
+/// dimensions as u32, then one record per evaluation holding the fitness followed
+/// by every router coordinate, all as f64.
+pub struct EvaluationLog {
+    writer: BufWriter<File>,
+    routers: usize,
+    dimensions: usize,
+    records: usize,
+}
+
+impl EvaluationLog {
+    pub fn create(path: &str, routers: usize, dimensions: usize) -> std::io::Result<EvaluationLog> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        for value in [FORMAT_VERSION, routers as u32, dimensions as u32] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        Ok(EvaluationLog {
+            writer,
+            routers,
+            dimensions,
+            records: 0,
+        })
+    }
+
+    pub fn record<const D: usize>(&mut self, layout: &[[f64; D]], fitness: f64) {
+        assert_eq!(
+            layout.len(),
+            self.routers,
+            "layout size changed during the run"
+        );
+        assert_eq!(
+            D, self.dimensions,
+            "layout dimensions changed during the run"
+        );
+        self.writer
+            .write_all(&fitness.to_le_bytes())
+            .expect("Unable to write evaluation log");
+        for coord in layout.iter().flatten() {
+            self.writer
+                .write_all(&coord.to_le_bytes())
+                .expect("Unable to write evaluation log");
+        }
+        self.records += 1;
+    }
+
+    pub fn finish(mut self) -> usize {
+        self.writer.flush().expect("Unable to write evaluation log");
+        self.records
+    }
+}
@@ -0,0 +1,488 @@
+use crate::brightness::BrightnessTransform;
+use crate::objective::Objective;
+use crate::sanity::{NonFinite, NonFinitePolicy};
+use crate::schedule::{AlphaSchedule, EarlyStopping, Stall};
+use crate::{TieBreak, distance};
+use rand::Rng;
+use std::ops::ControlFlow;
+
+/// The canonical population-based firefly algorithm over any [`Objective`].
+///
+/// Every firefly is a complete solution whose brightness is its objective value;
+/// each firefly moves toward every brighter one, attracted less the farther away it is.
+/// The brightest firefly has nothing to move toward, so it is never perturbed.
+///
+/// ```
+/// use ff_wmn::FireflyCore;
+/// use ff_wmn::objective::Sphere;
+///
+/// let result = FireflyCore::default()
+///     .optimize(&mut Sphere::new(2), &mut rand::thread_rng())
+///     .unwrap();
+/// assert!(result.best_value < 1.0);
+/// ```
+#[derive(Clone, Debug)]
+pub struct FireflyCore {
+    pub fireflies: usize,
+    pub iterations: usize,
+    /// Random step size, cycled over the dimensions: one value for all dimensions,
+    /// or one per dimension of a repeating group such as the axes of a router
+    pub alpha: Vec<f64>,
+    /// Attractiveness at distance zero
+    pub beta0: f64,
+    /// Light absorption coefficient
+    pub gamma: f64,
+    /// Scale every attraction by the light of the brighter firefly, perceived through
+    /// this transformation as the iteration starts; None attracts by distance alone
+    pub brightness: Option<BrightnessTransform>,
+    pub tie_break: TieBreak,
+    /// What to do with fireflies whose position or value is not finite
+    pub non_finite: NonFinitePolicy,
+    /// How alpha changes over the iterations
+    pub alpha_schedule: AlphaSchedule,
+    /// Stop before `iterations` once the best value stalls
+    pub early_stopping: Option<EarlyStopping>,
+    /// Evaluate the fireflies every this many iterations and in the last one; in
+    /// between they move by their last known values. Early stopping counts the
+    /// evaluated iterations only.
+    pub evaluation_interval: usize,
+}
+
+impl Default for FireflyCore {
+    fn default() -> FireflyCore {
+        FireflyCore {
+            fireflies: 20,
+            iterations: 100,
+            alpha: vec![0.5],
+            beta0: 1.0,
+            gamma: 1.0,
+            brightness: None,
+            tie_break: TieBreak::default(),
+            non_finite: NonFinitePolicy::default(),
+            alpha_schedule: AlphaSchedule::default(),
+            early_stopping: None,
+            evaluation_interval: 1,
+        }
+    }
+}
+
+/// Best solution found by the firefly core
+#[derive(Clone, Debug)]
+pub struct CoreResult {
+    pub best: Vec<f64>,
+    pub best_value: f64,
+    pub evaluations: usize,
+    /// Iterations run, fewer than configured when stopped early
+    pub iterations: usize,
+    /// Average fraction of fireflies per iteration whose move crossed a bound
+    pub bound_hit_fraction: f64,
+    /// Fireflies re-randomized because their position or value was not finite
+    pub repairs: usize,
+    /// Iterations that moved the fireflies without evaluating them
+    pub movement_only_iterations: usize,
+}
+
+/// The population after one iteration, as seen by the observer of
+/// [`FireflyCore::optimize_with`]
+pub struct IterationState<'a> {
+    pub iteration: usize,
+    /// Whether the fireflies were evaluated, or moved by their last known values
+    pub evaluated: bool,
+    pub fireflies: &'a [Vec<f64>],
+    /// Objective value of every firefly
+    pub values: &'a [f64],
+    /// Best solution found so far
+    pub best: &'a [f64],
+    pub best_value: f64,
+    /// Movement equation the iteration applied
+    pub movement: &'a MovementParameters,
+}
+
+/// The movement equation as one iteration applied it, for relating the parameters to
+/// the convergence
+#[derive(Clone, Debug, PartialEq)]
+pub struct MovementParameters {
+    /// Random step size per dimension after the alpha schedule, cycled over the
+    /// dimensions like [`FireflyCore::alpha`]
+    pub alpha: Vec<f64>,
+    pub beta0: f64,
+    pub gamma: f64,
+    /// Mean attractiveness of the iteration's moves, None when no firefly moved
+    pub mean_beta: Option<f64>,
+}
+
+impl MovementParameters {
+    pub(crate) fn new(
+        alpha: &[f64],
+        alpha_scale: f64,
+        beta0: f64,
+        gamma: f64,
+        betas: &[f64],
+    ) -> MovementParameters {
+        MovementParameters {
+            alpha: alpha.iter().map(|alpha| alpha * alpha_scale).collect(),
+            beta0,
+            gamma,
+            mean_beta: (!betas.is_empty()).then(|| betas.iter().sum::<f64>() / betas.len() as f64),
+        }
+    }
+}
+
+// A firefly's move toward another
+pub(crate) struct Attraction {
+    pub(crate) beta: f64,
+    pub(crate) hit_bound: bool,
+}
+
+/// A new best solution, as seen by the `on_new_best` hook of
+/// [`FireflyCore::optimize_hooked`]
+pub struct Improvement<'a> {
+    /// Iteration the solution was found in, None for the initial population
+    pub iteration: Option<usize>,
+    pub best: &'a [f64],
+    pub best_value: f64,
+}
+
+impl FireflyCore {
+    /// Search the objective, failing only when a firefly turns non-finite under
+    /// [`NonFinitePolicy::Abort`]
+    pub fn optimize<O: Objective + ?Sized>(
+        &self,
+        objective: &mut O,
+        rng: &mut impl Rng,
+    ) -> Result<CoreResult, NonFinite> {
+        self.optimize_with(objective, rng, |_| {})
+    }
+
+    /// Like [`optimize`](FireflyCore::optimize), calling `observer` after every iteration
+    pub fn optimize_with<O: Objective + ?Sized>(
+        &self,
+        objective: &mut O,
+        rng: &mut impl Rng,
+        observer: impl FnMut(IterationState),
+    ) -> Result<CoreResult, NonFinite> {
+        self.optimize_from(objective, Vec::new(), rng, observer)
+    }
+
+    /// Like [`optimize_with`](FireflyCore::optimize_with), starting from the fireflies
+    /// of `start` instead of random ones; random fireflies make up the rest of the
+    /// population and extra ones are dropped
+    ///
+    /// # Panics
+    ///
+    /// When a firefly of `start` does not have the objective's dimensions
+    pub fn optimize_from<O: Objective + ?Sized>(
+        &self,
+        objective: &mut O,
+        start: Vec<Vec<f64>>,
+        rng: &mut impl Rng,
+        observer: impl FnMut(IterationState),
+    ) -> Result<CoreResult, NonFinite> {
+        let mut observer = observer;
+        let observer = |state: IterationState| {
+            observer(state);
+            ControlFlow::Continue(())
+        };
+        self.optimize_hooked(objective, start, rng, observer, |_| {})
+    }
+
+    /// Like [`optimize_from`](FireflyCore::optimize_from), also calling `on_new_best`
+    /// as soon as a solution better than every earlier one is found, the best of the
+    /// starting population included, and stopping once `observer` breaks
+    pub fn optimize_hooked<O: Objective + ?Sized>(
+        &self,
+        objective: &mut O,
+        start: Vec<Vec<f64>>,
+        rng: &mut impl Rng,
+        mut observer: impl FnMut(IterationState) -> ControlFlow<()>,
+        mut on_new_best: impl FnMut(Improvement),
+    ) -> Result<CoreResult, NonFinite> {
+        let dimensions = objective.dimensions();
+        let bounds: Vec<(f64, f64)> = (0..dimensions).map(|d| objective.bounds(d)).collect();
+        let direction = objective.direction();
+
+        let mut fireflies: Vec<Vec<f64>> = start.into_iter().take(self.fireflies).collect();
+        assert!(
+            fireflies.iter().all(|firefly| firefly.len() == dimensions),
+            "every starting firefly needs {} dimensions",
+            dimensions
+        );
+        // Initialize the remaining fireflies randomly
+        while fireflies.len() < self.fireflies {
+            fireflies.push(random_position(&bounds, rng));
+        }
+        let mut brightness: Vec<f64> = fireflies
+            .iter()
+            .map(|firefly| direction.brightness(objective.evaluate(firefly)))
+            .collect();
+        let mut evaluations = fireflies.len();
+        let repairs = self.check_finite(None, objective, &mut fireflies, &mut brightness, rng)?;
+        evaluations += repairs.len();
+        let mut repair_count = repairs.len();
+
+        let mut best_index = 0;
+        for i in 1..fireflies.len() {
+            if self
+                .tie_break
+                .prefers(brightness[i], brightness[best_index])
+            {
+                best_index = i;
+            }
+        }
+        let mut best = fireflies[best_index].clone();
+        let mut best_brightness = brightness[best_index];
+        on_new_best(Improvement {
+            iteration: None,
+            best: &best,
+            best_value: direction.brightness(best_brightness),
+        });
+        let mut bound_hit_fraction_sum = 0.0;
+        let mut stall = Stall::new(self.early_stopping, best_brightness);
+        let mut iterations = 0;
+        let mut movement_only_iterations = 0;
+
+        for iteration in 0..self.iterations {
+            iterations += 1;
+            let evaluated = evaluates(self.evaluation_interval, iteration, self.iterations);
+            if !evaluated {
+                movement_only_iterations += 1;
+            }
+            let alpha_scale = self.alpha_schedule.scale(iteration, self.iterations);
+            // Sort fireflies from brightest to dimmest, NaN last
+            let mut indices: Vec<usize> = (0..fireflies.len()).collect();
+            indices.sort_by(|&i, &j| crate::compare_fitness(brightness[j], brightness[i]));
+            fireflies = indices.iter().map(|&i| fireflies[i].clone()).collect();
+            brightness = indices.iter().map(|&i| brightness[i]).collect();
+            let light = self
+                .brightness
+                .map(|transform| transform.light(&brightness));
+
+            let mut hit_bounds = vec![false; fireflies.len()];
+            let mut betas = Vec::new();
+            for i in 0..fireflies.len() {
+                for j in 0..fireflies.len() {
+                    if crate::compare_fitness(brightness[j], brightness[i]).is_gt() {
+                        let target = objective
+                            .align(&fireflies[i], &fireflies[j])
+                            .unwrap_or_else(|| fireflies[j].clone());
+                        let attraction = self.attract(
+                            &mut fireflies[i],
+                            &target,
+                            &bounds,
+                            alpha_scale,
+                            light.as_ref().map_or(1.0, |light| light[j]),
+                            rng,
+                        );
+                        hit_bounds[i] |= attraction.hit_bound;
+                        betas.push(attraction.beta);
+                        if !evaluated {
+                            continue;
+                        }
+                        brightness[i] = direction.brightness(objective.evaluate(&fireflies[i]));
+                        evaluations += 1;
+
+                        if self.tie_break.prefers(brightness[i], best_brightness) {
+                            best_brightness = brightness[i];
+                            best = fireflies[i].clone();
+                            on_new_best(Improvement {
+                                iteration: Some(iteration),
+                                best: &best,
+                                best_value: direction.brightness(best_brightness),
+                            });
+                        }
+                    }
+                }
+            }
+            bound_hit_fraction_sum +=
+                hit_bounds.iter().filter(|&&hit| hit).count() as f64 / fireflies.len() as f64;
+
+            let repairs = self.check_finite(
+                Some(iteration),
+                objective,
+                &mut fireflies,
+                &mut brightness,
+                rng,
+            )?;
+            evaluations += repairs.len();
+            repair_count += repairs.len();
+            for &i in &repairs {
+                if self.tie_break.prefers(brightness[i], best_brightness) {
+                    best_brightness = brightness[i];
+                    best = fireflies[i].clone();
+                    on_new_best(Improvement {
+                        iteration: Some(iteration),
+                        best: &best,
+                        best_value: direction.brightness(best_brightness),
+                    });
+                }
+            }
+
+            let values: Vec<f64> = brightness
+                .iter()
+                .map(|&brightness| direction.brightness(brightness))
+                .collect();
+            let control = observer(IterationState {
+                iteration,
+                evaluated,
+                fireflies: &fireflies,
+                values: &values,
+                best: &best,
+                best_value: direction.brightness(best_brightness),
+                movement: &self.movement(alpha_scale, &betas),
+            });
+            if (evaluated && stall.update(best_brightness)) || control.is_break() {
+                break;
+            }
+        }
+
+        Ok(CoreResult {
+            best,
+            best_value: direction.brightness(best_brightness),
+            evaluations,
+            iterations,
+            bound_hit_fraction: bound_hit_fraction_sum / iterations.max(1) as f64,
+            repairs: repair_count,
+            movement_only_iterations,
+        })
+    }
+
+    // Movement parameters of an iteration whose moves applied `betas`
+    pub(crate) fn movement(&self, alpha_scale: f64, betas: &[f64]) -> MovementParameters {
+        MovementParameters::new(&self.alpha, alpha_scale, self.beta0, self.gamma, betas)
+    }
+
+    // Move `firefly` toward `target`, attracted in proportion to the target's `light`,
+    // plus a random step of `alpha_scale` times alpha, clamped to the bounds
+    pub(crate) fn attract(
+        &self,
+        firefly: &mut [f64],
+        target: &[f64],
+        bounds: &[(f64, f64)],
+        alpha_scale: f64,
+        light: f64,
+        rng: &mut impl Rng,
+    ) -> Attraction {
+        let r = distance(firefly, target);
+        let beta = self.beta0 * light * (-self.gamma * r * r).exp();
+        let mut hit_bound = false;
+        for (d, (coord, target_coord)) in firefly.iter_mut().zip(target).enumerate() {
+            let alpha = self.alpha[d % self.alpha.len()] * alpha_scale;
+            let step = beta * (target_coord - *coord) + alpha * (rng.r#gen::<f64>() - 0.5);
+            let (lower, upper) = bounds[d];
+            if !(lower..=upper).contains(&(*coord + step)) {
+                hit_bound = true;
+            }
+            *coord = (*coord + step).clamp(lower, upper);
+        }
+        Attraction { beta, hit_bound }
+    }
+
+    // Re-randomize or report fireflies whose position or value is not finite,
+    // returning the indices of the re-randomized ones
+    fn check_finite<O: Objective + ?Sized>(
+        &self,
+        iteration: Option<usize>,
+        objective: &mut O,
+        fireflies: &mut [Vec<f64>],
+        brightness: &mut [f64],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<usize>, NonFinite> {
+        let direction = objective.direction();
+        let mut repairs = Vec::new();
+        for i in 0..fireflies.len() {
+            let value = direction.brightness(brightness[i]);
+            if let Some(non_finite) = NonFinite::detect(iteration, Some(i), &fireflies[i], value) {
+                if self.non_finite == NonFinitePolicy::Abort {
+                    return Err(non_finite);
+                }
+                eprintln!("Warning: {}, re-randomizing it", non_finite);
+                let bounds: Vec<(f64, f64)> = (0..objective.dimensions())
+                    .map(|d| objective.bounds(d))
+                    .collect();
+                fireflies[i] = random_position(&bounds, rng);
+                brightness[i] = direction.brightness(objective.evaluate(&fireflies[i]));
+                repairs.push(i);
+            }
+        }
+        Ok(repairs)
+    }
+}
+
+pub(crate) fn random_position(bounds: &[(f64, f64)], rng: &mut impl Rng) -> Vec<f64> {
+    bounds
+        .iter()
+        .map(|&(lower, upper)| rng.gen_range(lower..upper))
+        .collect()
+}
+
+// Whether `iteration` of `iterations` evaluates its fireflies when evaluating every
+// `interval` iterations
+pub(crate) fn evaluates(interval: usize, iteration: usize, iterations: usize) -> bool {
+    (iteration + 1).is_multiple_of(interval) || iteration + 1 == iterations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::brightness::BrightnessTransform;
+    use crate::objective::{Direction, Sphere};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    // The sphere function with its values multiplied by `scale`
+    struct ScaledSphere {
+        sphere: Sphere,
+        scale: f64,
+    }
+
+    impl Objective for ScaledSphere {
+        fn dimensions(&self) -> usize {
+            self.sphere.dimensions()
+        }
+
+        fn bounds(&self, dimension: usize) -> (f64, f64) {
+            self.sphere.bounds(dimension)
+        }
+
+        fn direction(&self) -> Direction {
+            self.sphere.direction()
+        }
+
+        fn evaluate(&mut self, x: &[f64]) -> f64 {
+            self.scale * self.sphere.evaluate(x)
+        }
+    }
+
+    // Fireflies after a seeded search of the sphere scaled by `scale`
+    fn fireflies(transform: BrightnessTransform, scale: f64) -> Vec<Vec<f64>> {
+        let core = FireflyCore {
+            iterations: 20,
+            gamma: 0.01,
+            brightness: Some(transform),
+            ..FireflyCore::default()
+        };
+        let mut objective = ScaledSphere {
+            sphere: Sphere::new(3),
+            scale,
+        };
+        let mut fireflies = Vec::new();
+        core.optimize_with(&mut objective, &mut StdRng::seed_from_u64(7), |state| {
+            fireflies = state.fireflies.to_vec()
+        })
+        .unwrap();
+        fireflies
+    }
+
+    #[test]
+    fn rank_attraction_ignores_the_fitness_scale() {
+        // A power of two scales every value exactly, so the order of the fireflies holds
+        assert_eq!(
+            fireflies(BrightnessTransform::Rank, 1.0),
+            fireflies(BrightnessTransform::Rank, 1024.0)
+        );
+        assert_ne!(
+            fireflies(BrightnessTransform::Identity, 1.0),
+            fireflies(BrightnessTransform::Identity, 1024.0)
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use crate::DIMENSIONS;
+
+// Attractiveness is applied as a binary fraction with this many bits
+const BETA_BITS: u32 = 32;
+
+/// Largest coordinate, in units of the precision, that a fixed-point search can hold
+/// exactly in a floating-point coordinate
+pub const MAX_UNITS: f64 = (1u64 << 52) as f64;
+
+/// Router coordinates kept as integer multiples of a precision during the search.
+///
+/// The router swarm moves routers by whole units with integer arithmetic, and the
+/// floating-point operations left in a move are correctly rounded IEEE operations:
+/// the attractiveness uses [`portable_exp`] instead of the platform's exponential.
+/// Seeded runs therefore find the same layout on every OS and CPU as long as the
+/// fitness only compares distances; the overlap penalty, the path-loss model,
+/// objective plugins and the geometric alpha schedule, whose `powi` has no specified
+/// precision, call the platform's math library.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FixedPoint {
+    precision: f64,
+    // The bounds in units, rounded inward
+    lower: i64,
+    upper: i64,
+}
+
+impl FixedPoint {
+    pub fn new(precision: f64, lower_bound: f64, upper_bound: f64) -> FixedPoint {
+        FixedPoint {
+            precision,
+            lower: (lower_bound / precision).ceil() as i64,
+            upper: (upper_bound / precision).floor() as i64,
+        }
+    }
+
+    pub fn precision(&self) -> f64 {
+        self.precision
+    }
+
+    // Nearest whole unit of a coordinate within the bounds
+    fn units(&self, coord: f64) -> i64 {
+        ((coord / self.precision).round() as i64).clamp(self.lower, self.upper)
+    }
+
+    fn coord(&self, units: i64) -> f64 {
+        units as f64 * self.precision
+    }
+
+    /// The nearest router position on the grid within the bounds
+    pub fn quantize(&self, router: &[f64; DIMENSIONS]) -> [f64; DIMENSIONS] {
+        router.map(|coord| self.coord(self.units(coord)))
+    }
+
+    /// Squared distance between two routers on the grid, summed exactly in units
+    pub fn squared_distance(&self, a: &[f64; DIMENSIONS], b: &[f64; DIMENSIONS]) -> f64 {
+        let units: i128 = a
+            .iter()
+            .zip(b)
+            .map(|(&a, &b)| (self.units(a) - self.units(b)) as i128)
+            .map(|difference| difference * difference)
+            .sum();
+        units as f64 * self.precision * self.precision
+    }
+
+    /// Coordinate `coord` moved `beta` of the way toward `target` plus `randomness`,
+    /// in whole units. A move crossing a bound ends on it, or with `damping` moves that
+    /// share of the way to it.
+    pub fn step(
+        &self,
+        coord: f64,
+        target: f64,
+        beta: f64,
+        randomness: f64,
+        damping: Option<f64>,
+    ) -> f64 {
+        let (coord, target) = (self.units(coord), self.units(target));
+        let beta = (beta * (1u64 << BETA_BITS) as f64).round() as i128;
+        // Rounded to the nearest unit, halves upward
+        let attraction = (beta * (target - coord) as i128 + (1 << (BETA_BITS - 1))) >> BETA_BITS;
+        let moved = coord + attraction as i64 + (randomness / self.precision).round() as i64;
+        let bounded = |bound: i64| match damping {
+            Some(damping) => coord + ((bound - coord) as f64 * damping).round() as i64,
+            None => bound,
+        };
+        self.coord(if moved > self.upper {
+            bounded(self.upper)
+        } else if moved < self.lower {
+            bounded(self.lower)
+        } else {
+            moved
+        })
+    }
+}
+
+/// `e^x` from correctly rounded IEEE operations only, so that every platform computes
+/// the same bits; the standard library's `exp` may differ in the last bit.
+///
+/// ```
+/// use ff_wmn::fixed_point::portable_exp;
+///
+/// for x in [-700.0, -20.0, -1.0, -0.1, 0.0, 0.5, 3.0] {
+///     assert!((portable_exp(x) - f64::exp(x)).abs() <= 1e-14 * f64::exp(x));
+/// }
+/// assert_eq!(portable_exp(f64::NEG_INFINITY), 0.0);
+/// ```
+pub fn portable_exp(x: f64) -> f64 {
+    // ln 2 split so that k · LN_2_HIGH is exact for the k in range
+    const LN_2_HIGH: f64 = f64::from_bits(0x3fe6_2e42_fee0_0000);
+    const LN_2_LOW: f64 = f64::from_bits(0x3dea_39ef_3579_3c76);
+    if x.is_nan() {
+        return x;
+    }
+    if x > 709.8 {
+        return f64::INFINITY;
+    }
+    if x < -745.2 {
+        return 0.0;
+    }
+    // e^x = 2^k · e^r with |r| ≤ ln 2 / 2, where the Taylor series converges quickly
+    let k = (x * std::f64::consts::LOG2_E).round();
+    let r = (x - k * LN_2_HIGH) - k * LN_2_LOW;
+    let mut sum = 1.0;
+    let mut term = 1.0;
+    for n in 1..=16 {
+        term *= r / n as f64;
+        sum += term;
+    }
+    let k = k as i64;
+    // Scaled in two steps where 2^k itself is outside the normal range
+    if k < -1022 {
+        sum * power_of_two(-1022) * power_of_two(k + 1022)
+    } else if k > 1023 {
+        sum * power_of_two(1023) * power_of_two(k - 1023)
+    } else {
+        sum * power_of_two(k)
+    }
+}
+
+// 2^k for a k in the normal exponent range
+fn power_of_two(k: i64) -> f64 {
+    f64::from_bits(((k + 1023) as u64) << 52)
+}
@@ -0,0 +1,87 @@
+use serde_json::json;
+
+/// Target shares of a reference-point search, replacing the weighted sum of SGC, NCMC
+/// and NCMCpR in the fitness.
+///
+/// The fitness becomes the negated achievement scalarizing function of the targets:
+/// the largest shortfall from a target, plus [`Goal::AUGMENTATION`] times the sum of
+/// the shortfalls so that layouts past the goal keep improving. A layout meeting every
+/// target scores at least zero. The overlap, separation and relocation penalties still
+/// apply on top.
+///
+/// ```
+/// use ff_wmn::Goal;
+///
+/// let goal = Goal { sgc: Some(1.0), coverage: Some(0.95) };
+/// assert!(goal.shortfall(1.0, 0.9) > 0.0);
+/// assert_eq!(goal.shortfall(1.0, 0.97), 0.0);
+/// assert!(goal.achievement(1.0, 0.97) < goal.achievement(1.0, 0.96));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Goal {
+    /// Share of the routers in the giant component
+    pub sgc: Option<f64>,
+    /// Share of the client weight covered
+    pub coverage: Option<f64>,
+}
+
+impl Goal {
+    /// Weight of the summed shortfalls next to the largest one
+    pub const AUGMENTATION: f64 = 0.01;
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.sgc.is_none() && self.coverage.is_none() {
+            return Err("a goal needs an SGC or coverage target".to_string());
+        }
+        for (name, target) in [("SGC", self.sgc), ("coverage", self.coverage)] {
+            if let Some(target) = target
+                && !(0.0..=1.0).contains(&target)
+            {
+                return Err(format!(
+                    "the {} target must be a share in [0, 1], got {}",
+                    name, target
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    // How far each target is from being met, negative once exceeded
+    fn shortfalls(&self, sgc: f64, coverage: f64) -> impl Iterator<Item = f64> {
+        [(self.sgc, sgc), (self.coverage, coverage)]
+            .into_iter()
+            .filter_map(|(target, share)| target.map(|target| target - share))
+    }
+
+    /// The largest shortfall of the SGC and coverage shares from their targets, zero or
+    /// less when every target is met
+    pub fn shortfall(&self, sgc: f64, coverage: f64) -> f64 {
+        self.shortfalls(sgc, coverage)
+            .fold(f64::NEG_INFINITY, f64::max)
+    }
+
+    /// Achievement scalarizing function of the SGC and coverage shares, minimized
+    pub fn achievement(&self, sgc: f64, coverage: f64) -> f64 {
+        self.shortfall(sgc, coverage)
+            + Goal::AUGMENTATION * self.shortfalls(sgc, coverage).sum::<f64>()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "sgc": self.sgc,
+            "coverage": self.coverage
+        })
+    }
+}
+
+impl std::fmt::Display for Goal {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let targets: Vec<String> = [("SGC", self.sgc), ("coverage", self.coverage)]
+            .into_iter()
+            .filter_map(|(name, target)| {
+                target.map(|target| format!("{} {}%", name, target * 100.0))
+            })
+            .collect();
+        write!(f, "{}", targets.join(", "))
+    }
+}
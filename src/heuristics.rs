@@ -0,0 +1,82 @@
+use crate::scenario::{Scenario, ScenarioPopulation};
+use crate::{DIMENSIONS, FireflyConfig, SearchMode};
+
+// Search-space diagonal of the 32×32 router swarm the fixed default gamma of 1 was tuned for
+const REFERENCE_DIAGONAL: f64 = 32.0 * std::f64::consts::SQRT_2;
+
+/// Number of coordinates a single firefly moves in: one router's in the router swarm,
+/// a whole layout's in population mode
+pub fn search_dimensions(config: &FireflyConfig) -> usize {
+    match config.search {
+        SearchMode::RouterSwarm => DIMENSIONS,
+        SearchMode::Population { .. } => config.routers * DIMENSIONS,
+    }
+}
+
+/// Population size for a search in `dimensions` coordinates, growing with the square root
+/// of the dimensionality
+pub fn population_size(dimensions: usize) -> usize {
+    (10.0 + 2.0 * (dimensions as f64).sqrt()).round() as usize
+}
+
+/// Light absorption coefficient that keeps the attraction across the search space the
+/// same as in the 32×32 router swarm, where it is exactly 1
+pub fn gamma(config: &FireflyConfig) -> f64 {
+    gamma_for_domain(config.extent(), search_dimensions(config))
+}
+
+/// Light absorption coefficient for any search over `dimensions` coordinates that
+/// each span `extent`, scaled like [`gamma`]
+pub fn gamma_for_domain(extent: f64, dimensions: usize) -> f64 {
+    let diagonal = extent * (dimensions as f64).sqrt();
+    let ratio = REFERENCE_DIAGONAL / diagonal;
+    ratio * ratio
+}
+
+/// Iterations that spend about `evaluations` fitness evaluations: one per iteration in
+/// the router swarm, one per move toward a brighter firefly in population mode
+pub fn iterations(config: &FireflyConfig, evaluations: usize) -> usize {
+    match config.search {
+        SearchMode::RouterSwarm => evaluations.max(1),
+        SearchMode::Population { fireflies } => {
+            let moves = (fireflies * fireflies.saturating_sub(1) / 2).max(1);
+            (evaluations.saturating_sub(fireflies) / moves).max(1)
+        }
+    }
+}
+
+/// Derive the parameters `scenario` leaves unset from the problem size, and describe
+/// every value chosen.
+///
+/// Gamma always follows the domain scale unless given; iterations follow
+/// `evaluation_budget` when one is given without an iteration count. The population
+/// size of `population = "auto"` is chosen by [`Scenario::apply`] and only described here.
+pub fn apply_heuristics(config: &mut FireflyConfig, scenario: &Scenario) -> Vec<String> {
+    let mut choices = Vec::new();
+    if let (Some(ScenarioPopulation::Auto(_)), SearchMode::Population { fireflies }) =
+        (scenario.population, config.search)
+    {
+        choices.push(format!(
+            "population of {} fireflies for {} search dimensions",
+            fireflies,
+            search_dimensions(config)
+        ));
+    }
+    if scenario.gamma.is_none() {
+        config.gamma = gamma(config);
+        choices.push(format!(
+            "gamma {:.4} for a {}-wide domain in {} search dimensions",
+            config.gamma,
+            config.extent(),
+            search_dimensions(config)
+        ));
+    }
+    if let (Some(budget), None) = (scenario.evaluation_budget, scenario.iterations) {
+        config.iterations = iterations(config, budget);
+        choices.push(format!(
+            "{} iterations for a budget of {} evaluations",
+            config.iterations, budget
+        ));
+    }
+    choices
+}
@@ -0,0 +1,225 @@
+use crate::firefly_core::MovementParameters;
+use crate::metrics::{ConnectivityGraph, ncmc, sgc};
+use crate::radio::{Radio, RadioModel};
+use crate::{ClientSet, DIMENSIONS};
+use serde_json::json;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+// Column names of the router coordinates in CSV histories
+const AXES: [&str; DIMENSIONS] = ["x", "y"];
+
+/// Which per-iteration statistics to record, and where
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HistoryConfig {
+    /// Stream the history to this file, as CSV for `.csv` paths and JSON Lines otherwise
+    pub file: Option<String>,
+    /// Append the history to the result file
+    pub in_report: bool,
+    /// Also record the router positions, not only the summary statistics
+    pub positions: bool,
+    /// Also record the movement parameters every iteration applied
+    pub parameters: bool,
+}
+
+impl HistoryConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.file.is_some() || self.in_report
+    }
+}
+
+/// Convergence statistics after one iteration
+#[derive(Clone, Debug)]
+pub struct IterationRecord {
+    pub iteration: usize,
+    /// Fitness of the best layout found so far
+    pub best_fitness: f64,
+    /// Mean fitness of the fireflies; in the router swarm every firefly shares the
+    /// fitness of the current layout
+    pub mean_fitness: f64,
+    /// SGC of the best layout found so far
+    pub sgc: usize,
+    /// NCMC of the best layout found so far
+    pub ncmc: f64,
+    /// Layout of the brightest firefly of the iteration, when positions are recorded
+    pub routers: Option<Vec<[f64; DIMENSIONS]>>,
+    /// Movement equation the iteration applied, when parameters are recorded
+    pub movement: Option<MovementParameters>,
+}
+
+impl IterationRecord {
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut data = json!({
+            "iteration": self.iteration,
+            "best_fitness": self.best_fitness,
+            "mean_fitness": self.mean_fitness,
+            "sgc": self.sgc,
+            "ncmc": self.ncmc
+        });
+        if let Some(routers) = &self.routers {
+            data["routers"] = json!(routers);
+        }
+        if let Some(movement) = &self.movement {
+            data["movement"] = json!({
+                "alpha": movement.alpha,
+                "beta0": movement.beta0,
+                "gamma": movement.gamma,
+                "mean_beta": movement.mean_beta
+            });
+        }
+        data
+    }
+}
+
+enum HistoryWriter {
+    JsonLines(BufWriter<File>),
+    Csv(BufWriter<File>),
+}
+
+/// Per-iteration convergence history, streamed to a file and/or kept for the report
+pub struct IterationHistory {
+    writer: Option<HistoryWriter>,
+    records: Option<Vec<IterationRecord>>,
+    positions: bool,
+    parameters: bool,
+    // Movement parameters of the iteration recorded next
+    movement: Option<MovementParameters>,
+    radius: f64,
+    radio: RadioModel,
+    // The last best layout with its SGC and NCMC, recomputed only when the best changes
+    best: Option<(Vec<[f64; DIMENSIONS]>, usize, f64)>,
+}
+
+impl IterationHistory {
+    /// Open the history described by `config`, `None` when it records nothing
+    pub fn create(
+        config: &HistoryConfig,
+        routers: usize,
+        radio: &Radio,
+    ) -> std::io::Result<Option<IterationHistory>> {
+        if !config.is_enabled() {
+            return Ok(None);
+        }
+        let writer = match &config.file {
+            Some(path) => {
+                let file = BufWriter::new(File::create(path)?);
+                Some(if path.ends_with(".csv") {
+                    HistoryWriter::Csv(file)
+                } else {
+                    HistoryWriter::JsonLines(file)
+                })
+            }
+            None => None,
+        };
+        let mut history = IterationHistory {
+            writer,
+            records: config.in_report.then(Vec::new),
+            positions: config.positions,
+            parameters: config.parameters,
+            movement: None,
+            radius: radio.base_radius(),
+            radio: radio.model().clone(),
+            best: None,
+        };
+        if let Some(HistoryWriter::Csv(writer)) = &mut history.writer {
+            let mut header = "iteration,best_fitness,mean_fitness,sgc,ncmc".to_string();
+            if config.parameters {
+                for axis in AXES {
+                    header += &format!(",alpha_{}", axis);
+                }
+                header += ",beta0,gamma,mean_beta";
+            }
+            if config.positions {
+                for router in 0..routers {
+                    for axis in AXES {
+                        header += &format!(",r{}_{}", router, axis);
+                    }
+                }
+            }
+            writeln!(writer, "{}", header)?;
+        }
+        Ok(Some(history))
+    }
+
+    /// Whether the history records the movement parameters of every iteration
+    pub fn records_movement(&self) -> bool {
+        self.parameters
+    }
+
+    /// Keep the movement parameters of the iteration recorded next, when the history
+    /// records them
+    pub fn record_movement(&mut self, movement: &MovementParameters) {
+        if self.parameters {
+            self.movement = Some(movement.clone());
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        iteration: usize,
+        best: &[[f64; DIMENSIONS]],
+        best_fitness: f64,
+        mean_fitness: f64,
+        brightest: &[[f64; DIMENSIONS]],
+        clients: &ClientSet,
+    ) {
+        if self.best.as_ref().is_none_or(|(layout, ..)| layout != best) {
+            let radio = Radio::with_model(self.radius, &self.radio);
+            let sgc = sgc(&ConnectivityGraph::new(best, &radio));
+            let ncmc = ncmc(best, clients, &radio);
+            self.best = Some((best.to_vec(), sgc, ncmc));
+        }
+        let (_, sgc, ncmc) = self.best.as_ref().expect("set above");
+        let record = IterationRecord {
+            iteration,
+            best_fitness,
+            mean_fitness,
+            sgc: *sgc,
+            ncmc: *ncmc,
+            routers: self.positions.then(|| brightest.to_vec()),
+            movement: self.movement.take(),
+        };
+
+        let result = match &mut self.writer {
+            Some(HistoryWriter::JsonLines(writer)) => writeln!(writer, "{}", record.to_json()),
+            Some(HistoryWriter::Csv(writer)) => {
+                let mut line = format!(
+                    "{},{},{},{},{}",
+                    record.iteration,
+                    record.best_fitness,
+                    record.mean_fitness,
+                    record.sgc,
+                    record.ncmc
+                );
+                if let Some(movement) = &record.movement {
+                    for alpha in &movement.alpha {
+                        line += &format!(",{}", alpha);
+                    }
+                    line += &format!(",{},{},", movement.beta0, movement.gamma);
+                    if let Some(mean_beta) = movement.mean_beta {
+                        line += &mean_beta.to_string();
+                    }
+                }
+                for coord in record.routers.iter().flatten().flatten() {
+                    line += &format!(",{}", coord);
+                }
+                writeln!(writer, "{}", line)
+            }
+            None => Ok(()),
+        };
+        result.expect("Unable to write iteration history");
+        if let Some(records) = &mut self.records {
+            records.push(record);
+        }
+    }
+
+    /// Flush the file and return the records kept for the report
+    pub fn finish(self) -> Option<Vec<IterationRecord>> {
+        if let Some(HistoryWriter::JsonLines(mut writer) | HistoryWriter::Csv(mut writer)) =
+            self.writer
+        {
+            writer.flush().expect("Unable to write iteration history");
+        }
+        self.records
+    }
+}
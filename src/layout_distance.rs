@@ -0,0 +1,138 @@
+use crate::{DIMENSIONS, distance};
+
+/// Largest router count matched exactly; bigger layouts use the greedy matching
+pub const HUNGARIAN_MAX_ROUTERS: usize = 256;
+
+/// Assignment of the routers of one layout to the routers of another of the same size
+pub struct LayoutMatching {
+    /// `assignment[i]` is the router of the second layout matched with router `i` of the first
+    pub assignment: Vec<usize>,
+    /// Sum of the distances between matched routers
+    pub total_distance: f64,
+    /// Whether the matching is the optimal one or the greedy approximation
+    pub exact: bool,
+}
+
+/// Match the routers of two layouts so that the total distance between matched routers
+/// is minimal, falling back to a greedy matching above [`HUNGARIAN_MAX_ROUTERS`] and
+/// for layouts with non-finite coordinates, which the exact matching cannot handle.
+///
+/// Routers are interchangeable, so two layouts that only differ in the order of their
+/// routers are matched at distance zero.
+pub fn match_layouts(a: &[[f64; DIMENSIONS]], b: &[[f64; DIMENSIONS]]) -> LayoutMatching {
+    assert_eq!(a.len(), b.len(), "layouts have different router counts");
+    let finite = |layout: &[[f64; DIMENSIONS]]| layout.as_flattened().iter().all(|c| c.is_finite());
+    let exact = a.len() <= HUNGARIAN_MAX_ROUTERS && finite(a) && finite(b);
+    let assignment = if exact { hungarian(a, b) } else { greedy(a, b) };
+    let total_distance = assignment
+        .iter()
+        .enumerate()
+        .map(|(i, &j)| distance(&a[i], &b[j]))
+        .sum();
+    LayoutMatching {
+        assignment,
+        total_distance,
+        exact,
+    }
+}
+
+/// Mean distance between matched routers of two layouts of the same size, zero for
+/// identical layouts in any router order
+pub fn layout_distance(a: &[[f64; DIMENSIONS]], b: &[[f64; DIMENSIONS]]) -> f64 {
+    if a.is_empty() {
+        return 0.0;
+    }
+    match_layouts(a, b).total_distance / a.len() as f64
+}
+
+// Minimum-cost assignment with the O(n³) Hungarian algorithm (Kuhn-Munkres with potentials)
+fn hungarian(a: &[[f64; DIMENSIONS]], b: &[[f64; DIMENSIONS]]) -> Vec<usize> {
+    let n = a.len();
+    // Every router pair is visited up to n times, so its distance is computed once:
+    // at most HUNGARIAN_MAX_ROUTERS² distances, 512 KiB
+    let costs: Vec<f64> = a
+        .iter()
+        .flat_map(|router| b.iter().map(move |other| distance(router, other)))
+        .collect();
+    // Rows and columns are 1-based below; index 0 is the virtual unmatched column
+    let mut row_potential = vec![0.0; n + 1];
+    let mut column_potential = vec![0.0; n + 1];
+    let mut column_row = vec![0; n + 1];
+    let mut way = vec![0; n + 1];
+
+    for row in 1..=n {
+        column_row[0] = row;
+        let mut column = 0;
+        let mut min_slack = vec![f64::INFINITY; n + 1];
+        let mut used = vec![false; n + 1];
+        loop {
+            used[column] = true;
+            let current_row = column_row[column];
+            let mut delta = f64::INFINITY;
+            let mut next_column = 0;
+            for j in 1..=n {
+                if !used[j] {
+                    let slack = costs[(current_row - 1) * n + j - 1]
+                        - row_potential[current_row]
+                        - column_potential[j];
+                    if slack < min_slack[j] {
+                        min_slack[j] = slack;
+                        way[j] = column;
+                    }
+                    if min_slack[j] < delta {
+                        delta = min_slack[j];
+                        next_column = j;
+                    }
+                }
+            }
+            for j in 0..=n {
+                if used[j] {
+                    row_potential[column_row[j]] += delta;
+                    column_potential[j] -= delta;
+                } else {
+                    min_slack[j] -= delta;
+                }
+            }
+            column = next_column;
+            if column_row[column] == 0 {
+                break;
+            }
+        }
+        // Flip the augmenting path back to the virtual column
+        while column != 0 {
+            let previous = way[column];
+            column_row[column] = column_row[previous];
+            column = previous;
+        }
+    }
+
+    let mut assignment = vec![0; n];
+    for j in 1..=n {
+        assignment[column_row[j] - 1] = j - 1;
+    }
+    assignment
+}
+
+// Repeatedly match the closest pair of still unmatched routers, O(n² log n)
+fn greedy(a: &[[f64; DIMENSIONS]], b: &[[f64; DIMENSIONS]]) -> Vec<usize> {
+    let mut pairs: Vec<(f64, usize, usize)> = a
+        .iter()
+        .enumerate()
+        .flat_map(|(i, router)| {
+            b.iter()
+                .enumerate()
+                .map(move |(j, other)| (distance(router, other), i, j))
+        })
+        .collect();
+    pairs.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let mut assignment = vec![usize::MAX; a.len()];
+    let mut taken = vec![false; b.len()];
+    for (_, i, j) in pairs {
+        if assignment[i] == usize::MAX && !taken[j] {
+            assignment[i] = j;
+            taken[j] = true;
+        }
+    }
+    assignment
+}
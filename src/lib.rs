@@ -0,0 +1,93 @@
+//! Firefly algorithm for placing mesh routers in a wireless mesh network (WMN).
+//!
+//! Every router is a firefly attracted to the others; the best layout seen is
+//! scored by a weighted sum of the Size of Giant Component (SGC), the Number of
+//! Covered Mesh Clients (NCMC) and the NCMC per Router (NCMCpR).
+//!
+//! ```no_run
+//! use ff_wmn::{FireflyConfig, FireflyOptimizer, FitnessWeights};
+//!
+//! let config = FireflyConfig::new()
+//!     .routers(24)
+//!     .clients(64)
+//!     .iterations(200)
+//!     .bounds(0.0, 48.0)
+//!     .weights(FitnessWeights {
+//!         sgc: 0.6,
+//!         ncmc: 0.3,
+//!         ncmcpr: 0.1,
+//!         overlap: 0.05,
+//!     });
+//! let solution = FireflyOptimizer::new(config).unwrap().run();
+//! println!("coverage: {}", solution.coverage());
+//! ```
+//!
+//! The same [`FireflyCore`] that searches whole layouts in population mode
+//! optimizes any [`objective::Objective`], such as the benchmark functions, and
+//! searches the Pareto front of any [`objective::MultiObjective`].
+
+pub mod brightness;
+pub mod calibration;
+pub mod clients;
+pub mod config;
+pub mod consensus;
+pub mod eval_log;
+pub mod firefly_core;
+pub mod fixed_point;
+pub mod goal;
+pub mod heuristics;
+pub mod history;
+pub mod layout_distance;
+pub mod metrics;
+pub mod migrate;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod multiresolution;
+pub mod objective;
+pub mod optimizer;
+pub mod ordering;
+pub mod pareto;
+pub mod plugin;
+pub mod population_trace;
+pub mod power;
+pub mod radio;
+pub mod rate;
+pub mod relocation;
+pub mod render;
+pub mod report;
+pub mod robustness;
+pub mod sanity;
+pub mod scenario;
+pub mod schedule;
+pub mod sleep;
+pub mod solution;
+pub mod spatial;
+pub mod termination;
+pub mod tiling;
+pub mod timeline;
+pub mod topology;
+
+pub use brightness::BrightnessTransform;
+pub use clients::ClientSet;
+pub use config::{ConfigError, FireflyConfig, FitnessWeights, SearchMode};
+pub use firefly_core::{CoreResult, FireflyCore, Improvement, IterationState, MovementParameters};
+pub use goal::Goal;
+pub use layout_distance::{LayoutMatching, layout_distance, match_layouts};
+pub use metrics::{ConnectivityGraph, Metrics, distance};
+pub use multiresolution::Multiresolution;
+pub use optimizer::{FireflyOptimizer, NewBest, NewBestHook};
+pub use ordering::{TieBreak, compare_fitness};
+pub use pareto::{ArchivePruning, ParetoPoint, ParetoResult, ParetoState};
+pub use radio::{PathLoss, RadioModel};
+pub use relocation::Relocation;
+pub use render::Plot;
+pub use robustness::Robustness;
+pub use sanity::{NonFinite, NonFinitePolicy};
+pub use scenario::Scenario;
+pub use schedule::{AlphaSchedule, EarlyStopping};
+pub use solution::{RunInfo, Solution};
+pub use termination::Termination;
+pub use tiling::Tiling;
+
+/// Number of coordinates of every router and client position
+pub const DIMENSIONS: usize = 2;
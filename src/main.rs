@@ -1,170 +1,718 @@
-use rand::Rng;
-use std::collections::VecDeque;
+use clap::{Args, Parser, Subcommand};
+use ff_wmn::eval_log::EvaluationLog;
+use ff_wmn::heuristics::apply_heuristics;
+use ff_wmn::plugin::ObjectivePlugin;
+use ff_wmn::rate::RateModel;
+use ff_wmn::scenario::{
+    Auto, RobustnessMode, ScenarioAlpha, ScenarioGoal, ScenarioHistory, ScenarioMultiresolution,
+    ScenarioPathLoss, ScenarioPopulation, ScenarioRadio, ScenarioRobustness, ScenarioTiling,
+    ScenarioWeights,
+};
+use ff_wmn::solution::routers_from_json;
+use ff_wmn::{
+    AlphaSchedule, ArchivePruning, BrightnessTransform, ClientSet, DIMENSIONS, FireflyConfig,
+    FireflyOptimizer, NonFinitePolicy, Scenario, Solution, Termination, TieBreak,
+};
 use std::fs::File;
 use std::io::Write;
-use serde_json::json;
-
-const NUMBER_OF_MESH_ROUTERS: usize = 16;
-const NUMBER_OF_MESH_CLIENTS: usize = 32;
-const DIMENSIONS: usize = 2;
-const NUMBER_OF_ITERATIONS: usize = 100;
-const ALPHA: f64 = 0.5;
-const BETA0: f64 = 1.0;
-const GAMMA: f64 = 1.0;
-const LOWER_BOUND: f64 = 0.0;
-const UPPER_BOUND: f64 = 32.0;
-const MAXIMUM_COMMUNICATION_DISTANCE: f64 = 4.5;
-
-// Fitness Weights
-const PRIORITY_SGC: f64 = 0.8;
-const PRIORITY_NCMC: f64 = 0.1;
-const PRIORITY_NCMCPR: f64 = 0.1;
-
-// Distance function
-fn distance(x: &[f64], y: &[f64]) -> f64 {
-    x.iter().zip(y.iter()).map(|(xi, yi)| (xi - yi).powi(2)).sum::<f64>().sqrt()
-}
-
-// Function to compute Size of Giant Component (SGC)
-fn sgc(routers: &[[f64; DIMENSIONS]]) -> usize {
-    let mut largest_component = 0;
-    let mut visited = vec![false; routers.len()];
-
-    for start in 0..routers.len() {
-        if !visited[start] {
-            let mut queue = VecDeque::new();
-            queue.push_back(start);
-            visited[start] = true;
-            let mut component_size = 1;
-
-            while let Some(current) = queue.pop_front() {
-                for (i, other_router) in routers.iter().enumerate() {
-                    if !visited[i] {
-                        let dist = distance(&routers[current], other_router);
-                        if dist <= MAXIMUM_COMMUNICATION_DISTANCE {
-                            visited[i] = true;
-                            queue.push_back(i);
-                            component_size += 1;
-                        }
-                    }
-                }
+
+mod commands;
+
+// Save results to file
+fn save_results(solution: &Solution, path: &str) {
+    let data = solution.to_json();
+
+    let mut file = File::create(path).expect("Unable to create file");
+    file.write_all(data.to_string().as_bytes()).expect("Unable to write data");
+}
+
+// Command-line interface
+#[derive(Parser)]
+#[command(
+    version,
+    about = "Place wireless mesh routers with the firefly algorithm",
+    args_conflicts_with_subcommands = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Minimize a benchmark function with the firefly core
+    Benchmark(commands::benchmark::BenchmarkArgs),
+    /// Combine the layouts of several runs into a consensus placement with per-router stability
+    Consensus(commands::consensus::ConsensusArgs),
+    /// Re-optimize periodically for clients fetched from a source and publish every plan
+    Daemon(commands::daemon::DaemonArgs),
+    /// Read the live topology of a batman-adv or OpenWrt mesh into a layout and measured radii
+    Import(commands::import::ImportArgs),
+    /// Fit the path-loss model to site-survey RSSI samples
+    Calibrate(commands::calibrate::CalibrateArgs),
+    /// Rerun the WMN-FA literature setup with seeded repetitions and save comparable tables
+    Literature(commands::literature::LiteratureArgs),
+    /// Upgrade a result file to the current schema version
+    Migrate(commands::migrate::MigrateArgs),
+    /// Plan the transmit power and channels of routers that stay where they are
+    Power(commands::power::PowerArgs),
+    /// Sweep one router coordinate of a saved layout and print the fitness curve as CSV
+    Probe(commands::probe::ProbeArgs),
+    /// Order the moves from an installed layout to a re-optimized one without dropping below a coverage floor
+    Relocate(commands::relocate::RelocateArgs),
+    /// Draw a saved layout with its coverage, router links and clients as SVG or PNG
+    Render(commands::render::RenderArgs),
+    /// Order the routers of a saved layout that can sleep while the rest stay connected and covering
+    Sleep(commands::sleep::SleepArgs),
+    /// Summarize the mesh clients of a scenario before optimizing it
+    Stats(commands::stats::StatsArgs),
+    /// Optimize while scripted events change the clients, routers and walls
+    Timeline(commands::timeline::TimelineArgs),
+}
+
+// Options of an optimization run; parameters given here override the scenario file
+#[derive(Args)]
+struct RunArgs {
+    /// TOML file with the parameter set of the run
+    #[arg(long, value_name = "FILE")]
+    scenario: Option<String>,
+    /// Number of mesh routers
+    #[arg(long)]
+    routers: Option<usize>,
+    /// Number of random mesh clients
+    #[arg(long)]
+    clients: Option<usize>,
+    /// Load the mesh clients from a CSV (x,y[,weight]), GeoJSON or JSON file
+    #[arg(long, value_name = "PATH", conflicts_with = "clients")]
+    clients_file: Option<String>,
+    /// Number of firefly iterations
+    #[arg(long)]
+    iterations: Option<usize>,
+    /// Lower and upper bound of every coordinate, e.g. 0..32
+    #[arg(long, value_name = "LOWER..UPPER", value_parser = parse_bounds, allow_hyphen_values = true)]
+    bounds: Option<(f64, f64)>,
+    /// Communication radius of routers
+    #[arg(long, value_name = "DISTANCE")]
+    max_comm_distance: Option<f64>,
+    /// Fitness weights, e.g. sgc=0.8,ncmc=0.1,ncmcpr=0.1; overlap=w penalizes coverage overlap
+    #[arg(long, value_parser = parse_weights)]
+    weights: Option<ScenarioWeights>,
+    /// Transmit radius of each router by index; routers past the list use the communication radius
+    #[arg(long, value_name = "RADII", value_delimiter = ',')]
+    router_radii: Option<Vec<f64>>,
+    /// Routers closer than this to each other are penalized
+    #[arg(long, value_name = "DISTANCE")]
+    min_separation: Option<f64>,
+    /// Fitness penalty per pair of routers closer than the minimum separation
+    #[arg(long, value_name = "PENALTY")]
+    separation_penalty: Option<f64>,
+    /// Cover clients by received signal strength, e.g. tx_power=20,reference_loss=40,exponent=3,sensitivity=-40
+    #[arg(long, value_name = "PARAMETERS", value_parser = parse_path_loss)]
+    path_loss: Option<ScenarioPathLoss>,
+    /// Obstacle blocking links and coverage that cross it, from x0,y0 to x1,y1; repeatable
+    #[arg(long, value_name = "X0,Y0,X1,Y1", value_parser = parse_wall, allow_hyphen_values = true)]
+    wall: Vec<[f64; 4]>,
+    /// Fit the path-loss exponent and reference loss to survey samples (CSV: distance,rssi[,tx_power])
+    #[arg(long, value_name = "PATH")]
+    survey_file: Option<String>,
+    /// Layout kept as best when fitness values tie: keep-incumbent or prefer-newer
+    #[arg(long, value_name = "POLICY")]
+    tie_break: Option<TieBreak>,
+    /// Random step size, one value for all dimensions or one comma-separated value per dimension
+    #[arg(long, value_parser = parse_alpha)]
+    alpha: Option<ScenarioAlpha>,
+    /// Decay of the random step size: constant, linear:<final fraction> or geometric:<factor>
+    #[arg(long, value_name = "SCHEDULE")]
+    alpha_schedule: Option<AlphaSchedule>,
+    /// Spare the router covering the most client weight from the random step
+    #[arg(long)]
+    elitism: bool,
+    /// Stop once the best fitness has not improved for this many iterations
+    #[arg(long, value_name = "ITERATIONS")]
+    patience: Option<usize>,
+    /// Smallest gain in the best fitness that resets the patience
+    #[arg(long, requires = "patience")]
+    tolerance: Option<f64>,
+    /// Stop once a rule holds: stall:<patience>[:<tolerance>], coverage:<share>, fitness:<value>, time:<seconds>, combined with all(...) and any(...)
+    #[arg(long, value_name = "RULE")]
+    termination: Option<Termination>,
+    /// Evaluate the fitness only every this many iterations and at the last, moving the swarm in between
+    #[arg(long, value_name = "ITERATIONS")]
+    evaluation_interval: Option<usize>,
+    /// Attractiveness at distance zero
+    #[arg(long)]
+    beta0: Option<f64>,
+    /// Light absorption coefficient
+    #[arg(long)]
+    gamma: Option<f64>,
+    /// Attract the layouts of a population search in proportion to their light: identity, log or rank
+    #[arg(long, value_name = "TRANSFORM")]
+    brightness: Option<BrightnessTransform>,
+    /// Estimate SGC from a sample of seed routers during the search
+    #[arg(long)]
+    approx_metrics: bool,
+    /// Damp moves into the boundary instead of clamping them
+    #[arg(long)]
+    soft_bounds: bool,
+    /// Snap the final router coordinates to multiples of this precision
+    #[arg(long, value_name = "PRECISION")]
+    snap: Option<f64>,
+    /// Move routers on a grid of this precision with integer arithmetic, reproducible across platforms
+    #[arg(long, value_name = "PRECISION")]
+    fixed_point: Option<f64>,
+    /// Lower the SGC fidelity whenever an iteration takes longer than this
+    #[arg(long, value_name = "MS")]
+    iteration_budget_ms: Option<f64>,
+    /// Fitness-sharing radius as a fraction of the domain diagonal
+    #[arg(long, value_name = "FRACTION")]
+    sharing_radius: Option<f64>,
+    /// Search a population of whole layouts instead of a swarm of routers: a firefly count, or auto
+    #[arg(long, value_name = "FIREFLIES|auto", value_parser = parse_population)]
+    population: Option<ScenarioPopulation>,
+    /// Return the Pareto front over SGC, NCMC and penalized overlap of a population search
+    #[arg(long)]
+    pareto: bool,
+    /// Layouts kept on the Pareto front
+    #[arg(long, value_name = "LAYOUTS")]
+    pareto_archive: Option<usize>,
+    /// Keep one layout per box of this size over the objectives of the Pareto front
+    #[arg(long, value_name = "EPSILON")]
+    pareto_epsilon: Option<f64>,
+    /// Optimize TILES×TILES tiles of the area in parallel, then stitch their layouts together
+    #[arg(long)]
+    tiles: Option<usize>,
+    /// Iterations of the stitching pass over the whole area, the run's iterations by default
+    #[arg(long, value_name = "ITERATIONS")]
+    stitch_iterations: Option<usize>,
+    /// Solve this many down-scaled problems first, coarsest first, and refine their layout
+    #[arg(long, value_name = "LEVELS", conflicts_with = "tiles")]
+    multiresolution: Option<usize>,
+    /// Share of the clients kept per coarse level
+    #[arg(long, value_name = "FRACTION")]
+    coarse_client_fraction: Option<f64>,
+    /// Communication distance multiplier per coarse level
+    #[arg(long, value_name = "FACTOR")]
+    coarse_radius_factor: Option<f64>,
+    /// Iterations of every coarse level, the run's iterations by default
+    #[arg(long, value_name = "ITERATIONS")]
+    coarse_iterations: Option<usize>,
+    /// Score layouts by their expected fitness when every router fails with this probability
+    #[arg(long, value_name = "PROBABILITY")]
+    failure_probability: Option<f64>,
+    /// Random failure patterns drawn per evaluation of the expected fitness
+    #[arg(long, value_name = "SAMPLES")]
+    failure_samples: Option<usize>,
+    /// Score layouts by their fitness under the worst single router failure instead
+    #[arg(long, conflicts_with = "failure_probability")]
+    worst_case: bool,
+    /// Count only clients covered wherever they move up to this distance, with --worst-case
+    #[arg(long, value_name = "DISTANCE", requires = "worst_case")]
+    client_shift: Option<f64>,
+    /// Report client data rates by distance: 802.11n, or bands as <range share>:<Mbit/s>,...
+    #[arg(long, value_name = "MODEL")]
+    rates: Option<RateModel>,
+    /// Search toward this share of routers in the giant component instead of the weighted sum
+    #[arg(long, value_name = "SHARE")]
+    goal_sgc: Option<f64>,
+    /// Search toward this share of the client weight covered instead of the weighted sum
+    #[arg(long, value_name = "SHARE")]
+    goal_coverage: Option<f64>,
+    /// Fitness evaluations to spend, used to choose the iterations when they are not given
+    #[arg(long, value_name = "EVALUATIONS")]
+    evaluation_budget: Option<usize>,
+    /// When a position or fitness turns NaN or infinite: repair (re-randomize) or abort
+    #[arg(long, value_name = "POLICY")]
+    non_finite: Option<NonFinitePolicy>,
+    /// Seed the random number generator so the run can be reproduced exactly
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Threads evaluating the fitness of large networks, all cores by default
+    #[arg(long)]
+    threads: Option<usize>,
+    /// Start the router swarm from the layout of a result or imported topology file
+    #[arg(long, value_name = "FILE")]
+    initial_layout: Option<String>,
+    /// Shared library providing the objective function
+    #[arg(long, value_name = "PATH")]
+    objective_plugin: Option<String>,
+    /// Where to save the results
+    #[arg(long, value_name = "PATH", default_value = "firefly_results.json")]
+    output: String,
+    /// Render the best layout as PNG for .png paths and as SVG otherwise
+    #[arg(long, value_name = "PATH")]
+    plot: Option<String>,
+    /// Stream every firefly's position per iteration as CSV, gzip-compressed for .gz paths
+    #[arg(long, value_name = "PATH")]
+    population_csv: Option<String>,
+    /// Stream per-iteration convergence statistics as CSV (.csv paths) or JSON Lines
+    #[arg(long, value_name = "PATH")]
+    history_file: Option<String>,
+    /// Append per-iteration convergence statistics to the result file
+    #[arg(long)]
+    history_in_report: bool,
+    /// Also record the router positions of every iteration in the history
+    #[arg(long)]
+    history_positions: bool,
+    /// Also record the alpha, beta and gamma every iteration applied in the history
+    #[arg(long)]
+    history_parameters: bool,
+    /// Record every evaluated layout and its fitness to a binary log
+    #[arg(long, value_name = "PATH")]
+    eval_log: Option<String>,
+    /// Name of the run
+    #[arg(long)]
+    name: Option<String>,
+    /// Tag the run, may be repeated
+    #[arg(long, value_name = "KEY=VALUE", value_parser = parse_tag)]
+    tag: Vec<(String, String)>,
+}
+
+impl RunArgs {
+    // Parameters given as flags, which override the scenario file
+    fn scenario(&self) -> Scenario {
+        Scenario {
+            name: self.name.clone(),
+            routers: self.routers,
+            clients: self.clients,
+            clients_file: self.clients_file.clone(),
+            survey_file: self.survey_file.clone(),
+            iterations: self.iterations,
+            alpha: self.alpha,
+            alpha_schedule: self.alpha_schedule,
+            elitism: self.elitism.then_some(true),
+            patience: self.patience,
+            tolerance: self.tolerance,
+            termination: self.termination.clone(),
+            evaluation_interval: self.evaluation_interval,
+            beta0: self.beta0,
+            gamma: self.gamma,
+            brightness: self.brightness,
+            lower_bound: self.bounds.map(|(lower, _)| lower),
+            upper_bound: self.bounds.map(|(_, upper)| upper),
+            max_comm_distance: self.max_comm_distance,
+            weights: self.weights.clone().unwrap_or_default(),
+            radio: ScenarioRadio {
+                radii: self.router_radii.clone(),
+                min_separation: self.min_separation,
+                separation_penalty: self.separation_penalty,
+                path_loss: self.path_loss,
+                walls: (!self.wall.is_empty()).then(|| self.wall.clone()),
+            },
+            tie_break: self.tie_break,
+            approx_metrics: self.approx_metrics.then_some(true),
+            soft_bounds: self.soft_bounds.then_some(true),
+            snap: self.snap,
+            fixed_point: self.fixed_point,
+            iteration_budget_ms: self.iteration_budget_ms,
+            sharing_radius: self.sharing_radius,
+            population: self.population,
+            pareto: self.pareto.then_some(true),
+            pareto_archive: self.pareto_archive,
+            pareto_epsilon: self.pareto_epsilon,
+            evaluation_budget: self.evaluation_budget,
+            non_finite: self.non_finite,
+            seed: self.seed,
+            history: ScenarioHistory {
+                file: self.history_file.clone(),
+                in_report: self.history_in_report.then_some(true),
+                positions: self.history_positions.then_some(true),
+                parameters: self.history_parameters.then_some(true),
+            },
+            tiling: ScenarioTiling {
+                tiles: self.tiles,
+                stitch_iterations: self.stitch_iterations,
+            },
+            multiresolution: ScenarioMultiresolution {
+                levels: self.multiresolution,
+                client_fraction: self.coarse_client_fraction,
+                radius_factor: self.coarse_radius_factor,
+                coarse_iterations: self.coarse_iterations,
+            },
+            robustness: ScenarioRobustness {
+                mode: if self.worst_case {
+                    Some(RobustnessMode::WorstCase)
+                } else {
+                    self.failure_probability.map(|_| RobustnessMode::Expected)
+                },
+                failure_probability: self.failure_probability,
+                samples: self.failure_samples,
+                client_shift: self.client_shift,
+                router_failure: None,
+            },
+            rates: self.rates.clone(),
+            goal: ScenarioGoal {
+                sgc: self.goal_sgc,
+                coverage: self.goal_coverage,
+            },
+            tags: self.tag.iter().cloned().collect(),
+        }
+    }
+
+    // Configuration of the run and the clients to load, if any
+    fn config(&self) -> (FireflyConfig, Option<ClientSet>) {
+        let exit = |message: String| -> ! {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        };
+        let scenario = match &self.scenario {
+            Some(path) => Scenario::load(path).unwrap_or_else(|message| exit(message)),
+            None => Scenario::default(),
+        }
+        .merge(self.scenario());
+        let clients = scenario
+            .load_clients()
+            .unwrap_or_else(|message| exit(message));
+        let mut config = scenario.clone().apply(FireflyConfig::default());
+        if let Some(calibration) = scenario
+            .calibrate(&mut config)
+            .unwrap_or_else(|message| exit(message))
+        {
+            println!("Calibrated the path-loss model: {}", calibration);
+        }
+        for choice in apply_heuristics(&mut config, &scenario) {
+            println!("Heuristic {}", choice);
+        }
+        (config, clients)
+    }
+}
+
+// Parse `lower..upper`
+fn parse_bounds(value: &str) -> Result<(f64, f64), String> {
+    let invalid = || format!("expected <lower>..<upper>, got {}", value);
+    let (lower, upper) = value.split_once("..").ok_or_else(invalid)?;
+    let lower = lower.trim().parse().map_err(|_| invalid())?;
+    let upper = upper.trim().parse().map_err(|_| invalid())?;
+    Ok((lower, upper))
+}
+
+// Parse comma-separated `component=weight` pairs
+fn parse_weights(value: &str) -> Result<ScenarioWeights, String> {
+    let mut weights = ScenarioWeights::default();
+    for part in value.split(',') {
+        let (key, weight) = part
+            .split_once('=')
+            .ok_or_else(|| format!("expected component=weight, got {}", part))?;
+        let weight = Some(
+            weight
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid weight {}", weight))?,
+        );
+        match key.trim() {
+            "sgc" => weights.sgc = weight,
+            "ncmc" => weights.ncmc = weight,
+            "ncmcpr" => weights.ncmcpr = weight,
+            "overlap" => weights.overlap = weight,
+            other => {
+                return Err(format!(
+                    "unknown component {}, expected sgc, ncmc, ncmcpr or overlap",
+                    other
+                ));
             }
-            largest_component = largest_component.max(component_size);
         }
     }
-    largest_component
+    Ok(weights)
 }
 
-// Function to compute Number of Covered Mesh Clients (NCMC)
-fn ncmc(routers: &[[f64; DIMENSIONS]], clients: &[[f64; DIMENSIONS]]) -> usize {
-    let mut covered_clients = 0;
-    for client in clients {
-        for router in routers {
-            if distance(router, client) <= MAXIMUM_COMMUNICATION_DISTANCE {
-                covered_clients += 1;
-                break;
+// Parse comma-separated `parameter=value` pairs of the path-loss model; an empty string
+// keeps the defaults
+fn parse_path_loss(value: &str) -> Result<ScenarioPathLoss, String> {
+    let mut path_loss = ScenarioPathLoss::default();
+    for part in value.split(',').filter(|part| !part.trim().is_empty()) {
+        let (key, parameter) = part
+            .split_once('=')
+            .ok_or_else(|| format!("expected parameter=value, got {}", part))?;
+        let parameter = Some(
+            parameter
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid value {}", parameter))?,
+        );
+        match key.trim() {
+            "tx_power" => path_loss.tx_power = parameter,
+            "reference_loss" => path_loss.reference_loss = parameter,
+            "exponent" => path_loss.exponent = parameter,
+            "sensitivity" => path_loss.sensitivity = parameter,
+            other => {
+                return Err(format!(
+                    "unknown parameter {}, expected tx_power, reference_loss, exponent or sensitivity",
+                    other
+                ));
             }
         }
     }
-    covered_clients
+    Ok(path_loss)
 }
 
-// Function to compute Number of Covered Mesh Clients per Router (NCMCpR)
-fn ncmcpr(routers: &[[f64; DIMENSIONS]], clients: &[[f64; DIMENSIONS]]) -> f64 {
-    ncmc(routers, clients) as f64 / routers.len() as f64
+// Parse a single alpha for all dimensions or one comma-separated alpha per dimension
+fn parse_alpha(value: &str) -> Result<ScenarioAlpha, String> {
+    let values = value
+        .split(',')
+        .map(|part| part.trim().parse())
+        .collect::<Result<Vec<f64>, _>>()
+        .map_err(|err| err.to_string())?;
+    match values.as_slice() {
+        [alpha] => Ok(ScenarioAlpha::Uniform(*alpha)),
+        _ => values
+            .try_into()
+            .map(ScenarioAlpha::PerDimension)
+            .map_err(|_| format!("expected 1 or {} comma-separated values", DIMENSIONS)),
+    }
 }
 
-// Fitness function
-fn fitness_function(routers: &[[f64; DIMENSIONS]], clients: &[[f64; DIMENSIONS]]) -> f64 {
-    let sgc = sgc(routers) as f64;
-    let ncmc = ncmc(routers, clients) as f64;
-    let ncmcpr = ncmcpr(routers, clients);
+// Parse a firefly count or `auto`
+fn parse_population(value: &str) -> Result<ScenarioPopulation, String> {
+    match value {
+        "auto" => Ok(ScenarioPopulation::Auto(Auto::Auto)),
+        _ => value
+            .parse()
+            .map(ScenarioPopulation::Fireflies)
+            .map_err(|_| format!("expected a firefly count or auto, got {}", value)),
+    }
+}
 
-    (PRIORITY_SGC * sgc) + (PRIORITY_NCMC * ncmc) + (PRIORITY_NCMCPR * ncmcpr)
+// Parse the ends of a wall, `x0,y0,x1,y1`
+fn parse_wall(value: &str) -> Result<[f64; 4], String> {
+    let invalid = || format!("expected x0,y0,x1,y1, got {}", value);
+    let ends: Vec<f64> = value
+        .split(',')
+        .map(|end| end.trim().parse().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    ends.try_into().map_err(|_| invalid())
 }
 
-// Save results to file
-fn save_results(
-    routers: &Vec<[f64; DIMENSIONS]>,
-    clients: &Vec<[f64; DIMENSIONS]>,
-    best_fitness: f64,
-    sgc: usize,
-    ncmc: usize,
-    ncmcpr: f64,
-) {
-    let data = json!({
-        "mesh_routers": routers,
-        "mesh_clients": clients,
-        "best_fitness": best_fitness,
-        "sgc": sgc,
-        "ncmc": ncmc,
-        "ncmcpr": ncmcpr
-    });
+// Parse `key=value`
+fn parse_tag(value: &str) -> Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected key=value, got {}", value)),
+    }
+}
 
-    let mut file = File::create("firefly_results.json").expect("Unable to create file");
-    file.write_all(data.to_string().as_bytes()).expect("Unable to write data");
+// Router layout of a result or imported topology file
+fn load_layout(path: &str) -> Result<Vec<[f64; DIMENSIONS]>, String> {
+    let contents =
+        std::fs::read_to_string(path).map_err(|err| format!("Unable to read {}: {}", path, err))?;
+    let data = serde_json::from_str(&contents)
+        .map_err(|err| format!("Unable to parse {}: {}", path, err))?;
+    routers_from_json(&data).map_err(|err| format!("{}: {}", path, err))
 }
 
-// Firefly Algorithm
-fn firefly_algorithm() {
-    let mut rng = rand::thread_rng();
-    let mut mesh_routers = vec![[0.0; DIMENSIONS]; NUMBER_OF_MESH_ROUTERS];
-    let mut mesh_clients = vec![[0.0; DIMENSIONS]; NUMBER_OF_MESH_CLIENTS];
+// Build the optimizer described by the command-line options
+fn optimizer(options: &RunArgs) -> FireflyOptimizer {
+    let (config, clients) = options.config();
+    let routers = config.routers;
+    let mut optimizer = FireflyOptimizer::new(config).unwrap_or_else(|err| {
+        eprintln!("Invalid configuration: {}", err);
+        std::process::exit(2);
+    });
+    if let Some(clients) = clients {
+        println!(
+            "Loaded {} mesh clients from {}",
+            clients.len(),
+            clients.source.as_deref().unwrap_or_default()
+        );
+        optimizer = optimizer.with_clients(clients);
+    }
+    if let Some(path) = &options.initial_layout {
+        let layout = load_layout(path).unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        });
+        optimizer = optimizer.with_initial_layout(layout).unwrap_or_else(|err| {
+            eprintln!("Invalid configuration: {}", err);
+            std::process::exit(2);
+        });
+        println!("Starting from the layout of {}", path);
+    }
+    if let Some(path) = &options.objective_plugin {
+        let plugin = ObjectivePlugin::load(path).unwrap_or_else(|message| {
+            eprintln!("{}", message);
+            std::process::exit(1);
+        });
+        println!("Using objective plugin {}", plugin.path());
+        optimizer = optimizer.with_objective_plugin(plugin);
+    }
+    if let Some(path) = &options.eval_log {
+        let eval_log = EvaluationLog::create(path, routers, DIMENSIONS)
+            .expect("Unable to create evaluation log");
+        optimizer = optimizer.with_evaluation_log(eval_log);
+    }
+    if let Some(path) = &options.population_csv {
+        optimizer = optimizer.with_population_trace(path).unwrap_or_else(|err| {
+            eprintln!("Invalid configuration: {}", err);
+            std::process::exit(2);
+        });
+    }
+    optimizer
+}
 
-    // Initialize mesh clients randomly
-    for client in mesh_clients.iter_mut() {
-        for coord in client.iter_mut() {
-            *coord = rng.gen_range(LOWER_BOUND..UPPER_BOUND);
+// Optimize, then save and print the results
+fn run(options: &RunArgs) {
+    if let Some(threads) = options.threads {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build_global()
+            .expect("Unable to start the evaluation threads");
+    }
+    let solution = optimizer(options).try_run().unwrap_or_else(|err| {
+        eprintln!("Aborted: {}", err);
+        std::process::exit(1);
+    });
+    let report = &solution.report;
+    if let Some(tiling) = solution.config.tiling {
+        println!(
+            "Optimized {0}×{0} tiles, stitched over {1} iterations",
+            tiling.tiles, tiling.stitch_iterations
+        );
+    }
+    if let Some(multiresolution) = solution.config.multiresolution {
+        println!(
+            "Refined the layouts of {} coarse levels",
+            multiresolution.levels
+        );
+    }
+    if let Some(budget) = solution.config.iteration_budget {
+        for change in &report.fidelity_changes {
+            println!(
+                "Iteration {} took {:.3} ms (budget {:.3} ms), switched to {}",
+                change.iteration,
+                change.elapsed.as_secs_f64() * 1000.0,
+                budget.as_secs_f64() * 1000.0,
+                change.sgc_mode.describe()
+            );
         }
     }
+    if let Some(iterations) = report.stopped_after {
+        let config = &solution.config;
+        let reason = match (&config.termination, config.early_stopping) {
+            (None, _) => "the best fitness stopped improving".to_string(),
+            (Some(termination), None) => format!("the rule {} held", termination),
+            (Some(termination), Some(_)) => format!(
+                "the best fitness stopped improving or the rule {} held",
+                termination
+            ),
+        };
+        println!("Stopped early after {} iterations: {}", iterations, reason);
+    }
+    if report.movement_only_iterations > 0 {
+        println!(
+            "Moved without evaluating in {} of {} iterations",
+            report.movement_only_iterations,
+            report.stopped_after.unwrap_or(solution.config.iterations)
+        );
+    }
+    if let Some(path) = &options.eval_log {
+        println!(
+            "Recorded {} evaluations to {}",
+            report.timings.evaluations, path
+        );
+    }
+    if let Some(path) = &options.population_csv {
+        println!("Population trace saved to {}", path);
+    }
+    if let Some(path) = &solution.config.history.file {
+        println!("Iteration history saved to {}", path);
+    }
+    save_results(&solution, &options.output);
 
-    // Initialize mesh routers randomly
-    for router in mesh_routers.iter_mut() {
-        for coord in router.iter_mut() {
-            *coord = rng.gen_range(LOWER_BOUND..UPPER_BOUND);
+    println!("Final Fitness Score: {}", report.metrics.fitness);
+    if let (Some(robustness), Some(fitness)) = (&solution.config.robustness, report.robust_fitness)
+    {
+        println!("Fitness with {}: {}", robustness, fitness);
+    }
+    if let (Some(goal), Some(shortfall)) = (&solution.config.goal, solution.goal_shortfall()) {
+        if shortfall > 0.0 {
+            println!("Goal {} missed by {:.1}%", goal, shortfall * 100.0);
+        } else {
+            println!("Goal {} met", goal);
         }
     }
-
-    let mut best_mesh_routers = mesh_routers.clone();
-    let mut best_fitness = fitness_function(&mesh_routers, &mesh_clients);
-
-    // Firefly Algorithm Iterations
-    for _ in 0..NUMBER_OF_ITERATIONS {
-        for i in 0..NUMBER_OF_MESH_ROUTERS {
-            for j in 0..NUMBER_OF_MESH_ROUTERS {
-                if i != j {
-                    let r_ij = distance(&mesh_routers[i], &mesh_routers[j]);
-                    let beta = BETA0 * (-GAMMA * r_ij * r_ij).exp();
-
-                    for d in 0..DIMENSIONS {
-                        let attraction = beta * (mesh_routers[j][d] - mesh_routers[i][d]);
-                        let randomness = ALPHA * (rng.r#gen::<f64>() - 0.5);
-
-                        mesh_routers[i][d] += attraction + randomness;
-                        mesh_routers[i][d] = mesh_routers[i][d].clamp(LOWER_BOUND, UPPER_BOUND);
-                    }
-                }
-            }
+    println!("Coverage: {:.1}%", solution.coverage() * 100.0);
+    if let (Some(model), Some(rates)) = (&solution.config.rates, solution.client_rates()) {
+        let served = rates.iter().filter(|&&rate| rate > 0.0).count();
+        let network_rate: f64 = rates.iter().sum();
+        println!(
+            "Network rate ({}): {:.1} Mbit/s, {:.1} Mbit/s per served client",
+            model,
+            network_rate,
+            network_rate / served.max(1) as f64
+        );
+    }
+    println!("Coverage overlap: {:.3} disk areas", report.metrics.overlap);
+    if let Some(violations) = report.metrics.separation_violations {
+        println!(
+            "Router pairs closer than the minimum separation: {}",
+            violations
+        );
+    }
+    let uncovered = solution.uncovered_clients();
+    if !uncovered.is_empty() {
+        println!("Uncovered clients: {:?}", uncovered);
+    }
+    if let Some(front) = &report.pareto_front {
+        match solution.config.pareto_pruning {
+            ArchivePruning::Crowding => println!("Pareto front of {} layouts:", front.len()),
+            pruning => println!(
+                "Pareto front of {} layouts, pruned by an {}:",
+                front.len(),
+                pruning
+            ),
         }
-
-        let current_fitness = fitness_function(&mesh_routers, &mesh_clients);
-        if current_fitness > best_fitness {
-            best_fitness = current_fitness;
-            best_mesh_routers = mesh_routers.clone();
+        println!(
+            "  {:>5} {:>10} {:>10} {:>12}",
+            "SGC", "NCMC", "Overlap", "Fitness"
+        );
+        for layout in front {
+            let metrics = &layout.metrics;
+            println!(
+                "  {:>5} {:>10} {:>10.3} {:>12}",
+                metrics.sgc, metrics.ncmc, metrics.overlap, metrics.fitness
+            );
         }
     }
-
-    // Save and print results
-    let sgc_value = sgc(&best_mesh_routers);
-    let ncmc_value = ncmc(&best_mesh_routers, &mesh_clients);
-    let ncmcpr_value = ncmcpr(&best_mesh_routers, &mesh_clients);
-    save_results(&best_mesh_routers, &mesh_clients, best_fitness, sgc_value, ncmc_value, ncmcpr_value);
-
-    println!("Final Fitness Score: {}", best_fitness);
-    println!("Results saved to firefly_results.json");
+    if let Some(search_fidelity) = &report.search_fidelity {
+        search_fidelity.print(
+            report
+                .snapping
+                .as_ref()
+                .map_or(&report.metrics, |s| &s.unsnapped),
+        );
+    }
+    if let Some(snapping) = &report.snapping {
+        snapping.print(&report.metrics);
+    }
+    for warning in &report.warnings {
+        println!("Warning: {}", warning);
+    }
+    report.timings.print();
+    println!("Results saved to {}", options.output);
+    if let Some(path) = &options.plot {
+        solution.plot().save(path).expect("Unable to write plot");
+        println!("Plot saved to {}", path);
+    }
 }
 
 // Main Function
 fn main() {
-    firefly_algorithm();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Benchmark(args)) => commands::benchmark::benchmark_command(args),
+        Some(Command::Calibrate(args)) => commands::calibrate::calibrate_command(args),
+        Some(Command::Consensus(args)) => commands::consensus::consensus_command(args),
+        Some(Command::Daemon(args)) => commands::daemon::daemon_command(args),
+        Some(Command::Import(args)) => commands::import::import_command(args),
+        Some(Command::Literature(args)) => commands::literature::literature_command(args),
+        Some(Command::Migrate(args)) => commands::migrate::migrate_command(args),
+        Some(Command::Power(args)) => commands::power::power_command(args),
+        Some(Command::Probe(args)) => commands::probe::probe_command(args),
+        Some(Command::Relocate(args)) => commands::relocate::relocate_command(args),
+        Some(Command::Render(args)) => commands::render::render_command(args),
+        Some(Command::Sleep(args)) => commands::sleep::sleep_command(args),
+        Some(Command::Stats(args)) => commands::stats::stats_command(args),
+        Some(Command::Timeline(args)) => commands::timeline::timeline_command(args),
+        None => run(&cli.run),
+    }
 }
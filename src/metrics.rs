@@ -0,0 +1,436 @@
+use crate::clients::ClientSet;
+use crate::plugin::ObjectivePlugin;
+use crate::radio::Radio;
+use crate::spatial::SpatialGrid;
+use crate::{DIMENSIONS, FireflyConfig};
+use rand::Rng;
+use rayon::prelude::*;
+use serde_json::json;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Seeds sampled by the first approximate SGC level
+pub const APPROX_SGC_SEEDS: usize = 8;
+
+// Fewest routers or clients handed to one parallel task; smaller networks run sequentially
+const PARALLEL_MIN_LEN: usize = 64;
+
+/// Euclidean distance between two points
+pub fn distance(x: &[f64], y: &[f64]) -> f64 {
+    // Squared by multiplication, which unlike powi is correctly rounded everywhere
+    x.iter()
+        .zip(y.iter())
+        .map(|(xi, yi)| (xi - yi) * (xi - yi))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Router adjacency built once per fitness evaluation and shared by the graph metrics
+pub struct ConnectivityGraph {
+    neighbors: Vec<Vec<usize>>,
+}
+
+impl ConnectivityGraph {
+    /// Connect every pair of routers within each other's radius without a wall between
+    pub fn new(routers: &[[f64; DIMENSIONS]], radio: &Radio) -> ConnectivityGraph {
+        let grid = SpatialGrid::new(routers, radio.max_radius(routers.len()));
+        let neighbors = routers
+            .par_iter()
+            .with_min_len(PARALLEL_MIN_LEN)
+            .enumerate()
+            .map(|(i, router)| {
+                let mut neighbors = grid.within(router);
+                neighbors.retain(|&j| {
+                    j != i
+                        && radio.linked(i, j, distance(router, &routers[j]))
+                        && !radio.blocked(router, &routers[j])
+                });
+                neighbors
+            })
+            .collect();
+        ConnectivityGraph { neighbors }
+    }
+
+    pub fn len(&self) -> usize {
+        self.neighbors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.neighbors.is_empty()
+    }
+
+    /// Routers linked to `router`
+    pub fn neighbors(&self, router: usize) -> &[usize] {
+        &self.neighbors[router]
+    }
+
+    /// Number of connected components, isolated routers counting as one each
+    pub fn components(&self) -> usize {
+        let mut visited = vec![false; self.len()];
+        (0..self.len())
+            .filter(|&start| {
+                let unvisited = !visited[start];
+                if unvisited {
+                    self.component_size(start, &mut visited);
+                }
+                unvisited
+            })
+            .count()
+    }
+
+    // Breadth-first search marking the component containing `start`, returning its size
+    fn component_size(&self, start: usize, visited: &mut [bool]) -> usize {
+        let mut queue = VecDeque::new();
+        queue.push_back(start);
+        visited[start] = true;
+        let mut component_size = 1;
+
+        while let Some(current) = queue.pop_front() {
+            for &i in &self.neighbors[current] {
+                if !visited[i] {
+                    visited[i] = true;
+                    queue.push_back(i);
+                    component_size += 1;
+                }
+            }
+        }
+        component_size
+    }
+}
+
+/// Size of Giant Component (SGC)
+pub fn sgc(graph: &ConnectivityGraph) -> usize {
+    let mut largest_component = 0;
+    let mut visited = vec![false; graph.len()];
+
+    for start in 0..graph.len() {
+        if !visited[start] {
+            largest_component = largest_component.max(graph.component_size(start, &mut visited));
+        }
+    }
+    largest_component
+}
+
+/// Estimate SGC by searching only from a random sample of seed routers
+pub fn approx_sgc(graph: &ConnectivityGraph, seeds: usize, rng: &mut impl Rng) -> usize {
+    let mut largest_component = 0;
+    let mut visited = vec![false; graph.len()];
+    let mut unvisited = graph.len();
+
+    for start in rand::seq::index::sample(rng, graph.len(), seeds.min(graph.len())) {
+        if !visited[start] {
+            let size = graph.component_size(start, &mut visited);
+            unvisited -= size;
+            largest_component = largest_component.max(size);
+        }
+        // Stop once no unexplored component could be larger
+        if largest_component * 2 > graph.len() || largest_component >= unvisited {
+            break;
+        }
+    }
+    largest_component
+}
+
+/// How SGC is computed during the search
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SgcMode {
+    Exact,
+    Sampled { seeds: usize },
+}
+
+impl SgcMode {
+    /// Next lower fidelity, or None if already at the lowest
+    pub fn reduced(self) -> Option<SgcMode> {
+        match self {
+            SgcMode::Exact => Some(SgcMode::Sampled {
+                seeds: APPROX_SGC_SEEDS,
+            }),
+            SgcMode::Sampled { seeds } if seeds > 1 => Some(SgcMode::Sampled { seeds: seeds / 2 }),
+            SgcMode::Sampled { .. } => None,
+        }
+    }
+
+    pub fn describe(self) -> String {
+        match self {
+            SgcMode::Exact => "exact SGC".to_string(),
+            SgcMode::Sampled { seeds } => format!("sampled SGC ({} seeds)", seeds),
+        }
+    }
+}
+
+/// Number of Covered Mesh Clients (NCMC), counting every covered client with its weight
+pub fn ncmc(routers: &[[f64; DIMENSIONS]], clients: &ClientSet, radio: &Radio) -> f64 {
+    worst_case_ncmc(routers, clients, radio, 0.0)
+}
+
+/// NCMC counting only the clients that stay within one router's coverage wherever they
+/// move up to `shift` from their position; walls are checked from the position itself
+pub fn worst_case_ncmc(
+    routers: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    radio: &Radio,
+    shift: f64,
+) -> f64 {
+    let grid = SpatialGrid::new(routers, radio.max_coverage_radius(routers.len()));
+    let covered: Vec<bool> = clients
+        .positions
+        .par_iter()
+        .with_min_len(PARALLEL_MIN_LEN)
+        .map(|client| {
+            grid.candidates(client).any(|i| {
+                radio.covers(i, distance(&routers[i], client) + shift)
+                    && !radio.blocked(&routers[i], client)
+            })
+        })
+        .collect();
+    // Summed in client order so the result does not depend on the thread count
+    covered
+        .iter()
+        .zip(&clients.weights)
+        .filter(|&(&covered, _)| covered)
+        .fold(0.0, |total, (_, weight)| total + weight)
+}
+
+/// Client weight within the coverage of every router, clients covered by several
+/// routers counting for each of them
+pub fn router_coverage(
+    routers: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    radio: &Radio,
+) -> Vec<f64> {
+    let grid = SpatialGrid::new(routers, radio.max_coverage_radius(routers.len()));
+    let mut coverage = vec![0.0; routers.len()];
+    for (client, weight) in clients.positions.iter().zip(&clients.weights) {
+        for i in grid.candidates(client) {
+            if radio.covers(i, distance(&routers[i], client)) && !radio.blocked(&routers[i], client)
+            {
+                coverage[i] += weight;
+            }
+        }
+    }
+    coverage
+}
+
+/// Number of Covered Mesh Clients per Router (NCMCpR)
+///
+/// Computes NCMC first; divide an NCMC already at hand by the router count instead.
+pub fn ncmcpr(routers: &[[f64; DIMENSIONS]], clients: &ClientSet, radio: &Radio) -> f64 {
+    ncmc(routers, clients, radio) / routers.len() as f64
+}
+
+/// Total pairwise overlap of the routers' coverage disks, in units of the area of a
+/// disk with the communication distance as radius
+pub fn coverage_overlap(routers: &[[f64; DIMENSIONS]], radio: &Radio) -> f64 {
+    overlap_between(routers, radio, |_, _| true)
+}
+
+/// Total pairwise overlap of the coverage disks of routers on the same channel, in the
+/// units of [`coverage_overlap`]; `channels` holds the channel of every router
+pub fn co_channel_overlap(routers: &[[f64; DIMENSIONS]], radio: &Radio, channels: &[usize]) -> f64 {
+    overlap_between(routers, radio, |i, j| channels[i] == channels[j])
+}
+
+// Coverage overlap summed over the router pairs `counts` accepts
+fn overlap_between(
+    routers: &[[f64; DIMENSIONS]],
+    radio: &Radio,
+    counts: impl Fn(usize, usize) -> bool + Sync,
+) -> f64 {
+    let radius = radio.base_radius();
+    let disk_area = std::f64::consts::PI * radius * radius;
+    let grid = SpatialGrid::new(routers, 2.0 * radio.max_coverage_radius(routers.len()));
+    let lenses: Vec<Vec<f64>> = routers
+        .par_iter()
+        .with_min_len(PARALLEL_MIN_LEN)
+        .enumerate()
+        .map(|(i, router)| {
+            grid.within(router)
+                .into_iter()
+                .filter(|&j| j > i && counts(i, j))
+                .map(|j| {
+                    lens_area(
+                        distance(router, &routers[j]),
+                        radio.coverage_radius(i),
+                        radio.coverage_radius(j),
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    // Summed pair by pair in order so the result does not depend on the thread count
+    let overlap = lenses
+        .iter()
+        .flatten()
+        .fold(0.0, |total, lens| total + lens);
+    overlap / disk_area
+}
+
+// Area where two disks of radii `a` and `b` with centers `d` apart intersect
+fn lens_area(d: f64, a: f64, b: f64) -> f64 {
+    if d >= a + b {
+        return 0.0;
+    }
+    if a == b {
+        return 2.0 * a * a * (d / (2.0 * a)).acos() - d / 2.0 * (4.0 * a * a - d * d).sqrt();
+    }
+    if d <= (a - b).abs() {
+        return std::f64::consts::PI * a.min(b) * a.min(b);
+    }
+    a * a * ((d * d + a * a - b * b) / (2.0 * d * a)).acos()
+        + b * b * ((d * d + b * b - a * a) / (2.0 * d * b)).acos()
+        - 0.5 * ((-d + a + b) * (d + a - b) * (d - a + b) * (d + a + b)).sqrt()
+}
+
+/// Pairs of routers closer to each other than `min_separation`
+pub fn separation_violations(routers: &[[f64; DIMENSIONS]], min_separation: f64) -> usize {
+    let grid = SpatialGrid::new(routers, min_separation);
+    routers
+        .par_iter()
+        .with_min_len(PARALLEL_MIN_LEN)
+        .enumerate()
+        .map(|(i, router)| {
+            grid.within(router)
+                .into_iter()
+                .filter(|&j| j > i && distance(router, &routers[j]) < min_separation)
+                .count()
+        })
+        .sum()
+}
+
+/// Time spent in each fitness component over a run
+#[derive(Default)]
+pub struct MetricTimings {
+    pub evaluations: usize,
+    pub graph: Duration,
+    pub sgc: Duration,
+    pub ncmc: Duration,
+    pub ncmcpr: Duration,
+    pub overlap: Duration,
+    pub plugin: Duration,
+}
+
+impl MetricTimings {
+    pub fn total(&self) -> Duration {
+        self.graph + self.sgc + self.ncmc + self.ncmcpr + self.overlap + self.plugin
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "evaluations": self.evaluations,
+            "graph_seconds": self.graph.as_secs_f64(),
+            "sgc_seconds": self.sgc.as_secs_f64(),
+            "ncmc_seconds": self.ncmc.as_secs_f64(),
+            "ncmcpr_seconds": self.ncmcpr.as_secs_f64(),
+            "overlap_seconds": self.overlap.as_secs_f64(),
+            "plugin_seconds": self.plugin.as_secs_f64(),
+            "total_seconds": self.total().as_secs_f64()
+        })
+    }
+
+    pub fn print(&self) {
+        let total = self.total().as_secs_f64().max(f64::EPSILON);
+        println!("Metric timings over {} evaluations:", self.evaluations);
+        for (name, elapsed) in [
+            ("Graph", self.graph),
+            ("SGC", self.sgc),
+            ("NCMC", self.ncmc),
+            ("NCMCpR", self.ncmcpr),
+            ("Overlap", self.overlap),
+            ("Plugin", self.plugin),
+        ] {
+            println!(
+                "  {:<7} {:>10.3} ms ({:>5.1}%)",
+                name,
+                elapsed.as_secs_f64() * 1000.0,
+                elapsed.as_secs_f64() / total * 100.0
+            );
+        }
+    }
+}
+
+/// Run a metric and add its elapsed time to the given counter
+pub fn timed<T>(elapsed: &mut Duration, metric: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let value = metric();
+    *elapsed += start.elapsed();
+    value
+}
+
+/// Exact metrics of a final layout
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    pub fitness: f64,
+    pub sgc: usize,
+    /// Weighted number of covered clients
+    pub ncmc: f64,
+    pub ncmcpr: f64,
+    pub overlap: f64,
+    /// Router pairs closer than the minimum separation, when one is set
+    pub separation_violations: Option<usize>,
+    /// Mean router distance from the relocation anchor, when one is set
+    pub relocation_distance: Option<f64>,
+}
+
+impl Metrics {
+    pub fn evaluate(
+        routers: &[[f64; DIMENSIONS]],
+        clients: &ClientSet,
+        config: &FireflyConfig,
+        plugin: Option<&ObjectivePlugin>,
+    ) -> Metrics {
+        let radio = Radio::new(config);
+        let sgc = sgc(&ConnectivityGraph::new(routers, &radio));
+        let ncmc = ncmc(routers, clients, &radio);
+        let ncmcpr = ncmc / routers.len() as f64;
+        let overlap = coverage_overlap(routers, &radio);
+        let min_separation = config.radio.min_separation;
+        let separation_violations =
+            (min_separation > 0.0).then(|| separation_violations(routers, min_separation));
+        let relocation_distance = config
+            .relocation
+            .as_ref()
+            .map(|relocation| relocation.displacement(routers));
+        let fitness = match plugin {
+            Some(plugin) => plugin.evaluate(routers, &clients.positions),
+            None => {
+                let coverage = clients.coverage_share(ncmc);
+                config.service_fitness(sgc as f64, ncmc, ncmcpr, coverage, overlap)
+                    - config.radio.penalty(separation_violations.unwrap_or(0))
+                    - config
+                        .relocation
+                        .as_ref()
+                        .zip(relocation_distance)
+                        .map_or(0.0, |(relocation, distance)| relocation.cost(distance))
+            }
+        };
+        Metrics {
+            fitness,
+            sgc,
+            ncmc,
+            ncmcpr,
+            overlap,
+            separation_violations,
+            relocation_distance,
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut data = json!({
+            "best_fitness": self.fitness,
+            "sgc": self.sgc,
+            "ncmc": self.ncmc,
+            "ncmcpr": self.ncmcpr,
+            "coverage_overlap": self.overlap
+        });
+        if let Some(violations) = self.separation_violations {
+            data["separation_violations"] = json!(violations);
+        }
+        if let Some(distance) = self.relocation_distance {
+            data["relocation_distance"] = json!(distance);
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+mod reference;
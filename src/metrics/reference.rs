@@ -0,0 +1,383 @@
+// Slow, obviously correct versions of the metrics, checked against the spatial-grid,
+// breadth-first and parallel versions on random layouts. The references only share
+// the radio's definitions of a link, of coverage and of a blocking wall.
+
+use super::*;
+use crate::layout_distance::match_layouts;
+use crate::radio::{PathLoss, RadioModel, Wall};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+
+// Random instances per check
+const CASES: usize = 200;
+
+// A random network: routers, weighted clients and a radio model with per-router radii,
+// walls and path-loss coverage mixed in
+struct Case {
+    routers: Vec<[f64; DIMENSIONS]>,
+    clients: ClientSet,
+    radius: f64,
+    model: RadioModel,
+}
+
+impl Case {
+    fn random(rng: &mut StdRng) -> Case {
+        let side = rng.gen_range(5.0..40.0);
+        // Past the parallel threshold now and then
+        let routers = if rng.gen_bool(0.1) {
+            rng.gen_range(PARALLEL_MIN_LEN..3 * PARALLEL_MIN_LEN)
+        } else {
+            rng.gen_range(1..40)
+        };
+        let clients = rng.gen_range(0..80);
+        let mut point = || std::array::from_fn(|_| rng.gen_range(0.0..side));
+        let routers: Vec<[f64; DIMENSIONS]> = (0..routers).map(|_| point()).collect();
+        let positions: Vec<[f64; DIMENSIONS]> = (0..clients).map(|_| point()).collect();
+        let weights = positions.iter().map(|_| rng.gen_range(0.0..3.0)).collect();
+        let clients = ClientSet::with_weights(positions, weights).unwrap();
+
+        let radius = rng.gen_range(0.5..8.0);
+        let mut model = RadioModel::default();
+        if rng.gen_bool(0.3) {
+            // Fewer radii than routers, so that some fall back to the radius
+            model.radii = (0..rng.gen_range(0..routers.len() + 1))
+                .map(|_| rng.gen_range(0.5..8.0))
+                .collect();
+        }
+        if rng.gen_bool(0.3) {
+            model.walls = (0..rng.gen_range(1..4))
+                .map(|_| Wall::from_ends(std::array::from_fn(|_| rng.gen_range(0.0..side))))
+                .collect();
+        }
+        if rng.gen_bool(0.2) {
+            model.path_loss = Some(PathLoss {
+                exponent: rng.gen_range(2.0..4.0),
+                ..PathLoss::default()
+            });
+        }
+        Case {
+            routers,
+            clients,
+            radius,
+            model,
+        }
+    }
+
+    fn radio(&self) -> Radio<'_> {
+        Radio::with_model(self.radius, &self.model)
+    }
+}
+
+fn cases() -> impl Iterator<Item = Case> {
+    let mut rng = StdRng::seed_from_u64(1030);
+    (0..CASES).map(move |_| Case::random(&mut rng))
+}
+
+fn assert_close(actual: f64, expected: f64, tolerance: f64, what: &str) {
+    assert!(
+        (actual - expected).abs() <= tolerance * expected.abs().max(1.0),
+        "{}: {} but the reference gives {}",
+        what,
+        actual,
+        expected
+    );
+}
+
+// Links of every router pair
+fn reference_links(routers: &[[f64; DIMENSIONS]], radio: &Radio) -> Vec<Vec<bool>> {
+    (0..routers.len())
+        .map(|i| {
+            (0..routers.len())
+                .map(|j| {
+                    i != j
+                        && radio.linked(i, j, distance(&routers[i], &routers[j]))
+                        && !radio.blocked(&routers[i], &routers[j])
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Which routers reach which, by Warshall's transitive closure
+fn reference_reach(routers: &[[f64; DIMENSIONS]], radio: &Radio) -> Vec<Vec<bool>> {
+    let mut reach = reference_links(routers, radio);
+    for (i, row) in reach.iter_mut().enumerate() {
+        row[i] = true;
+    }
+    for k in 0..routers.len() {
+        for i in 0..routers.len() {
+            for j in 0..routers.len() {
+                if reach[i][k] && reach[k][j] {
+                    reach[i][j] = true;
+                }
+            }
+        }
+    }
+    reach
+}
+
+fn reference_sgc(reach: &[Vec<bool>]) -> usize {
+    reach
+        .iter()
+        .map(|row| row.iter().filter(|&&reached| reached).count())
+        .max()
+        .unwrap_or(0)
+}
+
+// Every component counted once, by its lowest router
+fn reference_components(reach: &[Vec<bool>]) -> usize {
+    (0..reach.len())
+        .filter(|&i| (0..i).all(|j| !reach[i][j]))
+        .count()
+}
+
+fn reference_ncmc(
+    routers: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    radio: &Radio,
+    shift: f64,
+) -> f64 {
+    let mut covered = 0.0;
+    for (client, weight) in clients.positions.iter().zip(&clients.weights) {
+        if (0..routers.len()).any(|i| {
+            radio.covers(i, distance(&routers[i], client) + shift)
+                && !radio.blocked(&routers[i], client)
+        }) {
+            covered += weight;
+        }
+    }
+    covered
+}
+
+fn reference_router_coverage(
+    routers: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    radio: &Radio,
+) -> Vec<f64> {
+    (0..routers.len())
+        .map(|i| {
+            let mut coverage = 0.0;
+            for (client, weight) in clients.positions.iter().zip(&clients.weights) {
+                if radio.covers(i, distance(&routers[i], client))
+                    && !radio.blocked(&routers[i], client)
+                {
+                    coverage += weight;
+                }
+            }
+            coverage
+        })
+        .collect()
+}
+
+// Area shared by two disks, integrated over vertical slices with the midpoint rule
+fn reference_lens_area(a: &[f64; DIMENSIONS], ra: f64, b: &[f64; DIMENSIONS], rb: f64) -> f64 {
+    const SLICES: usize = 4000;
+    let left = (a[0] - ra).max(b[0] - rb);
+    let right = (a[0] + ra).min(b[0] + rb);
+    if right <= left {
+        return 0.0;
+    }
+    let width = (right - left) / SLICES as f64;
+    let chord = |center: &[f64; DIMENSIONS], radius: f64, x: f64| {
+        let half = (radius * radius - (x - center[0]).powi(2)).max(0.0).sqrt();
+        (center[1] - half, center[1] + half)
+    };
+    (0..SLICES)
+        .map(|slice| {
+            let x = left + (slice as f64 + 0.5) * width;
+            let (a_low, a_high) = chord(a, ra, x);
+            let (b_low, b_high) = chord(b, rb, x);
+            (a_high.min(b_high) - a_low.max(b_low)).max(0.0) * width
+        })
+        .sum()
+}
+
+fn reference_overlap(
+    routers: &[[f64; DIMENSIONS]],
+    radio: &Radio,
+    counts: impl Fn(usize, usize) -> bool,
+) -> f64 {
+    let mut overlap = 0.0;
+    for i in 0..routers.len() {
+        for j in i + 1..routers.len() {
+            if counts(i, j) {
+                overlap += reference_lens_area(
+                    &routers[i],
+                    radio.coverage_radius(i),
+                    &routers[j],
+                    radio.coverage_radius(j),
+                );
+            }
+        }
+    }
+    overlap / (std::f64::consts::PI * radio.base_radius().powi(2))
+}
+
+fn reference_separation_violations(routers: &[[f64; DIMENSIONS]], min_separation: f64) -> usize {
+    let mut violations = 0;
+    for i in 0..routers.len() {
+        for j in i + 1..routers.len() {
+            if distance(&routers[i], &routers[j]) < min_separation {
+                violations += 1;
+            }
+        }
+    }
+    violations
+}
+
+// Smallest total distance over every assignment of the routers of `a` to those of `b`
+fn reference_matching_distance(a: &[[f64; DIMENSIONS]], b: &[[f64; DIMENSIONS]]) -> f64 {
+    fn search(a: &[[f64; DIMENSIONS]], b: &[[f64; DIMENSIONS]], used: &mut [bool]) -> f64 {
+        let Some(router) = a.first() else {
+            return 0.0;
+        };
+        let mut best = f64::INFINITY;
+        for j in 0..b.len() {
+            if !used[j] {
+                used[j] = true;
+                best = best.min(distance(router, &b[j]) + search(&a[1..], b, used));
+                used[j] = false;
+            }
+        }
+        best
+    }
+    search(a, b, &mut vec![false; b.len()])
+}
+
+#[test]
+fn connectivity_graph_links_the_reference_pairs() {
+    for case in cases() {
+        let radio = case.radio();
+        let graph = ConnectivityGraph::new(&case.routers, &radio);
+        let links = reference_links(&case.routers, &radio);
+        for (i, row) in links.iter().enumerate() {
+            let mut neighbors = graph.neighbors(i).to_vec();
+            neighbors.sort_unstable();
+            let expected: Vec<usize> = (0..row.len()).filter(|&j| row[j]).collect();
+            assert_eq!(neighbors, expected, "neighbors of router {}", i);
+        }
+    }
+}
+
+#[test]
+fn sgc_and_components_match_the_transitive_closure() {
+    let mut rng = StdRng::seed_from_u64(1031);
+    for case in cases() {
+        let radio = case.radio();
+        let graph = ConnectivityGraph::new(&case.routers, &radio);
+        let reach = reference_reach(&case.routers, &radio);
+        let expected = reference_sgc(&reach);
+        assert_eq!(sgc(&graph), expected, "SGC");
+        assert_eq!(
+            graph.components(),
+            reference_components(&reach),
+            "components"
+        );
+        // Searching from every router is exact; fewer seeds never overestimate
+        assert_eq!(
+            approx_sgc(&graph, graph.len(), &mut rng),
+            expected,
+            "SGC from every seed"
+        );
+        let seeds = rng.gen_range(1..=graph.len());
+        assert!(
+            approx_sgc(&graph, seeds, &mut rng) <= expected,
+            "sampled SGC"
+        );
+    }
+}
+
+#[test]
+fn ncmc_matches_a_scan_of_every_router() {
+    let mut rng = StdRng::seed_from_u64(1032);
+    for case in cases() {
+        let radio = case.radio();
+        let expected = reference_ncmc(&case.routers, &case.clients, &radio, 0.0);
+        assert_close(
+            ncmc(&case.routers, &case.clients, &radio),
+            expected,
+            1e-12,
+            "NCMC",
+        );
+        assert_close(
+            ncmcpr(&case.routers, &case.clients, &radio),
+            expected / case.routers.len() as f64,
+            1e-12,
+            "NCMCpR",
+        );
+        let shift = rng.gen_range(0.0..2.0);
+        assert_close(
+            worst_case_ncmc(&case.routers, &case.clients, &radio, shift),
+            reference_ncmc(&case.routers, &case.clients, &radio, shift),
+            1e-12,
+            "worst-case NCMC",
+        );
+    }
+}
+
+#[test]
+fn router_coverage_matches_a_scan_of_every_client() {
+    for case in cases() {
+        let radio = case.radio();
+        let coverage = router_coverage(&case.routers, &case.clients, &radio);
+        let expected = reference_router_coverage(&case.routers, &case.clients, &radio);
+        for (actual, expected) in coverage.iter().zip(&expected) {
+            assert_close(*actual, *expected, 1e-12, "router coverage");
+        }
+    }
+}
+
+#[test]
+fn overlap_matches_numerically_integrated_lenses() {
+    let mut rng = StdRng::seed_from_u64(1033);
+    // Integration is slow, so fewer and smaller cases
+    for case in cases().filter(|case| case.routers.len() < 20).take(40) {
+        let radio = case.radio();
+        assert_close(
+            coverage_overlap(&case.routers, &radio),
+            reference_overlap(&case.routers, &radio, |_, _| true),
+            1e-4,
+            "coverage overlap",
+        );
+        let channels: Vec<usize> = case.routers.iter().map(|_| rng.gen_range(0..3)).collect();
+        assert_close(
+            co_channel_overlap(&case.routers, &radio, &channels),
+            reference_overlap(&case.routers, &radio, |i, j| channels[i] == channels[j]),
+            1e-4,
+            "co-channel overlap",
+        );
+    }
+}
+
+#[test]
+fn separation_violations_match_every_pair() {
+    let mut rng = StdRng::seed_from_u64(1034);
+    for case in cases() {
+        let min_separation = rng.gen_range(0.0..5.0);
+        assert_eq!(
+            separation_violations(&case.routers, min_separation),
+            reference_separation_violations(&case.routers, min_separation),
+            "separation violations"
+        );
+    }
+}
+
+#[test]
+fn layout_matching_finds_the_best_permutation() {
+    let mut rng = StdRng::seed_from_u64(1035);
+    for _ in 0..CASES {
+        let routers = rng.gen_range(0..7);
+        let mut layout = || -> Vec<[f64; DIMENSIONS]> {
+            (0..routers)
+                .map(|_| std::array::from_fn(|_| rng.gen_range(0.0..20.0)))
+                .collect()
+        };
+        let (a, b) = (layout(), layout());
+        assert_close(
+            match_layouts(&a, &b).total_distance,
+            reference_matching_distance(&a, &b),
+            1e-9,
+            "matched distance",
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use serde_json::{Value, json};
+
+/// Version written into every result file by this build
+pub const SCHEMA_VERSION: u64 = 8;
+
+// Upgrade steps, where MIGRATIONS[n] turns a version n + 1 file into version n + 2
+const MIGRATIONS: [fn(&mut Value); 7] = [
+    v1_to_v2, v2_to_v3, v3_to_v4, v4_to_v5, v5_to_v6, v6_to_v7, v7_to_v8,
+];
+
+// Files written before versioning had no run metadata
+fn v1_to_v2(data: &mut Value) {
+    if data.get("run").is_none() {
+        data["run"] = json!({ "name": null, "tags": {} });
+    }
+}
+
+// Version 3 added run warnings and the watchdog's fidelity changes
+fn v2_to_v3(data: &mut Value) {
+    for key in ["warnings", "fidelity_changes"] {
+        if data.get(key).is_none() {
+            data[key] = json!([]);
+        }
+    }
+}
+
+// Version 4 added the search-fidelity score of runs that used approximate metrics
+fn v3_to_v4(data: &mut Value) {
+    if data.get("search_fidelity").is_none() {
+        data["search_fidelity"] = Value::Null;
+    }
+}
+
+// Version 5 added the coverage overlap of the best layout
+fn v4_to_v5(data: &mut Value) {
+    if data.get("coverage_overlap").is_none() {
+        data["coverage_overlap"] = Value::Null;
+    }
+}
+
+// Version 6 added client weights, which were all 1 before, and the client file of the run
+fn v5_to_v6(data: &mut Value) {
+    if data.get("mesh_client_weights").is_none() {
+        let clients = data["mesh_clients"].as_array().map_or(0, Vec::len);
+        data["mesh_client_weights"] = json!(vec![1.0; clients]);
+    }
+    if data["run"].get("clients_file").is_none() {
+        data["run"]["clients_file"] = Value::Null;
+    }
+}
+
+// Version 7 added the coverage lists, which need the run's communication distance to rebuild
+fn v6_to_v7(data: &mut Value) {
+    for key in ["router_clients", "client_routers"] {
+        if data.get(key).is_none() {
+            data[key] = Value::Null;
+        }
+    }
+}
+
+// Version 8 recorded the seed of reproducible runs
+fn v7_to_v8(data: &mut Value) {
+    if data["run"].get("seed").is_none() {
+        data["run"]["seed"] = Value::Null;
+    }
+}
+
+// Schema version of a result file, treating unversioned files as version 1
+fn schema_version(data: &Value) -> Result<u64, String> {
+    match data.get("schema_version") {
+        None => Ok(1),
+        Some(version) => version
+            .as_u64()
+            .filter(|&version| version >= 1)
+            .ok_or_else(|| format!("Invalid schema_version: {}", version)),
+    }
+}
+
+/// Upgrade a result file to the current schema version
+pub fn migrate(data: &mut Value) -> Result<u64, String> {
+    if !data.is_object() {
+        return Err("Result file is not a JSON object".to_string());
+    }
+    let version = schema_version(data)?;
+    if version > SCHEMA_VERSION {
+        return Err(format!(
+            "Result file has schema version {}, newer than the supported version {}",
+            version, SCHEMA_VERSION
+        ));
+    }
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        migration(data);
+    }
+    data["schema_version"] = json!(SCHEMA_VERSION);
+    Ok(version)
+}
@@ -0,0 +1,331 @@
+//! MQTT telemetry subscriptions and plan publishing, for home-lab and community-mesh
+//! automation stacks. Only built with the `mqtt` feature.
+//!
+//! Topics are named by `mqtt://host[:port]/topic` URLs. A [`Subscription`] keeps
+//! parsing the JSON messages of its topic on a background thread, and an
+//! [`MqttPublisher`] publishes retained plans and per-router recommendations.
+
+use crate::radio::Radio;
+use crate::solution::routers_from_json;
+use crate::{ClientSet, DIMENSIONS, Solution, distance};
+use rumqttc::{Client, Connection, Event, MqttOptions, Packet, QoS};
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+/// Port of MQTT URLs without one
+pub const DEFAULT_PORT: u16 = 1883;
+// Largest message sent or received; plans of large networks exceed the default 10 kB
+const MAX_PACKET_SIZE: usize = 16 * 1024 * 1024;
+// Pause between reconnection attempts
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+// Time allowed for the broker to acknowledge published messages
+const ACK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Broker and topic of an `mqtt://host[:port]/topic` URL
+#[derive(Clone, Debug, PartialEq)]
+pub struct MqttTopic {
+    pub host: String,
+    pub port: u16,
+    pub topic: String,
+}
+
+impl MqttTopic {
+    /// The broker and topic of an MQTT URL, None for anything but an `mqtt://` URL
+    ///
+    /// ```
+    /// use ff_wmn::mqtt::MqttTopic;
+    ///
+    /// let topic = MqttTopic::parse("mqtt://broker:1884/mesh/clients").unwrap().unwrap();
+    /// assert_eq!((topic.host.as_str(), topic.port), ("broker", 1884));
+    /// assert_eq!(topic.topic, "mesh/clients");
+    /// assert_eq!(MqttTopic::parse("clients.csv"), Ok(None));
+    /// ```
+    pub fn parse(url: &str) -> Result<Option<MqttTopic>, String> {
+        let Some(rest) = url.strip_prefix("mqtt://") else {
+            return Ok(None);
+        };
+        let (authority, topic) = rest
+            .split_once('/')
+            .ok_or_else(|| format!("No topic in {}", url))?;
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                port.parse()
+                    .map_err(|_| format!("Invalid port in {}", url))?,
+            ),
+            None => (authority, DEFAULT_PORT),
+        };
+        if host.is_empty() {
+            return Err(format!("No host in {}", url));
+        }
+        if topic.is_empty() {
+            return Err(format!("No topic in {}", url));
+        }
+        Ok(Some(MqttTopic {
+            host: host.to_string(),
+            port,
+            topic: topic.to_string(),
+        }))
+    }
+
+    // Connection options with a client ID of its own, brokers drop the older of two
+    // connections sharing an ID
+    fn options(&self) -> MqttOptions {
+        static CONNECTIONS: AtomicUsize = AtomicUsize::new(0);
+        let id = format!(
+            "ff-wmn-{}-{}",
+            std::process::id(),
+            CONNECTIONS.fetch_add(1, Ordering::Relaxed)
+        );
+        let mut options = MqttOptions::new(id, &self.host, self.port);
+        options
+            .set_keep_alive(Duration::from_secs(30))
+            .set_max_packet_size(MAX_PACKET_SIZE, MAX_PACKET_SIZE);
+        options
+    }
+}
+
+impl std::fmt::Display for MqttTopic {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "mqtt://{}:{}/{}", self.host, self.port, self.topic)
+    }
+}
+
+/// Latest messages of a topic, parsed on a background thread that reconnects to the
+/// broker whenever the connection drops.
+///
+/// Messages that are not valid JSON or that `parse` rejects are reported on standard
+/// error and skipped. The thread ends with the first message after the subscription
+/// is dropped.
+pub struct Subscription<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T: Send + 'static> Subscription<T> {
+    /// Subscribe to `topic`, turning every message into a `T` with `parse`
+    pub fn new(topic: &MqttTopic, parse: fn(&Value) -> Result<T, String>) -> Subscription<T> {
+        let (client, connection) = Client::new(topic.options(), 16);
+        let (sender, receiver) = mpsc::channel();
+        let topic = topic.clone();
+        std::thread::spawn(move || listen(client, connection, topic, parse, sender));
+        Subscription { receiver }
+    }
+
+    /// The latest message since the previous call, None if none arrived
+    pub fn latest(&self) -> Option<T> {
+        self.receiver.try_iter().last()
+    }
+
+    /// The latest message, waiting up to `timeout` for one if none arrived since the
+    /// previous call
+    pub fn wait(&self, timeout: Duration) -> Option<T> {
+        self.latest()
+            .or_else(|| self.receiver.recv_timeout(timeout).ok())
+            .map(|message| self.latest().unwrap_or(message))
+    }
+
+    /// The parsed messages as a channel, e.g. for
+    /// [`StreamingObjective::from_channel`](crate::objective::StreamingObjective::from_channel)
+    pub fn into_receiver(self) -> Receiver<T> {
+        self.receiver
+    }
+}
+
+/// Subscription to client telemetry: every message is a client set in any of the
+/// JSON forms of [`ClientSet::from_value`]
+pub fn subscribe_clients(topic: &MqttTopic) -> Subscription<ClientSet> {
+    Subscription::new(topic, ClientSet::from_value)
+}
+
+/// Subscription to router telemetry: every message is the installed layout, either
+/// an array of positions or an object with `mesh_routers` like a result file
+pub fn subscribe_routers(topic: &MqttTopic) -> Subscription<Vec<[f64; DIMENSIONS]>> {
+    Subscription::new(topic, routers_from_json)
+}
+
+// Drive the connection of a subscription until its receiver is gone
+fn listen<T>(
+    client: Client,
+    mut connection: Connection,
+    topic: MqttTopic,
+    parse: fn(&Value) -> Result<T, String>,
+    sender: Sender<T>,
+) {
+    // Report a lost connection once rather than on every reconnection attempt
+    let mut connected = true;
+    for event in connection.iter() {
+        match event {
+            Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                connected = true;
+                // Subscribe again after every reconnection, the broker may have
+                // forgotten the session
+                if client.subscribe(&topic.topic, QoS::AtLeastOnce).is_err() {
+                    return;
+                }
+            }
+            Ok(Event::Incoming(Packet::Publish(message))) => {
+                let parsed = serde_json::from_slice(&message.payload)
+                    .map_err(|err| err.to_string())
+                    .and_then(|data| parse(&data));
+                match parsed {
+                    Ok(value) => {
+                        if sender.send(value).is_err() {
+                            let _ = client.disconnect();
+                            return;
+                        }
+                    }
+                    Err(err) => {
+                        eprintln!("Warning: skipping a message on {}: {}", message.topic, err)
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                if connected {
+                    eprintln!("Warning: {}: {}; reconnecting", topic, err);
+                    connected = false;
+                }
+                std::thread::sleep(RECONNECT_DELAY);
+            }
+        }
+    }
+}
+
+/// Publisher of retained messages to one broker, each acknowledged before
+/// [`publish`](MqttPublisher::publish) returns
+pub struct MqttPublisher {
+    client: Client,
+    topic: MqttTopic,
+    // Acknowledgements, or why the connection failed
+    acks: Receiver<Result<(), String>>,
+}
+
+impl MqttPublisher {
+    /// Publisher to the broker of `topic`, connecting in the background
+    pub fn new(topic: &MqttTopic) -> MqttPublisher {
+        let (client, mut connection) = Client::new(topic.options(), 1024);
+        let (sender, acks) = mpsc::channel();
+        // Ends once the publisher and its client are dropped
+        std::thread::spawn(move || {
+            for event in connection.iter() {
+                let ack = match event {
+                    Ok(Event::Incoming(Packet::PubAck(_))) => Ok(()),
+                    Ok(_) => continue,
+                    Err(err) => Err(err.to_string()),
+                };
+                let failed = ack.is_err();
+                if sender.send(ack).is_err() {
+                    return;
+                }
+                if failed {
+                    std::thread::sleep(RECONNECT_DELAY);
+                }
+            }
+        });
+        MqttPublisher {
+            client,
+            topic: topic.clone(),
+            acks,
+        }
+    }
+
+    /// The topic given to [`new`](MqttPublisher::new)
+    pub fn topic(&self) -> &MqttTopic {
+        &self.topic
+    }
+
+    /// Publish retained `(topic, payload)` messages and wait until the broker
+    /// acknowledged all of them
+    pub fn publish(&self, messages: &[(String, String)]) -> Result<(), String> {
+        // Failures from before this call are stale
+        while self.acks.try_recv().is_ok() {}
+        for (topic, payload) in messages {
+            self.client
+                .try_publish(topic.as_str(), QoS::AtLeastOnce, true, payload.as_str())
+                .map_err(|err| format!("Unable to publish to {}: {}", topic, err))?;
+        }
+        let deadline = Instant::now() + ACK_TIMEOUT;
+        for _ in messages {
+            match self
+                .acks
+                .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => return Err(format!("{}: {}", self.topic, err)),
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!(
+                        "{}: no acknowledgement within {} s",
+                        self.topic,
+                        ACK_TIMEOUT.as_secs()
+                    ));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(format!("{}: connection closed", self.topic));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Publish the plan to the topic and one recommendation per router to
+    /// `<topic>/routers/<index>`, see [`router_recommendations`]
+    pub fn publish_plan(&self, solution: &Solution) -> Result<(), String> {
+        let mut messages = vec![(self.topic.topic.clone(), solution.to_json().to_string())];
+        for (i, recommendation) in router_recommendations(solution).into_iter().enumerate() {
+            messages.push((
+                format!("{}/routers/{}", self.topic.topic, i),
+                recommendation.to_string(),
+            ));
+        }
+        self.publish(&messages)
+    }
+}
+
+/// Transmit power in dBm each router needs to reach the farthest client it serves,
+/// i.e. that it covers and no covering router is nearer to, capped at the configured
+/// power. None without a path-loss model.
+pub fn power_levels(solution: &Solution) -> Option<Vec<f64>> {
+    let radio = Radio::new(&solution.config);
+    let path_loss = radio.model().path_loss?;
+    let mut reach = vec![0.0f64; solution.routers.len()];
+    for client in &solution.clients.positions {
+        let serving = solution
+            .routers
+            .iter()
+            .enumerate()
+            .filter(|(_, router)| !radio.blocked(router, client))
+            .map(|(i, router)| (i, distance(router, client)))
+            .filter(|&(i, distance)| radio.covers(i, distance))
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some((i, distance)) = serving {
+            reach[i] = reach[i].max(distance);
+        }
+    }
+    Some(
+        reach
+            .into_iter()
+            .map(|reach| path_loss.power_for_range(reach).min(path_loss.tx_power))
+            .collect(),
+    )
+}
+
+/// Recommended position, transmit radius and, with a path-loss model, transmit power
+/// of every router, as `{"position", "radius", "tx_power"}`
+pub fn router_recommendations(solution: &Solution) -> Vec<Value> {
+    let radio = Radio::new(&solution.config);
+    let powers = power_levels(solution);
+    solution
+        .routers
+        .iter()
+        .enumerate()
+        .map(|(i, router)| {
+            json!({
+                "position": router,
+                "radius": radio.radius(i),
+                "tx_power": powers.as_ref().map(|powers| powers[i]),
+            })
+        })
+        .collect()
+}
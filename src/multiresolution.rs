@@ -0,0 +1,94 @@
+use crate::sanity::NonFinite;
+use crate::{ClientSet, DIMENSIONS, FireflyConfig, FireflyOptimizer};
+use rand::Rng;
+
+/// Coarse-to-fine schedule solving down-scaled problems before the full one.
+///
+/// Level `k` of `levels` keeps `client_fraction`ᵏ of the clients and multiplies the
+/// communication distance by `radius_factor`ᵏ; the levels run from the coarsest to
+/// level 1, each starting from the layout of the one before, and the full problem
+/// starts from the layout of level 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Multiresolution {
+    /// Coarse levels before the full problem
+    pub levels: usize,
+    /// Share of the clients kept per level, in (0, 1]
+    pub client_fraction: f64,
+    /// Relaxation of the communication distance per level, at least 1
+    pub radius_factor: f64,
+    /// Iterations of every coarse level
+    pub coarse_iterations: usize,
+}
+
+impl Multiresolution {
+    /// `levels` coarse levels keeping a quarter of the clients and relaxing the
+    /// communication distance by half per level
+    pub fn new(levels: usize, coarse_iterations: usize) -> Multiresolution {
+        Multiresolution {
+            levels,
+            client_fraction: 0.25,
+            radius_factor: 1.5,
+            coarse_iterations,
+        }
+    }
+}
+
+// A random `fraction` of the clients, at least one if there are any, with the weights
+// scaled up so the sample carries the weight of the whole set
+fn subsample(clients: &ClientSet, fraction: f64, rng: &mut impl Rng) -> ClientSet {
+    let count = ((clients.len() as f64 * fraction).round() as usize)
+        .max(1)
+        .min(clients.len());
+    let mut indices = rand::seq::index::sample(rng, clients.len(), count).into_vec();
+    // Kept in client order, as the sampled indices come in random order
+    indices.sort_unstable();
+    let scale = clients.len() as f64 / count.max(1) as f64;
+    ClientSet::with_weights(
+        indices.iter().map(|&i| clients.positions[i]).collect(),
+        indices
+            .iter()
+            .map(|&i| clients.weights[i] * scale)
+            .collect(),
+    )
+    .expect("a sample of a valid client set is valid")
+}
+
+// Solve every coarse level in turn and return the layout of the finest one
+pub(crate) fn coarse_layout(
+    config: &FireflyConfig,
+    multiresolution: Multiresolution,
+    clients: &ClientSet,
+    initial: Option<Vec<[f64; DIMENSIONS]>>,
+    rng: &mut impl Rng,
+) -> Result<Vec<[f64; DIMENSIONS]>, NonFinite> {
+    let mut layout = initial;
+    for level in (1..=multiresolution.levels).rev() {
+        let level_clients = subsample(
+            clients,
+            multiresolution.client_fraction.powi(level as i32),
+            rng,
+        );
+        let level_config = FireflyConfig {
+            iterations: multiresolution.coarse_iterations,
+            max_communication_distance: config.max_communication_distance
+                * multiresolution.radius_factor.powi(level as i32),
+            snap: None,
+            tiling: None,
+            multiresolution: None,
+            history: Default::default(),
+            seed: config.seed.map(|_| rng.r#gen()),
+            run: Default::default(),
+            ..config.clone()
+        };
+        let mut optimizer = FireflyOptimizer::new(level_config)
+            .expect("levels inherit a valid configuration")
+            .with_clients(level_clients);
+        if let Some(layout) = layout {
+            optimizer = optimizer
+                .with_initial_layout(layout)
+                .expect("coarse levels run the router swarm with every router");
+        }
+        layout = Some(optimizer.try_run()?.routers);
+    }
+    Ok(layout.expect("there is at least one coarse level"))
+}
@@ -0,0 +1,115 @@
+use super::{Direction, Objective};
+use std::f64::consts::PI;
+
+/// Sphere function Σ xᵢ², minimized at the origin
+#[derive(Clone, Debug)]
+pub struct Sphere {
+    pub dimensions: usize,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+}
+
+impl Sphere {
+    pub fn new(dimensions: usize) -> Sphere {
+        Sphere {
+            dimensions,
+            lower_bound: -10.0,
+            upper_bound: 10.0,
+        }
+    }
+}
+
+impl Objective for Sphere {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn bounds(&self, _dimension: usize) -> (f64, f64) {
+        (self.lower_bound, self.upper_bound)
+    }
+
+    fn direction(&self) -> Direction {
+        Direction::Minimize
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        x.iter().map(|&xi| xi * xi).sum()
+    }
+}
+
+/// Rastrigin function 10n + Σ (xᵢ² − 10 cos 2πxᵢ), highly multimodal and minimized at the origin
+#[derive(Clone, Debug)]
+pub struct Rastrigin {
+    pub dimensions: usize,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+}
+
+impl Rastrigin {
+    pub fn new(dimensions: usize) -> Rastrigin {
+        Rastrigin {
+            dimensions,
+            lower_bound: -5.12,
+            upper_bound: 5.12,
+        }
+    }
+}
+
+impl Objective for Rastrigin {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn bounds(&self, _dimension: usize) -> (f64, f64) {
+        (self.lower_bound, self.upper_bound)
+    }
+
+    fn direction(&self) -> Direction {
+        Direction::Minimize
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        10.0 * x.len() as f64
+            + x.iter()
+                .map(|&xi| xi * xi - 10.0 * (2.0 * PI * xi).cos())
+                .sum::<f64>()
+    }
+}
+
+/// Rosenbrock function Σ 100(xᵢ₊₁ − xᵢ²)² + (1 − xᵢ)², a narrow curved valley minimized at (1, …, 1)
+#[derive(Clone, Debug)]
+pub struct Rosenbrock {
+    pub dimensions: usize,
+    pub lower_bound: f64,
+    pub upper_bound: f64,
+}
+
+impl Rosenbrock {
+    pub fn new(dimensions: usize) -> Rosenbrock {
+        Rosenbrock {
+            dimensions,
+            lower_bound: -5.0,
+            upper_bound: 5.0,
+        }
+    }
+}
+
+impl Objective for Rosenbrock {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn bounds(&self, _dimension: usize) -> (f64, f64) {
+        (self.lower_bound, self.upper_bound)
+    }
+
+    fn direction(&self) -> Direction {
+        Direction::Minimize
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        x.windows(2)
+            .map(|pair| 100.0 * (pair[1] - pair[0] * pair[0]).powi(2) + (1.0 - pair[0]).powi(2))
+            .sum()
+    }
+}
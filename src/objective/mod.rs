@@ -0,0 +1,118 @@
+//! Objective functions the firefly core can optimize.
+
+mod benchmarks;
+mod streaming;
+mod wmn;
+
+pub use benchmarks::{Rastrigin, Rosenbrock, Sphere};
+pub use streaming::StreamingObjective;
+pub(crate) use wmn::layout_fitness;
+pub use wmn::{WmnObjective, layout_from_flat, objectives_fitness};
+
+use crate::compare_fitness;
+use std::cmp::Ordering;
+
+/// Whether an objective is minimized or maximized
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Minimize,
+    Maximize,
+}
+
+impl Direction {
+    /// Objective value as brightness, which is always maximized
+    pub fn brightness(self, value: f64) -> f64 {
+        match self {
+            Direction::Minimize => -value,
+            Direction::Maximize => value,
+        }
+    }
+
+    /// Order two objective values from worse to better, NaN being worst in both directions
+    pub fn compare(self, a: f64, b: f64) -> Ordering {
+        compare_fitness(self.brightness(a), self.brightness(b))
+    }
+}
+
+/// A function over bounded real vectors for the firefly core to optimize.
+///
+/// ```
+/// use ff_wmn::objective::{Direction, Objective};
+///
+/// // Maximize -(x - 3)² on [0, 10]
+/// struct Parabola;
+///
+/// impl Objective for Parabola {
+///     fn dimensions(&self) -> usize {
+///         1
+///     }
+///
+///     fn bounds(&self, _dimension: usize) -> (f64, f64) {
+///         (0.0, 10.0)
+///     }
+///
+///     fn direction(&self) -> Direction {
+///         Direction::Maximize
+///     }
+///
+///     fn evaluate(&mut self, x: &[f64]) -> f64 {
+///         -(x[0] - 3.0).powi(2)
+///     }
+/// }
+/// ```
+pub trait Objective {
+    /// Length of every solution vector
+    fn dimensions(&self) -> usize;
+
+    /// Lower and upper bound of one dimension
+    fn bounds(&self, dimension: usize) -> (f64, f64);
+
+    fn direction(&self) -> Direction;
+
+    fn evaluate(&mut self, x: &[f64]) -> f64;
+
+    /// `target` rearranged to line up with `firefly` before the firefly moves toward it,
+    /// for solutions made of interchangeable parts; None moves dimension by dimension
+    fn align(&self, _firefly: &[f64], _target: &[f64]) -> Option<Vec<f64>> {
+        None
+    }
+}
+
+/// Several objectives over the same bounded real vectors, all maximized, for the
+/// Pareto search of [`FireflyCore::optimize_pareto`](crate::FireflyCore::optimize_pareto).
+///
+/// ```
+/// use ff_wmn::objective::MultiObjective;
+///
+/// // Schaffer's problem: minimize x² and (x - 2)², negated to maximize
+/// struct Schaffer;
+///
+/// impl MultiObjective for Schaffer {
+///     fn dimensions(&self) -> usize {
+///         1
+///     }
+///
+///     fn bounds(&self, _dimension: usize) -> (f64, f64) {
+///         (-10.0, 10.0)
+///     }
+///
+///     fn evaluate(&mut self, x: &[f64]) -> Vec<f64> {
+///         vec![-x[0].powi(2), -(x[0] - 2.0).powi(2)]
+///     }
+/// }
+/// ```
+pub trait MultiObjective {
+    /// Length of every solution vector
+    fn dimensions(&self) -> usize;
+
+    /// Lower and upper bound of one dimension
+    fn bounds(&self, dimension: usize) -> (f64, f64);
+
+    /// Value of every objective, the same number for every solution
+    fn evaluate(&mut self, x: &[f64]) -> Vec<f64>;
+
+    /// Same as [`Objective::align`]
+    fn align(&self, _firefly: &[f64], _target: &[f64]) -> Option<Vec<f64>> {
+        None
+    }
+}
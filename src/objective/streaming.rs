@@ -0,0 +1,138 @@
+use super::wmn::{align_layouts, layout_fitness};
+use super::{Direction, Objective, layout_from_flat};
+use crate::metrics::{APPROX_SGC_SEEDS, MetricTimings, SgcMode};
+use crate::{ClientSet, DIMENSIONS, FireflyConfig};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::sync::mpsc::Receiver;
+
+/// The weighted WMN fitness against clients that change while the search runs.
+///
+/// Before every evaluation the objective asks its source for the current clients,
+/// e.g. the stations associated in live telemetry, and scores the layout against the
+/// latest set it was given. Fitness values from before an update were computed for
+/// other clients, so a search that must compare like with like should re-evaluate its
+/// best layout once [`updates`](StreamingObjective::updates) changes. Objective
+/// plugins and evaluation logs are not supported.
+///
+/// ```
+/// use ff_wmn::objective::StreamingObjective;
+/// use ff_wmn::{ClientSet, FireflyConfig, FireflyCore};
+/// use std::sync::mpsc;
+///
+/// let config = FireflyConfig::new().routers(4);
+/// let (telemetry, clients) = mpsc::channel();
+/// let mut objective =
+///     StreamingObjective::from_channel(&config, ClientSet::new(vec![[1.0, 1.0]]), clients);
+/// telemetry.send(ClientSet::new(vec![[2.0, 2.0], [3.0, 3.0]])).unwrap();
+///
+/// let core = FireflyCore { iterations: 5, ..FireflyCore::default() };
+/// core.optimize(&mut objective, &mut rand::thread_rng()).unwrap();
+/// assert_eq!(objective.clients().len(), 2);
+/// assert_eq!(objective.updates(), 1);
+/// ```
+pub struct StreamingObjective<'a> {
+    config: &'a FireflyConfig,
+    clients: ClientSet,
+    source: Box<dyn FnMut() -> Option<ClientSet> + 'a>,
+    updates: usize,
+    sgc_mode: SgcMode,
+    timings: MetricTimings,
+    // Draws the seeds of sampled SGC
+    rng: StdRng,
+}
+
+impl<'a> StreamingObjective<'a> {
+    /// Start from `clients` and replace them with whatever `source` returns; it returns
+    /// None while the clients are unchanged
+    pub fn from_fn(
+        config: &'a FireflyConfig,
+        clients: ClientSet,
+        source: impl FnMut() -> Option<ClientSet> + 'a,
+    ) -> StreamingObjective<'a> {
+        StreamingObjective {
+            config,
+            clients,
+            source: Box::new(source),
+            updates: 0,
+            sgc_mode: if config.approx_metrics {
+                SgcMode::Sampled {
+                    seeds: APPROX_SGC_SEEDS,
+                }
+            } else {
+                SgcMode::Exact
+            },
+            timings: MetricTimings::default(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Start from `clients` and switch to the latest client set sent on `receiver`;
+    /// the clients stay as they are once every sender is gone
+    pub fn from_channel(
+        config: &'a FireflyConfig,
+        clients: ClientSet,
+        receiver: Receiver<ClientSet>,
+    ) -> StreamingObjective<'a> {
+        StreamingObjective::from_fn(config, clients, move || receiver.try_iter().last())
+    }
+
+    /// Draw the seeds of sampled SGC from this generator instead of a randomly seeded one
+    pub fn with_rng(mut self, rng: StdRng) -> StreamingObjective<'a> {
+        self.rng = rng;
+        self
+    }
+
+    /// The clients the latest evaluation was scored against
+    pub fn clients(&self) -> &ClientSet {
+        &self.clients
+    }
+
+    /// How many times the source replaced the clients
+    pub fn updates(&self) -> usize {
+        self.updates
+    }
+
+    /// Time spent in each fitness component so far
+    pub fn timings(&self) -> &MetricTimings {
+        &self.timings
+    }
+
+    /// Fitness of a layout against the current clients
+    pub fn fitness(&mut self, routers: &[[f64; DIMENSIONS]]) -> f64 {
+        if let Some(clients) = (self.source)() {
+            self.clients = clients;
+            self.updates += 1;
+        }
+        layout_fitness(
+            self.config,
+            &self.clients,
+            self.sgc_mode,
+            &mut self.rng,
+            &mut self.timings,
+            routers,
+        )
+    }
+}
+
+impl Objective for StreamingObjective<'_> {
+    fn dimensions(&self) -> usize {
+        self.config.routers * DIMENSIONS
+    }
+
+    fn bounds(&self, _dimension: usize) -> (f64, f64) {
+        (self.config.lower_bound, self.config.upper_bound)
+    }
+
+    fn direction(&self) -> Direction {
+        Direction::Maximize
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        self.fitness(&layout_from_flat(x))
+    }
+
+    fn align(&self, firefly: &[f64], target: &[f64]) -> Option<Vec<f64>> {
+        Some(align_layouts(firefly, target))
+    }
+}
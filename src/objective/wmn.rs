@@ -0,0 +1,417 @@
+use super::{Direction, MultiObjective, Objective};
+use crate::eval_log::EvaluationLog;
+use crate::layout_distance::match_layouts;
+use crate::metrics::{
+    APPROX_SGC_SEEDS, ConnectivityGraph, MetricTimings, Metrics, SgcMode, approx_sgc,
+    coverage_overlap, separation_violations, sgc, timed, worst_case_ncmc,
+};
+use crate::optimizer::{NewBest, NewBestHook};
+use crate::plugin::ObjectivePlugin;
+use crate::radio::{Radio, RadioModel};
+use crate::{ClientSet, DIMENSIONS, FireflyConfig, Robustness, compare_fitness};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The weighted WMN fitness of a router layout, maximized.
+///
+/// As an [`Objective`], a solution is the layout flattened router by router, so its
+/// dimensions are the router count times [`DIMENSIONS`]. As a [`MultiObjective`], the
+/// same layouts are scored by [`objectives`](WmnObjective::objectives) instead.
+pub struct WmnObjective<'a> {
+    config: &'a FireflyConfig,
+    clients: &'a ClientSet,
+    pub(crate) plugin: Option<&'a ObjectivePlugin>,
+    pub(crate) eval_log: Option<&'a mut EvaluationLog>,
+    pub(crate) on_new_best: Option<&'a mut NewBestHook>,
+    pub(crate) sgc_mode: SgcMode,
+    pub(crate) timings: MetricTimings,
+    // Draws the seeds of sampled SGC and the router failures of robust fitness
+    rng: StdRng,
+}
+
+impl<'a> WmnObjective<'a> {
+    pub fn new(config: &'a FireflyConfig, clients: &'a ClientSet) -> WmnObjective<'a> {
+        WmnObjective {
+            config,
+            clients,
+            plugin: None,
+            eval_log: None,
+            on_new_best: None,
+            sgc_mode: if config.approx_metrics {
+                SgcMode::Sampled {
+                    seeds: APPROX_SGC_SEEDS,
+                }
+            } else {
+                SgcMode::Exact
+            },
+            timings: MetricTimings::default(),
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Draw the seeds of sampled SGC from this generator instead of a randomly seeded one
+    pub fn with_rng(mut self, rng: StdRng) -> WmnObjective<'a> {
+        self.rng = rng;
+        self
+    }
+
+    pub fn clients(&self) -> &'a ClientSet {
+        self.clients
+    }
+
+    /// Time spent in each fitness component so far
+    pub fn timings(&self) -> &MetricTimings {
+        &self.timings
+    }
+
+    /// Fitness of a layout at the current SGC fidelity
+    pub fn fitness(&mut self, routers: &[[f64; DIMENSIONS]]) -> f64 {
+        self.timings.evaluations += 1;
+        let fitness = match self.plugin {
+            Some(plugin) => timed(&mut self.timings.plugin, || {
+                plugin.evaluate(routers, &self.clients.positions)
+            }),
+            None => self.components(routers).fitness(self.config),
+        };
+
+        if let Some(eval_log) = self.eval_log.as_mut() {
+            eval_log.record(routers, fitness);
+        }
+        fitness
+    }
+
+    // Deliver an improvement of the best layout to the hook, with its exact metrics
+    pub(crate) fn report_best(
+        &mut self,
+        iteration: Option<usize>,
+        routers: &[[f64; DIMENSIONS]],
+        fitness: f64,
+    ) {
+        if let Some(hook) = self.on_new_best.as_mut() {
+            let metrics = Metrics::evaluate(routers, self.clients, self.config, self.plugin);
+            hook(NewBest {
+                iteration,
+                routers,
+                fitness,
+                metrics: &metrics,
+            });
+        }
+    }
+
+    /// The objectives of a Pareto search: SGC, NCMC and the negations of the penalized
+    /// coverage overlap, separation violations and router displacement, all at the
+    /// current SGC fidelity
+    pub fn objectives(&mut self, routers: &[[f64; DIMENSIONS]]) -> Vec<f64> {
+        self.timings.evaluations += 1;
+        let components = self.components(routers);
+        if let Some(eval_log) = self.eval_log.as_mut() {
+            eval_log.record(routers, components.fitness(self.config));
+        }
+        let mut objectives = vec![components.sgc, components.ncmc];
+        if self.config.weights.overlap > 0.0 {
+            objectives.push(-components.overlap);
+        }
+        if penalizes_separation(self.config) {
+            objectives.push(-(components.violations as f64));
+        }
+        if penalizes_relocation(self.config) {
+            objectives.push(-components.displacement);
+        }
+        objectives
+    }
+
+    // Fitness components of a layout, timed
+    fn components(&mut self, routers: &[[f64; DIMENSIONS]]) -> Components {
+        components(
+            self.config,
+            self.clients,
+            self.sgc_mode,
+            &mut self.rng,
+            &mut self.timings,
+            routers,
+        )
+    }
+}
+
+// Fitness components of a layout for the given clients, timed
+fn components(
+    config: &FireflyConfig,
+    clients: &ClientSet,
+    sgc_mode: SgcMode,
+    rng: &mut StdRng,
+    timings: &mut MetricTimings,
+    routers: &[[f64; DIMENSIONS]],
+) -> Components {
+    let radio = Radio::new(config);
+    let (sgc, ncmc, ncmcpr) = match config.robustness {
+        None => service(clients, sgc_mode, rng, timings, routers, &radio, 0.0),
+        Some(Robustness::Expected {
+            failure_probability,
+            samples,
+        }) => {
+            let mut totals = (0.0, 0.0, 0.0);
+            for _ in 0..samples {
+                let working: Vec<usize> = (0..routers.len())
+                    .filter(|_| !rng.gen_bool(failure_probability))
+                    .collect();
+                if working.is_empty() {
+                    continue;
+                }
+                let (layout, model) = working_routers(config, routers, &working);
+                let radio = Radio::with_model(config.max_communication_distance, &model);
+                let (sgc, ncmc, ncmcpr) =
+                    service(clients, sgc_mode, rng, timings, &layout, &radio, 0.0);
+                totals = (totals.0 + sgc, totals.1 + ncmc, totals.2 + ncmcpr);
+            }
+            let samples = samples as f64;
+            (totals.0 / samples, totals.1 / samples, totals.2 / samples)
+        }
+        Some(Robustness::WorstCase {
+            client_shift,
+            router_failure,
+        }) => {
+            let score = |(sgc, ncmc, ncmcpr): (f64, f64, f64)| {
+                config.service_fitness(sgc, ncmc, ncmcpr, clients.coverage_share(ncmc), 0.0)
+            };
+            let mut worst = service(
+                clients,
+                sgc_mode,
+                rng,
+                timings,
+                routers,
+                &radio,
+                client_shift,
+            );
+            // Losing the only router leaves nothing to score
+            let failures = if router_failure && routers.len() > 1 {
+                routers.len()
+            } else {
+                0
+            };
+            for failed in 0..failures {
+                let working: Vec<usize> = (0..routers.len()).filter(|&i| i != failed).collect();
+                let (layout, model) = working_routers(config, routers, &working);
+                let radio = Radio::with_model(config.max_communication_distance, &model);
+                let case = service(
+                    clients,
+                    sgc_mode,
+                    rng,
+                    timings,
+                    &layout,
+                    &radio,
+                    client_shift,
+                );
+                if compare_fitness(score(case), score(worst)).is_lt() {
+                    worst = case;
+                }
+            }
+            worst
+        }
+    };
+    // Overlap and separation only matter when they are penalized
+    let overlap = if config.weights.overlap > 0.0 {
+        timed(&mut timings.overlap, || coverage_overlap(routers, &radio))
+    } else {
+        0.0
+    };
+    let violations = if penalizes_separation(config) {
+        separation_violations(routers, config.radio.min_separation)
+    } else {
+        0
+    };
+    let displacement = match &config.relocation {
+        Some(relocation) if penalizes_relocation(config) => relocation.displacement(routers),
+        _ => 0.0,
+    };
+    Components {
+        sgc,
+        ncmc,
+        ncmcpr,
+        coverage: clients.coverage_share(ncmc),
+        overlap,
+        violations,
+        displacement,
+    }
+}
+
+// The routers of `working` with the radio model they keep their own radii in
+fn working_routers(
+    config: &FireflyConfig,
+    routers: &[[f64; DIMENSIONS]],
+    working: &[usize],
+) -> (Vec<[f64; DIMENSIONS]>, RadioModel) {
+    let model = config
+        .radio
+        .subset(config.max_communication_distance, working);
+    (working.iter().map(|&i| routers[i]).collect(), model)
+}
+
+// SGC, NCMC and NCMCpR of a layout, timed, counting the clients that stay covered
+// wherever they move up to `client_shift`
+fn service(
+    clients: &ClientSet,
+    sgc_mode: SgcMode,
+    rng: &mut StdRng,
+    timings: &mut MetricTimings,
+    routers: &[[f64; DIMENSIONS]],
+    radio: &Radio,
+    client_shift: f64,
+) -> (f64, f64, f64) {
+    let graph = timed(&mut timings.graph, || {
+        ConnectivityGraph::new(routers, radio)
+    });
+    let sgc = timed(&mut timings.sgc, || match sgc_mode {
+        SgcMode::Exact => sgc(&graph),
+        SgcMode::Sampled { seeds } => approx_sgc(&graph, seeds, rng),
+    }) as f64;
+    let ncmc = timed(&mut timings.ncmc, || {
+        worst_case_ncmc(routers, clients, radio, client_shift)
+    });
+    let ncmcpr = timed(&mut timings.ncmcpr, || ncmc / routers.len() as f64);
+    (sgc, ncmc, ncmcpr)
+}
+
+// Weighted fitness of a layout for the given clients, without a plugin or evaluation log
+pub(crate) fn layout_fitness(
+    config: &FireflyConfig,
+    clients: &ClientSet,
+    sgc_mode: SgcMode,
+    rng: &mut StdRng,
+    timings: &mut MetricTimings,
+    routers: &[[f64; DIMENSIONS]],
+) -> f64 {
+    timings.evaluations += 1;
+    components(config, clients, sgc_mode, rng, timings, routers).fitness(config)
+}
+
+// Everything the weighted fitness of a layout is made of
+struct Components {
+    sgc: f64,
+    ncmc: f64,
+    ncmcpr: f64,
+    // Share of the client weight NCMC makes up
+    coverage: f64,
+    overlap: f64,
+    violations: usize,
+    // Mean router distance from the relocation anchor
+    displacement: f64,
+}
+
+impl Components {
+    fn fitness(&self, config: &FireflyConfig) -> f64 {
+        config.service_fitness(
+            self.sgc,
+            self.ncmc,
+            self.ncmcpr,
+            self.coverage,
+            self.overlap,
+        ) - config.radio.penalty(self.violations)
+            - config
+                .relocation
+                .as_ref()
+                .map_or(0.0, |relocation| relocation.cost(self.displacement))
+    }
+}
+
+fn penalizes_separation(config: &FireflyConfig) -> bool {
+    config.radio.min_separation > 0.0 && config.radio.separation_penalty > 0.0
+}
+
+fn penalizes_relocation(config: &FireflyConfig) -> bool {
+    config
+        .relocation
+        .as_ref()
+        .is_some_and(|relocation| relocation.penalty > 0.0)
+}
+
+/// Weighted fitness of a layout from its objectives as returned by
+/// [`WmnObjective::objectives`]
+pub fn objectives_fitness(config: &FireflyConfig, objectives: &[f64]) -> f64 {
+    let mut penalties = objectives[2..].iter().map(|objective| -objective);
+    let mut penalty = |penalized: bool| {
+        if penalized {
+            penalties
+                .next()
+                .expect("penalized components are objectives")
+        } else {
+            0.0
+        }
+    };
+    let overlap = penalty(config.weights.overlap > 0.0);
+    let violations = penalty(penalizes_separation(config));
+    let displacement = penalty(penalizes_relocation(config));
+    Components {
+        sgc: objectives[0],
+        ncmc: objectives[1],
+        ncmcpr: objectives[1] / config.routers as f64,
+        // Only a goal needs the coverage share, and a Pareto search has none
+        coverage: f64::NAN,
+        overlap,
+        violations: violations as usize,
+        displacement,
+    }
+    .fitness(config)
+}
+
+// Routers are interchangeable, so move every router toward its matched router
+pub(super) fn align_layouts(firefly: &[f64], target: &[f64]) -> Vec<f64> {
+    let target = layout_from_flat(target);
+    let matching = match_layouts(&layout_from_flat(firefly), &target);
+    matching
+        .assignment
+        .iter()
+        .flat_map(|&router| target[router])
+        .collect()
+}
+
+/// Router layout stored in a flat solution vector
+pub fn layout_from_flat(x: &[f64]) -> Vec<[f64; DIMENSIONS]> {
+    x.chunks_exact(DIMENSIONS)
+        .map(|router| {
+            router
+                .try_into()
+                .expect("chunks have DIMENSIONS coordinates")
+        })
+        .collect()
+}
+
+impl Objective for WmnObjective<'_> {
+    fn dimensions(&self) -> usize {
+        self.config.routers * DIMENSIONS
+    }
+
+    fn bounds(&self, _dimension: usize) -> (f64, f64) {
+        (self.config.lower_bound, self.config.upper_bound)
+    }
+
+    fn direction(&self) -> Direction {
+        Direction::Maximize
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        self.fitness(&layout_from_flat(x))
+    }
+
+    fn align(&self, firefly: &[f64], target: &[f64]) -> Option<Vec<f64>> {
+        Some(align_layouts(firefly, target))
+    }
+}
+
+// Scores the metrics separately; an objective plugin only applies to the weighted fitness
+impl MultiObjective for WmnObjective<'_> {
+    fn dimensions(&self) -> usize {
+        Objective::dimensions(self)
+    }
+
+    fn bounds(&self, dimension: usize) -> (f64, f64) {
+        Objective::bounds(self, dimension)
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> Vec<f64> {
+        self.objectives(&layout_from_flat(x))
+    }
+
+    fn align(&self, firefly: &[f64], target: &[f64]) -> Option<Vec<f64>> {
+        Objective::align(self, firefly, target)
+    }
+}
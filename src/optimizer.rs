@@ -0,0 +1,876 @@
+use crate::clients::ClientSet;
+use crate::eval_log::EvaluationLog;
+use crate::firefly_core::{
+    FireflyCore, Improvement, IterationState, MovementParameters, evaluates,
+};
+use crate::fixed_point::{FixedPoint, portable_exp};
+use crate::history::IterationHistory;
+use crate::metrics::{ConnectivityGraph, Metrics, SgcMode, approx_sgc, ncmc, router_coverage};
+use crate::multiresolution::coarse_layout;
+use crate::objective::{WmnObjective, layout_fitness, layout_from_flat, objectives_fitness};
+use crate::pareto::ParetoArchive;
+use crate::plugin::ObjectivePlugin;
+use crate::population_trace::PopulationTrace;
+use crate::radio::Radio;
+use crate::report::{
+    FidelityChange, ParetoLayout, Report, SearchFidelity, Snapping, run_warnings, snap_layout,
+};
+use crate::sanity::{NonFinite, NonFinitePolicy};
+use crate::schedule::Stall;
+use crate::termination::Stopper;
+use crate::tiling::tile_layout;
+use crate::{
+    ConfigError, DIMENSIONS, FireflyConfig, SearchMode, Solution, TieBreak, compare_fitness,
+    distance,
+};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::ops::ControlFlow;
+use std::time::Instant;
+
+// Share of a boundary-crossing move kept by soft bounds
+const BOUNDARY_DAMPING: f64 = 0.5;
+
+/// An improvement of the best layout, as seen by the hook of
+/// [`FireflyOptimizer::on_new_best`]
+pub struct NewBest<'a> {
+    /// Iteration the layout was found in, None for the initial layout or population
+    pub iteration: Option<usize>,
+    pub routers: &'a [[f64; DIMENSIONS]],
+    /// Fitness the search compares layouts by, at its current SGC fidelity
+    pub fitness: f64,
+    /// Exact metrics of the layout
+    pub metrics: &'a Metrics,
+}
+
+/// Callback receiving every improvement of the best layout
+pub type NewBestHook = Box<dyn FnMut(NewBest) + Send>;
+
+/// Runs the firefly algorithm for one [`FireflyConfig`].
+///
+/// ```no_run
+/// use ff_wmn::{FireflyConfig, FireflyOptimizer};
+///
+/// let optimizer = FireflyOptimizer::new(FireflyConfig::new().routers(8)).unwrap();
+/// let solution = optimizer.run();
+/// println!("fitness: {}", solution.report.metrics.fitness);
+/// ```
+pub struct FireflyOptimizer {
+    config: FireflyConfig,
+    clients: Option<ClientSet>,
+    plugin: Option<ObjectivePlugin>,
+    eval_log: Option<EvaluationLog>,
+    population_trace: Option<PopulationTrace>,
+    history: Option<IterationHistory>,
+    initial: Option<Vec<[f64; DIMENSIONS]>>,
+    on_new_best: Option<NewBestHook>,
+}
+
+impl FireflyOptimizer {
+    pub fn new(config: FireflyConfig) -> Result<FireflyOptimizer, ConfigError> {
+        config.validate()?;
+        let history =
+            IterationHistory::create(&config.history, config.routers, &Radio::new(&config))
+                .map_err(|err| {
+                    ConfigError(format!("unable to create the iteration history: {}", err))
+                })?;
+        Ok(FireflyOptimizer {
+            config,
+            clients: None,
+            plugin: None,
+            eval_log: None,
+            population_trace: None,
+            history,
+            initial: None,
+            on_new_best: None,
+        })
+    }
+
+    /// Place routers for these clients instead of randomly generated ones
+    pub fn with_clients(mut self, clients: ClientSet) -> FireflyOptimizer {
+        self.config.clients = clients.len();
+        self.config.run.clients_file = clients.source.clone();
+        self.clients = Some(clients);
+        self
+    }
+
+    /// Score layouts with an external objective instead of the weighted metrics
+    pub fn with_objective_plugin(mut self, plugin: ObjectivePlugin) -> FireflyOptimizer {
+        self.config.run.objective_plugin = Some(plugin.path().to_string());
+        self.plugin = Some(plugin);
+        self
+    }
+
+    /// Record every evaluated layout; the log must be sized for the configured routers
+    pub fn with_evaluation_log(mut self, eval_log: EvaluationLog) -> FireflyOptimizer {
+        self.eval_log = Some(eval_log);
+        self
+    }
+
+    /// Stream every firefly's position after each iteration to `path`, see
+    /// [`PopulationTrace`]; only the router swarm, whose fireflies are routers, is traced
+    pub fn with_population_trace(mut self, path: &str) -> Result<FireflyOptimizer, ConfigError> {
+        if self.config.search != SearchMode::RouterSwarm {
+            return Err(ConfigError(
+                "a population trace needs the router swarm".to_string(),
+            ));
+        }
+        let trace = PopulationTrace::create(path).map_err(|err| {
+            ConfigError(format!("unable to create the population trace: {}", err))
+        })?;
+        self.population_trace = Some(trace);
+        Ok(self)
+    }
+
+    /// Start the router swarm from this layout instead of a random one, failing when
+    /// the search cannot start from a layout or the layout does not hold the configured
+    /// number of routers
+    pub fn with_initial_layout(
+        mut self,
+        routers: Vec<[f64; DIMENSIONS]>,
+    ) -> Result<FireflyOptimizer, ConfigError> {
+        if !self.config.starts_from_layout() {
+            return Err(ConfigError(
+                "only the router swarm without tiling starts from a given layout".to_string(),
+            ));
+        }
+        if routers.len() != self.config.routers {
+            return Err(ConfigError(format!(
+                "the initial layout holds {} routers, the run has {}",
+                routers.len(),
+                self.config.routers
+            )));
+        }
+        self.initial = Some(routers);
+        Ok(self)
+    }
+
+    /// Call `hook` as soon as the search finds a layout better than every earlier one,
+    /// starting with the initial best, instead of waiting for the iteration or the run
+    /// to end. Every call scores the layout with the exact metrics once more. A Pareto
+    /// search has no single best layout and never calls the hook.
+    ///
+    /// ```no_run
+    /// use ff_wmn::{FireflyConfig, FireflyOptimizer};
+    ///
+    /// let solution = FireflyOptimizer::new(FireflyConfig::new())
+    ///     .unwrap()
+    ///     .on_new_best(|best| println!("fitness {}, SGC {}", best.fitness, best.metrics.sgc))
+    ///     .run();
+    /// ```
+    pub fn on_new_best(mut self, hook: impl FnMut(NewBest) + Send + 'static) -> FireflyOptimizer {
+        self.on_new_best = Some(Box::new(hook));
+        self
+    }
+
+    pub fn config(&self) -> &FireflyConfig {
+        &self.config
+    }
+
+    /// Optimize a random layout for the given or random clients and return the best layout found
+    ///
+    /// # Panics
+    ///
+    /// When a firefly turns non-finite under [`NonFinitePolicy::Abort`]; use
+    /// [`try_run`](FireflyOptimizer::try_run) to handle that case.
+    pub fn run(self) -> Solution {
+        self.try_run()
+            .unwrap_or_else(|err| panic!("Aborted: {}", err))
+    }
+
+    /// Like [`run`](FireflyOptimizer::run), but return the offending firefly when the
+    /// search is aborted because a position or fitness turned non-finite
+    pub fn try_run(mut self) -> Result<Solution, NonFinite> {
+        let config = &self.config;
+        let mut rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        // Initialize mesh clients randomly unless they were given
+        let mesh_clients = self.clients.take().unwrap_or_else(|| {
+            ClientSet::random(
+                config.clients,
+                config.lower_bound,
+                config.upper_bound,
+                &mut rng,
+            )
+        });
+
+        let objective_rng = StdRng::from_rng(&mut rng).expect("StdRng seeding cannot fail");
+        let mut objective = WmnObjective::new(config, &mesh_clients).with_rng(objective_rng);
+        objective.plugin = self.plugin.as_ref();
+        objective.eval_log = self.eval_log.as_mut();
+        objective.on_new_best = self.on_new_best.as_mut();
+        let search = match config.search {
+            SearchMode::RouterSwarm => {
+                // Tiling and multiresolution prepare the layout the router swarm starts from
+                let initial = match (config.tiling, config.multiresolution) {
+                    (Some(tiling), _) => {
+                        tile_layout(config, tiling, &mesh_clients, &mut rng).map(Some)
+                    }
+                    (None, Some(multiresolution)) => coarse_layout(
+                        config,
+                        multiresolution,
+                        &mesh_clients,
+                        self.initial.take(),
+                        &mut rng,
+                    )
+                    .map(Some),
+                    (None, None) => Ok(self.initial.take()),
+                };
+                let iterations = config
+                    .tiling
+                    .map_or(config.iterations, |tiling| tiling.stitch_iterations);
+                initial.and_then(|initial| {
+                    router_swarm(
+                        config,
+                        iterations,
+                        initial,
+                        &mut objective,
+                        self.population_trace.as_mut(),
+                        self.history.as_mut(),
+                        &mut rng,
+                    )
+                })
+            }
+            SearchMode::Population { fireflies } if config.pareto => pareto_search(
+                config,
+                fireflies,
+                &mut objective,
+                self.history.as_mut(),
+                &mut rng,
+            ),
+            SearchMode::Population { fireflies } => population_search(
+                config,
+                fireflies,
+                &mut objective,
+                self.history.as_mut(),
+                &mut rng,
+            ),
+        };
+        let search = match search {
+            Ok(search) => search,
+            Err(non_finite) => {
+                if let Some(eval_log) = self.eval_log {
+                    eval_log.finish();
+                }
+                if let Some(trace) = self.population_trace {
+                    trace.finish();
+                }
+                if let Some(history) = self.history {
+                    history.finish();
+                }
+                return Err(non_finite);
+            }
+        };
+        let SearchOutcome {
+            routers: mut best_mesh_routers,
+            fitness: best_fitness,
+            fidelity_changes,
+            bound_hit_fraction,
+            repairs,
+            front,
+            stopped_after,
+            movement_only_iterations,
+        } = search;
+
+        let WmnObjective {
+            sgc_mode,
+            mut timings,
+            ..
+        } = objective;
+        let plugin = self.plugin.as_ref();
+
+        // The returned layout is always scored with the exact metrics
+        let mut metrics = Metrics::evaluate(&best_mesh_routers, &mesh_clients, config, plugin);
+        let pareto_front = front.map(|front| {
+            let mut front: Vec<ParetoLayout> = front
+                .into_iter()
+                .map(|routers| ParetoLayout {
+                    metrics: Metrics::evaluate(&routers, &mesh_clients, config, plugin),
+                    routers,
+                })
+                .collect();
+            front.sort_by(|a, b| {
+                b.metrics
+                    .sgc
+                    .cmp(&a.metrics.sgc)
+                    .then(b.metrics.ncmc.total_cmp(&a.metrics.ncmc))
+            });
+            front
+        });
+        let search_fidelity = match sgc_mode {
+            SgcMode::Exact => None,
+            SgcMode::Sampled { seeds } => Some(SearchFidelity {
+                sgc_mode,
+                fitness: best_fitness,
+                sgc: approx_sgc(
+                    &ConnectivityGraph::new(&best_mesh_routers, &Radio::new(config)),
+                    seeds,
+                    &mut rng,
+                ),
+            }),
+        };
+
+        // Snap the layout to installable coordinates and re-evaluate
+        let snapping = config.snap.map(|precision| {
+            best_mesh_routers = snap_layout(
+                &best_mesh_routers,
+                precision,
+                config.lower_bound,
+                config.upper_bound,
+            );
+            let unsnapped = std::mem::replace(
+                &mut metrics,
+                Metrics::evaluate(&best_mesh_routers, &mesh_clients, config, plugin),
+            );
+            Snapping {
+                precision,
+                unsnapped,
+            }
+        });
+
+        // Scored anew, as the search's estimates of the expected fitness favour layouts
+        // that were lucky in their failure draws
+        let robust_fitness = config.robustness.filter(|_| plugin.is_none()).map(|_| {
+            layout_fitness(
+                config,
+                &mesh_clients,
+                sgc_mode,
+                &mut rng,
+                &mut timings,
+                &best_mesh_routers,
+            )
+        });
+
+        let mut warnings = run_warnings(
+            config,
+            &mesh_clients,
+            &metrics,
+            snapping.as_ref(),
+            &fidelity_changes,
+            bound_hit_fraction,
+        );
+        if repairs > 0 {
+            warnings.push(format!(
+                "{} fireflies turned non-finite and were re-randomized",
+                repairs
+            ));
+        }
+        let report = Report {
+            metrics,
+            search_fidelity,
+            timings,
+            snapping,
+            fidelity_changes,
+            warnings,
+            history: self.history.and_then(IterationHistory::finish),
+            pareto_front,
+            stopped_after,
+            movement_only_iterations,
+            robust_fitness,
+        };
+
+        if let Some(eval_log) = self.eval_log {
+            eval_log.finish();
+        }
+        if let Some(trace) = self.population_trace {
+            trace.finish();
+        }
+
+        Ok(Solution {
+            routers: best_mesh_routers,
+            clients: mesh_clients,
+            config: self.config,
+            report,
+        })
+    }
+}
+
+// Best layout found by a search and how the search went
+struct SearchOutcome {
+    routers: Vec<[f64; DIMENSIONS]>,
+    // Fitness of `routers` at the final search fidelity
+    fitness: f64,
+    fidelity_changes: Vec<FidelityChange>,
+    bound_hit_fraction: f64,
+    // Fireflies re-randomized because they turned non-finite
+    repairs: usize,
+    // Every non-dominated layout of a Pareto search, `routers` among them
+    front: Option<Vec<Vec<[f64; DIMENSIONS]>>>,
+    // Iterations run when early stopping ended the search before the last one
+    stopped_after: Option<usize>,
+    movement_only_iterations: usize,
+}
+
+// Every router is a firefly attracted by the other routers of the one layout, starting
+// from `initial` or a random layout
+fn router_swarm(
+    config: &FireflyConfig,
+    iterations: usize,
+    initial: Option<Vec<[f64; DIMENSIONS]>>,
+    objective: &mut WmnObjective,
+    mut population_trace: Option<&mut PopulationTrace>,
+    mut history: Option<&mut IterationHistory>,
+    rng: &mut impl Rng,
+) -> Result<SearchOutcome, NonFinite> {
+    // Initialize mesh routers randomly
+    let mut mesh_routers = initial.unwrap_or_else(|| {
+        (0..config.routers)
+            .map(|_| random_router(config, rng))
+            .collect()
+    });
+    let fixed_point = config
+        .fixed_point
+        .map(|precision| FixedPoint::new(precision, config.lower_bound, config.upper_bound));
+    if let Some(fixed_point) = &fixed_point {
+        for router in mesh_routers.iter_mut() {
+            *router = fixed_point.quantize(router);
+        }
+    }
+
+    let mut repairs = 0;
+    let mut initial_fitness = objective.fitness(&mesh_routers);
+    repairs += repair_non_finite(
+        config,
+        None,
+        objective,
+        &mut mesh_routers,
+        &mut initial_fitness,
+        rng,
+    )?;
+    let mut best_mesh_routers = mesh_routers.clone();
+    let mut best_fitness = initial_fitness;
+    objective.report_best(None, &best_mesh_routers, best_fitness);
+
+    let mut fidelity_changes = Vec::new();
+    let mut bound_hit_fraction_sum = 0.0;
+    let mut stall = Stall::new(config.early_stopping, best_fitness);
+    let mut stopper = config
+        .termination
+        .as_ref()
+        .map(|termination| Stopper::new(termination, best_fitness));
+    let mut iterations_run = 0;
+    let mut movement_only_iterations = 0;
+    let mut current_fitness = initial_fitness;
+
+    // Firefly Algorithm Iterations
+    for iteration in 0..iterations {
+        iterations_run += 1;
+        let evaluated = evaluates(config.evaluation_interval, iteration, iterations);
+        let iteration_start = Instant::now();
+        let alpha_scale = config.alpha_schedule.scale(iteration, iterations);
+        let niche_counts = config
+            .sharing_radius
+            .map(|fraction| niche_counts(&mesh_routers, fraction * config.domain_diagonal()));
+        // The router serving the most client weight only follows the others
+        let elite = config
+            .elitism
+            .then(|| elite_router(&mesh_routers, objective.clients(), config));
+        let mut hit_bounds = vec![false; config.routers];
+        // Attractiveness of every move, collected only for the history
+        let mut betas = history
+            .as_ref()
+            .is_some_and(|history| history.records_movement())
+            .then(Vec::new);
+        let previous_routers = population_trace.as_ref().map(|_| mesh_routers.clone());
+        for i in 0..config.routers {
+            for j in 0..config.routers {
+                if i != j {
+                    let mut beta = match &fixed_point {
+                        Some(fixed_point) => {
+                            let squared_distance =
+                                fixed_point.squared_distance(&mesh_routers[i], &mesh_routers[j]);
+                            config.beta0 * portable_exp(-config.gamma * squared_distance)
+                        }
+                        None => {
+                            let r_ij = distance(&mesh_routers[i], &mesh_routers[j]);
+                            config.beta0 * (-config.gamma * r_ij * r_ij).exp()
+                        }
+                    };
+                    if let Some(niche_counts) = &niche_counts {
+                        beta /= niche_counts[j];
+                    }
+                    if let Some(betas) = betas.as_mut() {
+                        betas.push(beta);
+                    }
+
+                    let target = mesh_routers[j];
+                    for ((coord, target_coord), alpha) in
+                        mesh_routers[i].iter_mut().zip(target).zip(config.alpha)
+                    {
+                        let attraction = beta * (target_coord - *coord);
+                        // Drawn even for the elite router so the random sequence does
+                        // not depend on elitism
+                        let randomness = alpha * alpha_scale * (rng.r#gen::<f64>() - 0.5);
+                        let randomness = if elite == Some(i) { 0.0 } else { randomness };
+                        let step = attraction + randomness;
+
+                        if !(config.lower_bound..=config.upper_bound).contains(&(*coord + step)) {
+                            hit_bounds[i] = true;
+                        }
+                        *coord = if let Some(fixed_point) = &fixed_point {
+                            fixed_point.step(
+                                *coord,
+                                target_coord,
+                                beta,
+                                randomness,
+                                config.soft_bounds.then_some(BOUNDARY_DAMPING),
+                            )
+                        } else if config.soft_bounds {
+                            soft_clamp(*coord, step, config.lower_bound, config.upper_bound)
+                        } else {
+                            (*coord + step).clamp(config.lower_bound, config.upper_bound)
+                        };
+                    }
+                }
+            }
+        }
+
+        bound_hit_fraction_sum +=
+            hit_bounds.iter().filter(|&&hit| hit).count() as f64 / config.routers as f64;
+
+        // Movement-only iterations keep the last known fitness
+        if evaluated {
+            current_fitness = objective.fitness(&mesh_routers);
+        } else {
+            movement_only_iterations += 1;
+        }
+        repairs += repair_non_finite(
+            config,
+            Some(iteration),
+            objective,
+            &mut mesh_routers,
+            &mut current_fitness,
+            rng,
+        )?;
+        if let (Some(trace), Some(previous)) = (population_trace.as_mut(), &previous_routers) {
+            trace.record(iteration, previous, &mesh_routers, current_fitness);
+        }
+        if evaluated && config.tie_break.prefers(current_fitness, best_fitness) {
+            best_fitness = current_fitness;
+            best_mesh_routers = mesh_routers.clone();
+            objective.report_best(Some(iteration), &best_mesh_routers, best_fitness);
+        }
+
+        // Watchdog: trade metric fidelity for speed when iterations run over budget
+        let elapsed = iteration_start.elapsed();
+        if let Some(budget) = config.iteration_budget
+            && elapsed > budget
+            && let Some(reduced) = objective.sgc_mode.reduced()
+        {
+            objective.sgc_mode = reduced;
+            // Rescore the best layout so later comparisons use the same fidelity
+            best_fitness = objective.fitness(&best_mesh_routers);
+            stall.reset(best_fitness);
+            if let Some(stopper) = stopper.as_mut() {
+                stopper.reset(best_fitness);
+            }
+            fidelity_changes.push(FidelityChange {
+                iteration,
+                elapsed,
+                sgc_mode: reduced,
+            });
+        }
+
+        if let Some(history) = history.as_mut() {
+            if let Some(betas) = &betas {
+                history.record_movement(&MovementParameters::new(
+                    &config.alpha,
+                    alpha_scale,
+                    config.beta0,
+                    config.gamma,
+                    betas,
+                ));
+            }
+            history.record(
+                iteration,
+                &best_mesh_routers,
+                best_fitness,
+                current_fitness,
+                &mesh_routers,
+                objective.clients(),
+            );
+        }
+        let clients = objective.clients();
+        let coverage =
+            || clients.coverage_share(ncmc(&best_mesh_routers, clients, &Radio::new(config)));
+        if evaluated
+            && (stall.update(best_fitness)
+                || stopper
+                    .as_mut()
+                    .is_some_and(|stopper| stopper.update(best_fitness, coverage)))
+        {
+            break;
+        }
+    }
+
+    Ok(SearchOutcome {
+        routers: best_mesh_routers,
+        fitness: best_fitness,
+        fidelity_changes,
+        bound_hit_fraction: bound_hit_fraction_sum / iterations_run.max(1) as f64,
+        repairs,
+        front: None,
+        stopped_after: (iterations_run < iterations).then_some(iterations_run),
+        movement_only_iterations,
+    })
+}
+
+// Router covering the most client weight, the first of them on ties
+fn elite_router(
+    routers: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    config: &FireflyConfig,
+) -> usize {
+    let coverage = router_coverage(routers, clients, &Radio::new(config));
+    let mut elite = 0;
+    for i in 1..coverage.len() {
+        if coverage[i] > coverage[elite] {
+            elite = i;
+        }
+    }
+    elite
+}
+
+fn random_router(config: &FireflyConfig, rng: &mut impl Rng) -> [f64; DIMENSIONS] {
+    let router = std::array::from_fn(|_| rng.gen_range(config.lower_bound..config.upper_bound));
+    match config.fixed_point {
+        Some(precision) => {
+            FixedPoint::new(precision, config.lower_bound, config.upper_bound).quantize(&router)
+        }
+        None => router,
+    }
+}
+
+// Re-randomize or report routers with a non-finite position, and the whole layout if
+// its fitness is still not finite; returns how many fireflies were re-randomized
+fn repair_non_finite(
+    config: &FireflyConfig,
+    iteration: Option<usize>,
+    objective: &mut WmnObjective,
+    routers: &mut [[f64; DIMENSIONS]],
+    fitness: &mut f64,
+    rng: &mut impl Rng,
+) -> Result<usize, NonFinite> {
+    let mut repairs = 0;
+    for (i, router) in routers.iter_mut().enumerate() {
+        // Every router shares the layout's fitness, so only its position is checked here
+        if let Some(non_finite) = NonFinite::detect(iteration, Some(i), router, 0.0) {
+            let non_finite = NonFinite {
+                fitness: *fitness,
+                ..non_finite
+            };
+            if config.non_finite == NonFinitePolicy::Abort {
+                return Err(non_finite);
+            }
+            eprintln!("Warning: {}, re-randomizing it", non_finite);
+            *router = random_router(config, rng);
+            repairs += 1;
+        }
+    }
+    if repairs > 0 {
+        *fitness = objective.fitness(routers);
+    }
+    let position = routers.as_flattened();
+    if let Some(non_finite) = NonFinite::detect(iteration, None, position, *fitness) {
+        if config.non_finite == NonFinitePolicy::Abort {
+            return Err(non_finite);
+        }
+        eprintln!("Warning: {}, re-randomizing it", non_finite);
+        for router in routers.iter_mut() {
+            *router = random_router(config, rng);
+        }
+        *fitness = objective.fitness(routers);
+        repairs += routers.len();
+    }
+    Ok(repairs)
+}
+
+// The shared firefly core with the parameters of a configuration
+fn firefly_core(config: &FireflyConfig, fireflies: usize) -> FireflyCore {
+    FireflyCore {
+        fireflies,
+        iterations: config.iterations,
+        alpha: config.alpha.to_vec(),
+        beta0: config.beta0,
+        gamma: config.gamma,
+        brightness: config.brightness,
+        tie_break: config.tie_break,
+        non_finite: config.non_finite,
+        alpha_schedule: config.alpha_schedule,
+        early_stopping: config.early_stopping,
+        evaluation_interval: config.evaluation_interval,
+    }
+}
+
+// Every firefly is a whole layout, searched by the shared firefly core
+fn population_search(
+    config: &FireflyConfig,
+    fireflies: usize,
+    objective: &mut WmnObjective,
+    mut history: Option<&mut IterationHistory>,
+    rng: &mut impl Rng,
+) -> Result<SearchOutcome, NonFinite> {
+    let core = firefly_core(config, fireflies);
+    let clients = objective.clients();
+    let plugin = objective.plugin;
+    // The core holds the objective, so improvements are scored outside it
+    let mut on_new_best = objective.on_new_best.take();
+    let report_best = |improvement: Improvement| {
+        if let Some(hook) = on_new_best.as_mut() {
+            let routers = layout_from_flat(improvement.best);
+            let metrics = Metrics::evaluate(&routers, clients, config, plugin);
+            hook(NewBest {
+                iteration: improvement.iteration,
+                routers: &routers,
+                fitness: improvement.best_value,
+                metrics: &metrics,
+            });
+        }
+    };
+    let mut stopper = config
+        .termination
+        .as_ref()
+        .map(|termination| Stopper::new(termination, f64::NAN));
+    let observer = |state: IterationState| {
+        if let Some(history) = history.as_mut() {
+            let brightest = (0..state.values.len())
+                .max_by(|&i, &j| compare_fitness(state.values[i], state.values[j]))
+                .expect("the population is not empty");
+            history.record_movement(state.movement);
+            history.record(
+                state.iteration,
+                &layout_from_flat(state.best),
+                state.best_value,
+                state.values.iter().sum::<f64>() / state.values.len() as f64,
+                &layout_from_flat(&state.fireflies[brightest]),
+                clients,
+            );
+        }
+        let coverage = || {
+            let best = layout_from_flat(state.best);
+            clients.coverage_share(ncmc(&best, clients, &Radio::new(config)))
+        };
+        if state.evaluated
+            && stopper
+                .as_mut()
+                .is_some_and(|stopper| stopper.update(state.best_value, coverage))
+        {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    };
+    let result = core.optimize_hooked(objective, Vec::new(), rng, observer, report_best)?;
+    Ok(SearchOutcome {
+        routers: layout_from_flat(&result.best),
+        fitness: result.best_value,
+        fidelity_changes: Vec::new(),
+        bound_hit_fraction: result.bound_hit_fraction,
+        repairs: result.repairs,
+        front: None,
+        stopped_after: (result.iterations < config.iterations).then_some(result.iterations),
+        movement_only_iterations: result.movement_only_iterations,
+    })
+}
+
+// Population search ranking layouts by Pareto dominance; the reported layout is the
+// front's best by fitness
+fn pareto_search(
+    config: &FireflyConfig,
+    fireflies: usize,
+    objective: &mut WmnObjective,
+    mut history: Option<&mut IterationHistory>,
+    rng: &mut impl Rng,
+) -> Result<SearchOutcome, NonFinite> {
+    let core = firefly_core(config, fireflies);
+    let fitness = |objectives: &[f64]| objectives_fitness(config, objectives);
+    let clients = objective.clients();
+    let archive = ParetoArchive::with_pruning(config.pareto_archive, config.pareto_pruning);
+    let result = core.optimize_pareto(objective, archive, rng, |state| {
+        if let Some(history) = history.as_mut() {
+            let front: Vec<f64> = state
+                .front
+                .iter()
+                .map(|point| fitness(&point.objectives))
+                .collect();
+            let values: Vec<f64> = state
+                .objectives
+                .iter()
+                .map(|objectives| fitness(objectives))
+                .collect();
+            let best = fittest(&front, config.tie_break);
+            history.record_movement(state.movement);
+            history.record(
+                state.iteration,
+                &layout_from_flat(&state.front[best].solution),
+                front[best],
+                values.iter().sum::<f64>() / values.len() as f64,
+                &layout_from_flat(&state.fireflies[fittest(&values, config.tie_break)]),
+                clients,
+            );
+        }
+    })?;
+
+    let front: Vec<Vec<[f64; DIMENSIONS]>> = result
+        .front
+        .iter()
+        .map(|point| layout_from_flat(&point.solution))
+        .collect();
+    let fitness: Vec<f64> = front
+        .iter()
+        .map(|layout| objective.fitness(layout))
+        .collect();
+    let best = fittest(&fitness, config.tie_break);
+    Ok(SearchOutcome {
+        routers: front[best].clone(),
+        fitness: fitness[best],
+        fidelity_changes: Vec::new(),
+        bound_hit_fraction: result.bound_hit_fraction,
+        repairs: result.repairs,
+        front: Some(front),
+        stopped_after: (result.iterations < config.iterations).then_some(result.iterations),
+        // A Pareto search evaluates every iteration
+        movement_only_iterations: 0,
+    })
+}
+
+// Index of the highest fitness, ties resolved by `tie_break` in index order
+fn fittest(fitness: &[f64], tie_break: TieBreak) -> usize {
+    let mut best = 0;
+    for i in 1..fitness.len() {
+        if tie_break.prefers(fitness[i], fitness[best]) {
+            best = i;
+        }
+    }
+    best
+}
+
+// Move a coordinate by `step`, damping the part of the move that would cross a boundary
+fn soft_clamp(coord: f64, step: f64, lower_bound: f64, upper_bound: f64) -> f64 {
+    let moved = coord + step;
+    if moved > upper_bound {
+        coord + (upper_bound - coord) * BOUNDARY_DAMPING
+    } else if moved < lower_bound {
+        coord - (coord - lower_bound) * BOUNDARY_DAMPING
+    } else {
+        moved
+    }
+}
+
+// Fitness-sharing niche count of every router: how crowded its neighbourhood is within `radius`
+fn niche_counts(routers: &[[f64; DIMENSIONS]], radius: f64) -> Vec<f64> {
+    routers
+        .iter()
+        .map(|router| {
+            routers
+                .iter()
+                .map(|other| distance(router, other))
+                .filter(|&dist| dist < radius)
+                .map(|dist| 1.0 - (dist / radius) * (dist / radius))
+                .sum()
+        })
+        .collect()
+}
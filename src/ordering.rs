@@ -0,0 +1,51 @@
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+/// Order two fitness values from worse to better (higher is better), with NaN
+/// below every other value so a pathological evaluation can never win
+pub fn compare_fitness(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Less,
+        (false, true) => Ordering::Greater,
+        (false, false) => a.total_cmp(&b),
+    }
+}
+
+/// Which layout to keep when a candidate is exactly as fit as the incumbent best
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TieBreak {
+    /// Keep the layout found first
+    #[default]
+    KeepIncumbent,
+    /// Replace it with the layout found later
+    PreferNewer,
+}
+
+impl TieBreak {
+    /// Whether `candidate` should replace `incumbent` as the best fitness
+    pub fn prefers(self, candidate: f64, incumbent: f64) -> bool {
+        match compare_fitness(candidate, incumbent) {
+            Ordering::Greater => true,
+            Ordering::Equal => self == TieBreak::PreferNewer,
+            Ordering::Less => false,
+        }
+    }
+}
+
+impl FromStr for TieBreak {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<TieBreak, String> {
+        match value {
+            "keep-incumbent" => Ok(TieBreak::KeepIncumbent),
+            "prefer-newer" => Ok(TieBreak::PreferNewer),
+            _ => Err(format!(
+                "unknown tie-break policy {}, expected keep-incumbent or prefer-newer",
+                value
+            )),
+        }
+    }
+}
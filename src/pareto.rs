@@ -0,0 +1,361 @@
+use crate::FireflyCore;
+use crate::firefly_core::{MovementParameters, random_position};
+use crate::objective::MultiObjective;
+use crate::sanity::{NonFinite, NonFinitePolicy};
+use crate::schedule::Stall;
+use rand::Rng;
+
+/// Solutions kept on the front of a Pareto search unless configured otherwise
+pub const ARCHIVE_SIZE: usize = 50;
+
+/// Whether `a` is at least as good as `b` in every objective and better in at least
+/// one, all objectives being maximized
+pub fn dominates(a: &[f64], b: &[f64]) -> bool {
+    a.iter().zip(b).all(|(a, b)| a >= b) && a.iter().zip(b).any(|(a, b)| a > b)
+}
+
+/// A solution on a Pareto front with its objective values
+#[derive(Clone, Debug)]
+pub struct ParetoPoint {
+    pub solution: Vec<f64>,
+    pub objectives: Vec<f64>,
+}
+
+/// How a [`ParetoArchive`] keeps its front small
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ArchivePruning {
+    /// Only bound the front, dropping the solution in its most crowded part when full
+    #[default]
+    Crowding,
+    /// Split every objective into boxes `epsilon` wide and keep at most one solution
+    /// per box, the one nearest the box's best corner; a solution whose box another
+    /// box dominates is not kept at all. The front stays bounded as when crowding.
+    EpsilonGrid { epsilon: f64 },
+}
+
+impl ArchivePruning {
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            ArchivePruning::Crowding => Ok(()),
+            ArchivePruning::EpsilonGrid { epsilon } => {
+                if epsilon > 0.0 && epsilon.is_finite() {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "the Pareto epsilon must be positive and finite, got {}",
+                        epsilon
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ArchivePruning {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ArchivePruning::Crowding => write!(f, "crowding"),
+            ArchivePruning::EpsilonGrid { epsilon } => write!(f, "epsilon grid of {}", epsilon),
+        }
+    }
+}
+
+/// Bounded set of mutually non-dominated solutions.
+///
+/// Once full, the solution in the most crowded part of the front is dropped, so the
+/// archive keeps both ends of the front and spreads out between them. An
+/// [`ArchivePruning::EpsilonGrid`] also thins out near-duplicates long before that.
+pub struct ParetoArchive {
+    capacity: usize,
+    pruning: ArchivePruning,
+    points: Vec<ParetoPoint>,
+}
+
+impl ParetoArchive {
+    pub fn new(capacity: usize) -> ParetoArchive {
+        ParetoArchive::with_pruning(capacity, ArchivePruning::Crowding)
+    }
+
+    pub fn with_pruning(capacity: usize, pruning: ArchivePruning) -> ParetoArchive {
+        ParetoArchive {
+            capacity: capacity.max(1),
+            pruning,
+            points: Vec::new(),
+        }
+    }
+
+    /// Add a solution unless an archived one dominates or equals it, dropping the ones
+    /// it dominates; returns whether the solution was kept
+    pub fn offer(&mut self, solution: &[f64], objectives: &[f64]) -> bool {
+        if !objectives.iter().all(|value| value.is_finite())
+            || self.points.iter().any(|point| {
+                point.objectives == objectives || dominates(&point.objectives, objectives)
+            })
+        {
+            return false;
+        }
+        if let ArchivePruning::EpsilonGrid { epsilon } = self.pruning {
+            let cell = grid_cell(objectives, epsilon);
+            for (i, point) in self.points.iter().enumerate() {
+                let other = grid_cell(&point.objectives, epsilon);
+                if dominates(&other, &cell) {
+                    return false;
+                }
+                // Neither dominates the other, so keep the one nearer the best corner
+                if other == cell {
+                    if corner_distance(&point.objectives, &cell, epsilon)
+                        <= corner_distance(objectives, &cell, epsilon)
+                    {
+                        return false;
+                    }
+                    self.points.remove(i);
+                    break;
+                }
+            }
+            self.points
+                .retain(|point| !dominates(&cell, &grid_cell(&point.objectives, epsilon)));
+        }
+        self.points
+            .retain(|point| !dominates(objectives, &point.objectives));
+        self.points.push(ParetoPoint {
+            solution: solution.to_vec(),
+            objectives: objectives.to_vec(),
+        });
+        if self.points.len() > self.capacity {
+            let crowding = crowding_distances(&self.points);
+            let most_crowded = (0..self.points.len())
+                .min_by(|&i, &j| crowding[i].total_cmp(&crowding[j]))
+                .expect("the archive is not empty");
+            self.points.remove(most_crowded);
+            return most_crowded != self.points.len();
+        }
+        true
+    }
+
+    pub fn points(&self) -> &[ParetoPoint] {
+        &self.points
+    }
+
+    pub fn into_points(self) -> Vec<ParetoPoint> {
+        self.points
+    }
+}
+
+// Box of the epsilon grid holding `objectives`, as the index of the box along each
+// objective
+fn grid_cell(objectives: &[f64], epsilon: f64) -> Vec<f64> {
+    objectives
+        .iter()
+        .map(|value| (value / epsilon).floor())
+        .collect()
+}
+
+// Distance from `objectives` to the best corner of their box `cell`, all objectives
+// being maximized
+fn corner_distance(objectives: &[f64], cell: &[f64], epsilon: f64) -> f64 {
+    objectives
+        .iter()
+        .zip(cell)
+        .map(|(value, index)| ((index + 1.0) * epsilon - value).powi(2))
+        .sum::<f64>()
+        .sqrt()
+}
+
+// Crowding distance of every point: the sum over the objectives of the normalized gap
+// between its two neighbours along that objective, infinite for the extremes
+fn crowding_distances(points: &[ParetoPoint]) -> Vec<f64> {
+    let mut crowding = vec![0.0; points.len()];
+    let objectives = points.first().map_or(0, |point| point.objectives.len());
+    for m in 0..objectives {
+        let mut order: Vec<usize> = (0..points.len()).collect();
+        order.sort_by(|&i, &j| points[i].objectives[m].total_cmp(&points[j].objectives[m]));
+        let (first, last) = (order[0], order[order.len() - 1]);
+        let range = points[last].objectives[m] - points[first].objectives[m];
+        crowding[first] = f64::INFINITY;
+        crowding[last] = f64::INFINITY;
+        if range > 0.0 {
+            for window in order.windows(3) {
+                crowding[window[1]] +=
+                    (points[window[2]].objectives[m] - points[window[0]].objectives[m]) / range;
+            }
+        }
+    }
+    crowding
+}
+
+/// Non-dominated solutions found by [`FireflyCore::optimize_pareto`]
+#[derive(Clone, Debug)]
+pub struct ParetoResult {
+    pub front: Vec<ParetoPoint>,
+    pub evaluations: usize,
+    /// Iterations run, fewer than configured when stopped early
+    pub iterations: usize,
+    /// Average fraction of fireflies per iteration whose move crossed a bound
+    pub bound_hit_fraction: f64,
+    /// Fireflies re-randomized because their position or objectives were not finite
+    pub repairs: usize,
+}
+
+/// The population after one iteration, as seen by the observer of
+/// [`FireflyCore::optimize_pareto`]
+pub struct ParetoState<'a> {
+    pub iteration: usize,
+    pub fireflies: &'a [Vec<f64>],
+    /// Objective values of every firefly
+    pub objectives: &'a [Vec<f64>],
+    /// Non-dominated solutions found so far
+    pub front: &'a [ParetoPoint],
+    /// Movement equation the iteration applied
+    pub movement: &'a MovementParameters,
+}
+
+impl FireflyCore {
+    /// Search several objectives at once and return the non-dominated solutions found.
+    ///
+    /// A firefly is brighter than another when it dominates it: every firefly moves
+    /// toward each firefly dominating it, and fireflies no other dominates take a
+    /// random step instead. Every evaluated solution is offered to `archive`, which
+    /// becomes the returned front.
+    pub fn optimize_pareto<O: MultiObjective + ?Sized>(
+        &self,
+        objective: &mut O,
+        mut archive: ParetoArchive,
+        rng: &mut impl Rng,
+        mut observer: impl FnMut(ParetoState),
+    ) -> Result<ParetoResult, NonFinite> {
+        let dimensions = objective.dimensions();
+        let bounds: Vec<(f64, f64)> = (0..dimensions).map(|d| objective.bounds(d)).collect();
+
+        let mut fireflies: Vec<Vec<f64>> = (0..self.fireflies)
+            .map(|_| random_position(&bounds, rng))
+            .collect();
+        let mut values: Vec<Vec<f64>> = fireflies
+            .iter()
+            .map(|firefly| objective.evaluate(firefly))
+            .collect();
+        let mut evaluations = fireflies.len();
+        let repairs =
+            self.check_finite_pareto(None, objective, &bounds, &mut fireflies, &mut values, rng)?;
+        evaluations += repairs.len();
+        let mut repair_count = repairs.len();
+        for (firefly, value) in fireflies.iter().zip(&values) {
+            archive.offer(firefly, value);
+        }
+        let mut bound_hit_fraction_sum = 0.0;
+        // The front improves whenever the archive takes a new solution
+        let mut stall = Stall::new(self.early_stopping, f64::NAN);
+        let mut iterations = 0;
+
+        for iteration in 0..self.iterations {
+            iterations += 1;
+            let alpha_scale = self.alpha_schedule.scale(iteration, self.iterations);
+            let mut improved = false;
+            let mut hit_bounds = vec![false; fireflies.len()];
+            let mut betas = Vec::new();
+            for i in 0..fireflies.len() {
+                let mut attracted = false;
+                for j in 0..fireflies.len() {
+                    if dominates(&values[j], &values[i]) {
+                        attracted = true;
+                        let target = objective
+                            .align(&fireflies[i], &fireflies[j])
+                            .unwrap_or_else(|| fireflies[j].clone());
+                        let attraction = self.attract(
+                            &mut fireflies[i],
+                            &target,
+                            &bounds,
+                            alpha_scale,
+                            1.0,
+                            rng,
+                        );
+                        hit_bounds[i] |= attraction.hit_bound;
+                        betas.push(attraction.beta);
+                        values[i] = objective.evaluate(&fireflies[i]);
+                        evaluations += 1;
+                        improved |= archive.offer(&fireflies[i], &values[i]);
+                    }
+                }
+                // Non-dominated fireflies explore around their position: a move toward
+                // their own position is only the random step
+                if !attracted {
+                    let target = fireflies[i].clone();
+                    let attraction =
+                        self.attract(&mut fireflies[i], &target, &bounds, alpha_scale, 1.0, rng);
+                    hit_bounds[i] |= attraction.hit_bound;
+                    betas.push(attraction.beta);
+                    values[i] = objective.evaluate(&fireflies[i]);
+                    evaluations += 1;
+                    improved |= archive.offer(&fireflies[i], &values[i]);
+                }
+            }
+            bound_hit_fraction_sum +=
+                hit_bounds.iter().filter(|&&hit| hit).count() as f64 / fireflies.len() as f64;
+
+            let repairs = self.check_finite_pareto(
+                Some(iteration),
+                objective,
+                &bounds,
+                &mut fireflies,
+                &mut values,
+                rng,
+            )?;
+            evaluations += repairs.len();
+            repair_count += repairs.len();
+            for &i in &repairs {
+                improved |= archive.offer(&fireflies[i], &values[i]);
+            }
+
+            observer(ParetoState {
+                iteration,
+                fireflies: &fireflies,
+                objectives: &values,
+                front: archive.points(),
+                movement: &self.movement(alpha_scale, &betas),
+            });
+            if stall.update_improved(improved) {
+                break;
+            }
+        }
+
+        Ok(ParetoResult {
+            front: archive.into_points(),
+            evaluations,
+            iterations,
+            bound_hit_fraction: bound_hit_fraction_sum / iterations.max(1) as f64,
+            repairs: repair_count,
+        })
+    }
+
+    // Re-randomize or report fireflies whose position or objectives are not finite,
+    // returning the indices of the re-randomized ones
+    fn check_finite_pareto<O: MultiObjective + ?Sized>(
+        &self,
+        iteration: Option<usize>,
+        objective: &mut O,
+        bounds: &[(f64, f64)],
+        fireflies: &mut [Vec<f64>],
+        values: &mut [Vec<f64>],
+        rng: &mut impl Rng,
+    ) -> Result<Vec<usize>, NonFinite> {
+        let mut repairs = Vec::new();
+        for i in 0..fireflies.len() {
+            // The first non-finite objective, or any when all are finite
+            let value = values[i]
+                .iter()
+                .copied()
+                .find(|value| !value.is_finite())
+                .or(values[i].first().copied())
+                .unwrap_or(0.0);
+            if let Some(non_finite) = NonFinite::detect(iteration, Some(i), &fireflies[i], value) {
+                if self.non_finite == NonFinitePolicy::Abort {
+                    return Err(non_finite);
+                }
+                eprintln!("Warning: {}, re-randomizing it", non_finite);
+                fireflies[i] = random_position(bounds, rng);
+                values[i] = objective.evaluate(&fireflies[i]);
+                repairs.push(i);
+            }
+        }
+        Ok(repairs)
+    }
+}
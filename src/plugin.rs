@@ -0,0 +1,67 @@
+use libloading::Library;
+use std::ffi::c_uint;
+
+/// Version of the objective plugin ABI this build understands
+pub const PLUGIN_ABI_VERSION: c_uint = 1;
+
+// Plugins are shared libraries exporting two C functions:
+//
+//     unsigned int firefly_plugin_abi_version(void);
+//     double firefly_objective(const double *routers, size_t router_count,
+//                              const double *clients, size_t client_count,
+//                              size_t dimensions);
+//
+// Router and client coordinates are passed row-major with `dimensions` values
+// per point, and the returned fitness is maximized like the built-in one.
+type AbiVersionFn = unsafe extern "C" fn() -> c_uint;
+type ObjectiveFn = unsafe extern "C" fn(*const f64, usize, *const f64, usize, usize) -> f64;
+
+/// Objective function loaded from a plugin shared library at runtime
+pub struct ObjectivePlugin {
+    objective: ObjectiveFn,
+    path: String,
+    // Keeps the library mapped for as long as `objective` may be called
+    _library: Library,
+}
+
+impl ObjectivePlugin {
+    pub fn load(path: &str) -> Result<ObjectivePlugin, String> {
+        let error = |err: libloading::Error| format!("Unable to load plugin {}: {}", path, err);
+        // SAFETY: loading runs the library's initializers; plugins are trusted code chosen by the user
+        let library = unsafe { Library::new(path) }.map_err(error)?;
+        // SAFETY: the symbol types match the documented plugin ABI
+        let version =
+            unsafe { library.get::<AbiVersionFn>(b"firefly_plugin_abi_version") }.map_err(error)?;
+        let version = unsafe { version() };
+        if version != PLUGIN_ABI_VERSION {
+            return Err(format!(
+                "Plugin {} uses ABI version {}, expected {}",
+                path, version, PLUGIN_ABI_VERSION
+            ));
+        }
+        let objective =
+            *unsafe { library.get::<ObjectiveFn>(b"firefly_objective") }.map_err(error)?;
+        Ok(ObjectivePlugin {
+            objective,
+            path: path.to_string(),
+            _library: library,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn evaluate<const D: usize>(&self, routers: &[[f64; D]], clients: &[[f64; D]]) -> f64 {
+        // SAFETY: both pointers cover `len * D` contiguous f64 values that outlive the call
+        unsafe {
+            (self.objective)(
+                routers.as_ptr().cast(),
+                routers.len(),
+                clients.as_ptr().cast(),
+                clients.len(),
+                D,
+            )
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{DIMENSIONS, distance};
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+
+enum TraceWriter {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+}
+
+/// Per-iteration, per-firefly CSV stream for studying the algorithm's dynamics,
+/// gzip-compressed when the path ends in `.gz`.
+///
+/// Every router is a firefly of the same layout, so all fireflies of an iteration
+/// share that layout's fitness as their brightness.
+pub struct PopulationTrace {
+    writer: TraceWriter,
+}
+
+impl PopulationTrace {
+    pub fn create(path: &str) -> std::io::Result<PopulationTrace> {
+        let file = BufWriter::new(File::create(path)?);
+        let writer = if path.ends_with(".gz") {
+            TraceWriter::Gzip(GzEncoder::new(file, Compression::default()))
+        } else {
+            TraceWriter::Plain(file)
+        };
+        let mut trace = PopulationTrace { writer };
+        trace.write_line(format_args!(
+            "iteration,firefly,x,y,displacement,brightness"
+        ));
+        Ok(trace)
+    }
+
+    fn write_line(&mut self, line: std::fmt::Arguments) {
+        let result = match &mut self.writer {
+            TraceWriter::Plain(writer) => writeln!(writer, "{}", line),
+            TraceWriter::Gzip(writer) => writeln!(writer, "{}", line),
+        };
+        result.expect("Unable to write population trace");
+    }
+
+    pub fn record(
+        &mut self,
+        iteration: usize,
+        previous: &[[f64; DIMENSIONS]],
+        current: &[[f64; DIMENSIONS]],
+        brightness: f64,
+    ) {
+        for (firefly, (before, after)) in previous.iter().zip(current).enumerate() {
+            self.write_line(format_args!(
+                "{},{},{},{},{},{}",
+                iteration,
+                firefly,
+                after[0],
+                after[1],
+                distance(before, after),
+                brightness
+            ));
+        }
+    }
+
+    pub fn finish(self) {
+        let result = match self.writer {
+            TraceWriter::Plain(mut writer) => writer.flush(),
+            TraceWriter::Gzip(writer) => writer.finish().and_then(|mut file| file.flush()),
+        };
+        result.expect("Unable to write population trace");
+    }
+}
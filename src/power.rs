@@ -0,0 +1,263 @@
+//! Transmit power and channel planning for routers that stay where they are.
+//!
+//! Moving installed hardware is expensive; turning its power up or down and changing
+//! its channel is not. The power search keeps the router positions fixed and lets the
+//! [`FireflyCore`] choose every router's transmit power, which sets its radius through
+//! the path-loss model, and optionally its channel. Covering clients and keeping the
+//! routers connected pulls the power up; the coverage overlap of routers on the same
+//! channel, their interference, pulls it down.
+
+use crate::heuristics::gamma_for_domain;
+use crate::metrics::{ConnectivityGraph, co_channel_overlap, ncmc, sgc};
+use crate::objective::{Direction, Objective};
+use crate::radio::{Radio, RadioModel};
+use crate::sanity::NonFinite;
+use crate::{ClientSet, DIMENSIONS, FireflyConfig, FireflyCore, PathLoss};
+use rand::Rng;
+use serde_json::json;
+
+/// Bounds and weights of a power search
+#[derive(Clone, Debug, PartialEq)]
+pub struct PowerSettings {
+    /// Lowest transmit power in dBm
+    pub min_power: f64,
+    /// Highest transmit power in dBm
+    pub max_power: f64,
+    /// Non-overlapping channels to choose from; 1 plans power alone
+    pub channels: usize,
+    /// Fitness penalty per disk area of co-channel coverage overlap
+    pub interference_weight: f64,
+    pub fireflies: usize,
+}
+
+impl Default for PowerSettings {
+    fn default() -> PowerSettings {
+        PowerSettings {
+            min_power: 0.0,
+            max_power: PathLoss::default().tx_power,
+            channels: 1,
+            interference_weight: 0.05,
+            fireflies: 20,
+        }
+    }
+}
+
+impl PowerSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(self.min_power.is_finite() && self.max_power.is_finite())
+            || self.min_power > self.max_power
+        {
+            return Err(format!(
+                "invalid power range {} to {} dBm",
+                self.min_power, self.max_power
+            ));
+        }
+        if self.channels == 0 {
+            return Err("at least one channel is needed".to_string());
+        }
+        if !(self.interference_weight >= 0.0 && self.interference_weight.is_finite()) {
+            return Err("the interference weight must be finite and non-negative".to_string());
+        }
+        if self.fireflies == 0 {
+            return Err("at least one firefly is needed".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Transmit power and channel of every router with the metrics they achieve
+#[derive(Clone, Debug, PartialEq)]
+pub struct PowerPlan {
+    /// Transmit power of every router in dBm
+    pub tx_power: Vec<f64>,
+    /// Channel of every router, from 0
+    pub channels: Vec<usize>,
+    /// Radius of every router at its power
+    pub radii: Vec<f64>,
+    pub fitness: f64,
+    pub sgc: usize,
+    /// Weighted number of covered clients
+    pub ncmc: f64,
+    /// Coverage overlap of routers on the same channel, in disk areas
+    pub interference: f64,
+}
+
+impl PowerPlan {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "tx_power": self.tx_power,
+            "channels": self.channels,
+            "radii": self.radii,
+            "fitness": self.fitness,
+            "sgc": self.sgc,
+            "ncmc": self.ncmc,
+            "interference": self.interference
+        })
+    }
+}
+
+/// Fitness of the power and channel of fixed routers.
+///
+/// A solution holds each router's power followed by its channel, the latter only with
+/// more than one channel; channels are the continuous values floored.
+pub struct PowerObjective<'a> {
+    config: &'a FireflyConfig,
+    routers: &'a [[f64; DIMENSIONS]],
+    clients: &'a ClientSet,
+    settings: &'a PowerSettings,
+    path_loss: PathLoss,
+}
+
+impl<'a> PowerObjective<'a> {
+    /// Objective over the routers of `routers`, turning powers into radii with the
+    /// path-loss model of `config`, or the default model without one
+    pub fn new(
+        config: &'a FireflyConfig,
+        routers: &'a [[f64; DIMENSIONS]],
+        clients: &'a ClientSet,
+        settings: &'a PowerSettings,
+    ) -> PowerObjective<'a> {
+        PowerObjective {
+            config,
+            routers,
+            clients,
+            settings,
+            path_loss: config.radio.path_loss.unwrap_or_default(),
+        }
+    }
+
+    // Values per router in a solution
+    fn stride(&self) -> usize {
+        if self.settings.channels > 1 { 2 } else { 1 }
+    }
+
+    /// The solution with every router at the same power and channel
+    pub fn uniform(&self, tx_power: f64, channel: usize) -> Vec<f64> {
+        let router = [tx_power, channel as f64];
+        (0..self.routers.len())
+            .flat_map(|_| router[..self.stride()].to_vec())
+            .collect()
+    }
+
+    /// Decode and score a solution
+    pub fn plan(&self, x: &[f64]) -> PowerPlan {
+        let stride = self.stride();
+        let tx_power: Vec<f64> = x.iter().step_by(stride).copied().collect();
+        let channels: Vec<usize> = match stride {
+            1 => vec![0; self.routers.len()],
+            _ => x
+                .iter()
+                .skip(1)
+                .step_by(stride)
+                .map(|&channel| (channel.max(0.0) as usize).min(self.settings.channels - 1))
+                .collect(),
+        };
+        let radii: Vec<f64> = tx_power
+            .iter()
+            .map(|&tx_power| {
+                PathLoss {
+                    tx_power,
+                    ..self.path_loss
+                }
+                .range()
+            })
+            .collect();
+
+        // The radii decide both links and coverage, as the path-loss model would
+        let model = RadioModel {
+            radii: radii.clone(),
+            path_loss: None,
+            ..self.config.radio.clone()
+        };
+        let radio = Radio::with_model(self.config.max_communication_distance, &model);
+        let sgc = sgc(&ConnectivityGraph::new(self.routers, &radio));
+        let ncmc = ncmc(self.routers, self.clients, &radio);
+        let interference = co_channel_overlap(self.routers, &radio, &channels);
+        let weights = &self.config.weights;
+        let fitness = weights.fitness(sgc as f64, ncmc, ncmc / self.routers.len() as f64, 0.0)
+            - self.settings.interference_weight * interference;
+        PowerPlan {
+            tx_power,
+            channels,
+            radii,
+            fitness,
+            sgc,
+            ncmc,
+            interference,
+        }
+    }
+}
+
+impl Objective for PowerObjective<'_> {
+    fn dimensions(&self) -> usize {
+        self.routers.len() * self.stride()
+    }
+
+    fn bounds(&self, dimension: usize) -> (f64, f64) {
+        match dimension % self.stride() {
+            0 => (self.settings.min_power, self.settings.max_power),
+            _ => (0.0, self.settings.channels as f64),
+        }
+    }
+
+    fn direction(&self) -> Direction {
+        Direction::Maximize
+    }
+
+    fn evaluate(&mut self, x: &[f64]) -> f64 {
+        self.plan(x).fitness
+    }
+}
+
+/// Plan the power and channels of routers fixed at `routers`, returning the plan of
+/// every router at the highest power on the first channel followed by the best plan
+/// found, which is never worse. The search runs `config.iterations` iterations with
+/// the configured step sizes scaled from the deployment area to the power range.
+pub fn optimize_power(
+    config: &FireflyConfig,
+    routers: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    settings: &PowerSettings,
+    rng: &mut impl Rng,
+) -> Result<(PowerPlan, PowerPlan), NonFinite> {
+    let mut objective = PowerObjective::new(config, routers, clients, settings);
+    let baseline = objective.plan(&objective.uniform(settings.max_power, 0));
+
+    let power_extent = settings.max_power - settings.min_power;
+    // The same step relative to the range as the routers take relative to the area
+    let mut alpha = vec![config.alpha[0] / config.extent() * power_extent];
+    if settings.channels > 1 {
+        alpha.push(config.alpha[0] / config.extent() * settings.channels as f64);
+    }
+    let core = FireflyCore {
+        fireflies: settings.fireflies,
+        iterations: config.iterations,
+        alpha,
+        beta0: config.beta0,
+        gamma: gamma_for_domain(power_extent.max(f64::EPSILON), objective.dimensions()),
+        brightness: config.brightness,
+        tie_break: config.tie_break,
+        non_finite: config.non_finite,
+        alpha_schedule: config.alpha_schedule,
+        early_stopping: config.early_stopping,
+        evaluation_interval: config.evaluation_interval,
+    };
+    // Start from the routers at full power and variations on random channels with some
+    // routers turned down, since a random plan is usually far worse
+    let mut start = vec![objective.uniform(settings.max_power, 0)];
+    while start.len() < settings.fireflies {
+        start.push(
+            start[0]
+                .iter()
+                .enumerate()
+                .map(|(dimension, &value)| match dimension % objective.stride() {
+                    0 if rng.gen_bool(0.5) => value - rng.gen_range(0.0..=power_extent / 4.0),
+                    0 => value,
+                    _ => rng.gen_range(0.0..settings.channels as f64),
+                })
+                .collect(),
+        );
+    }
+    let result = core.optimize_from(&mut objective, start, rng, |_| {})?;
+    Ok((baseline, objective.plan(&result.best)))
+}
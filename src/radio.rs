@@ -0,0 +1,232 @@
+use crate::{DIMENSIONS, FireflyConfig};
+
+/// Log-distance path-loss model deciding client coverage by received signal strength.
+///
+/// A router's signal arrives at distance `d` with power
+/// `tx_power - reference_loss - 10 · exponent · log10(d)`, taking distances below the
+/// reference distance of 1 as 1; a client is covered when that power reaches the
+/// `sensitivity`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PathLoss {
+    /// Transmit power in dBm
+    pub tx_power: f64,
+    /// Path loss at the reference distance of 1, in dB
+    pub reference_loss: f64,
+    /// Path-loss exponent: 2 in free space, about 3 to 5 indoors or in cities
+    pub exponent: f64,
+    /// Weakest usable received power in dBm
+    pub sensitivity: f64,
+}
+
+impl Default for PathLoss {
+    // About the default communication distance of 4.5
+    fn default() -> PathLoss {
+        PathLoss {
+            tx_power: 20.0,
+            reference_loss: 40.0,
+            exponent: 3.0,
+            sensitivity: -40.0,
+        }
+    }
+}
+
+impl PathLoss {
+    /// Received power in dBm at `distance` from a router
+    pub fn received_power(&self, distance: f64) -> f64 {
+        self.tx_power - self.reference_loss - 10.0 * self.exponent * distance.max(1.0).log10()
+    }
+
+    /// Distance up to which the received power reaches the sensitivity, 0 when it
+    /// never does
+    pub fn range(&self) -> f64 {
+        let margin = self.tx_power - self.reference_loss - self.sensitivity;
+        if margin < 0.0 {
+            return 0.0;
+        }
+        10f64.powf(margin / (10.0 * self.exponent))
+    }
+
+    /// Transmit power in dBm whose range is `range`, the inverse of
+    /// [`range`](PathLoss::range)
+    pub fn power_for_range(&self, range: f64) -> f64 {
+        self.sensitivity + self.reference_loss + 10.0 * self.exponent * range.max(1.0).log10()
+    }
+}
+
+/// Straight obstacle, such as a building wall, that no link or coverage crosses
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wall {
+    pub from: [f64; DIMENSIONS],
+    pub to: [f64; DIMENSIONS],
+}
+
+impl Wall {
+    /// A wall given as `[x0, y0, x1, y1]`
+    pub fn from_ends(ends: [f64; 4]) -> Wall {
+        Wall {
+            from: [ends[0], ends[1]],
+            to: [ends[2], ends[3]],
+        }
+    }
+
+    /// Whether the straight path from `a` to `b` crosses the wall; a path touching
+    /// the wall or running along it counts as crossing.
+    ///
+    /// ```
+    /// use ff_wmn::radio::Wall;
+    ///
+    /// let wall = Wall { from: [5.0, 0.0], to: [5.0, 10.0] };
+    /// assert!(wall.blocks(&[2.0, 3.0], &[8.0, 4.0]));
+    /// assert!(!wall.blocks(&[2.0, 3.0], &[4.0, 12.0]));
+    /// ```
+    pub fn blocks(&self, a: &[f64; DIMENSIONS], b: &[f64; DIMENSIONS]) -> bool {
+        // Side of the line through p and q that r is on, 0 on the line
+        let side = |p: &[f64; DIMENSIONS], q: &[f64; DIMENSIONS], r: &[f64; DIMENSIONS]| {
+            let cross = (q[0] - p[0]) * (r[1] - p[1]) - (q[1] - p[1]) * (r[0] - p[0]);
+            (cross > 0.0) as i8 - (cross < 0.0) as i8
+        };
+        // Whether r, on the line through p and q, lies between them
+        let between = |p: &[f64; DIMENSIONS], q: &[f64; DIMENSIONS], r: &[f64; DIMENSIONS]| {
+            (0..2).all(|axis| p[axis].min(q[axis]) <= r[axis] && r[axis] <= p[axis].max(q[axis]))
+        };
+        let (c, d) = (&self.from, &self.to);
+        let sides = [side(a, b, c), side(a, b, d), side(c, d, a), side(c, d, b)];
+        (sides[0] * sides[1] < 0 && sides[2] * sides[3] < 0)
+            || (sides[0] == 0 && between(a, b, c))
+            || (sides[1] == 0 && between(a, b, d))
+            || (sides[2] == 0 && between(c, d, a))
+            || (sides[3] == 0 && between(c, d, b))
+    }
+}
+
+/// Radio characteristics of the routers beyond one shared communication distance.
+///
+/// The default model gives every router the communication distance as its radius,
+/// for both router links and client coverage, and places no constraint on router
+/// separation.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RadioModel {
+    /// Transmit radius of each router by index; routers past the end of the list use
+    /// the communication distance. Two routers are linked when each is within the
+    /// other's radius.
+    pub radii: Vec<f64>,
+    /// Routers closer than this to each other violate the separation constraint; 0
+    /// disables it
+    pub min_separation: f64,
+    /// Fitness penalty per pair of routers violating the separation constraint
+    pub separation_penalty: f64,
+    /// Cover clients by received signal strength instead of the routers' radii
+    pub path_loss: Option<PathLoss>,
+    /// Obstacles blocking every link and coverage that crosses them
+    pub walls: Vec<Wall>,
+}
+
+impl RadioModel {
+    /// Fitness penalty for `violations` router pairs closer than the minimum separation
+    pub fn penalty(&self, violations: usize) -> f64 {
+        self.separation_penalty * violations as f64
+    }
+
+    /// The model of the routers `routers` alone, by their index in this model, each
+    /// keeping its own radius; `radius` is the communication distance of the others
+    pub fn subset(&self, radius: f64, routers: &[usize]) -> RadioModel {
+        let radio = Radio::with_model(radius, self);
+        RadioModel {
+            radii: if self.radii.is_empty() {
+                Vec::new()
+            } else {
+                routers.iter().map(|&i| radio.radius(i)).collect()
+            },
+            ..self.clone()
+        }
+    }
+}
+
+// Model of radios that all have the communication distance as their radius
+static UNIFORM: RadioModel = RadioModel {
+    radii: Vec::new(),
+    min_separation: 0.0,
+    separation_penalty: 0.0,
+    path_loss: None,
+    walls: Vec::new(),
+};
+
+/// A [`RadioModel`] together with the communication distance it falls back to, as
+/// used by the metrics
+#[derive(Clone, Copy, Debug)]
+pub struct Radio<'a> {
+    radius: f64,
+    model: &'a RadioModel,
+}
+
+impl<'a> Radio<'a> {
+    /// The radio model of a configuration
+    pub fn new(config: &'a FireflyConfig) -> Radio<'a> {
+        Radio::with_model(config.max_communication_distance, &config.radio)
+    }
+
+    pub fn with_model(radius: f64, model: &'a RadioModel) -> Radio<'a> {
+        Radio { radius, model }
+    }
+
+    /// Every router covering and linking within `radius`
+    pub fn uniform(radius: f64) -> Radio<'static> {
+        Radio::with_model(radius, &UNIFORM)
+    }
+
+    /// The communication distance of routers without a radius of their own
+    pub fn base_radius(&self) -> f64 {
+        self.radius
+    }
+
+    pub fn model(&self) -> &'a RadioModel {
+        self.model
+    }
+
+    /// Transmit radius of a router
+    pub fn radius(&self, router: usize) -> f64 {
+        self.model.radii.get(router).copied().unwrap_or(self.radius)
+    }
+
+    /// Largest transmit radius among the first `routers` routers
+    pub fn max_radius(&self, routers: usize) -> f64 {
+        (0..routers.min(self.model.radii.len()))
+            .map(|router| self.radius(router))
+            .fold(self.radius, f64::max)
+    }
+
+    /// Whether two routers `distance` apart are linked
+    pub fn linked(&self, a: usize, b: usize, distance: f64) -> bool {
+        distance <= self.radius(a).min(self.radius(b))
+    }
+
+    /// Distance up to which a router covers clients
+    pub fn coverage_radius(&self, router: usize) -> f64 {
+        match &self.model.path_loss {
+            Some(path_loss) => path_loss.range(),
+            None => self.radius(router),
+        }
+    }
+
+    /// Largest coverage radius among the first `routers` routers
+    pub fn max_coverage_radius(&self, routers: usize) -> f64 {
+        match &self.model.path_loss {
+            Some(path_loss) => path_loss.range(),
+            None => self.max_radius(routers),
+        }
+    }
+
+    /// Whether a wall stands between two points, so a router at one neither links to
+    /// nor covers the other
+    pub fn blocked(&self, a: &[f64; DIMENSIONS], b: &[f64; DIMENSIONS]) -> bool {
+        self.model.walls.iter().any(|wall| wall.blocks(a, b))
+    }
+
+    /// Whether a router covers a client `distance` away
+    pub fn covers(&self, router: usize, distance: f64) -> bool {
+        match &self.model.path_loss {
+            Some(path_loss) => path_loss.received_power(distance) >= path_loss.sensitivity,
+            None => distance <= self.radius(router),
+        }
+    }
+}
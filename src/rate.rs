@@ -0,0 +1,129 @@
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::str::FromStr;
+
+// 802.11n MCS 0 to 7 on one 20 MHz stream with the long guard interval, fastest first,
+// each usable up to the given share of the coverage radius
+const IEEE_802_11N: [(f64, f64); 8] = [
+    (0.25, 65.0),
+    (0.35, 58.5),
+    (0.45, 52.0),
+    (0.55, 39.0),
+    (0.65, 26.0),
+    (0.75, 19.5),
+    (0.875, 13.0),
+    (1.0, 6.5),
+];
+
+/// Step model of the data rate a client gets from a router at a given distance.
+///
+/// Distances are shares of the router's coverage radius, split into bands each with
+/// the rate of its modulation and coding scheme; clients beyond the last band get
+/// nothing. Rates are PHY rates of a single link, before the airtime routers share
+/// among their clients.
+///
+/// ```
+/// use ff_wmn::rate::RateModel;
+///
+/// let model: RateModel = "0.5:54,1:6".parse().unwrap();
+/// assert_eq!(model.rate(1.0, 4.0), 54.0);
+/// assert_eq!(model.rate(3.0, 4.0), 6.0);
+/// assert_eq!(model.rate(5.0, 4.0), 0.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateModel {
+    // Upper distance share and rate in Mbit/s of every band, nearest first
+    bands: Vec<(f64, f64)>,
+}
+
+impl RateModel {
+    /// Rates of 802.11n MCS 0 to 7 on one 20 MHz stream, the fastest within a quarter
+    /// of the coverage radius
+    pub fn ieee_802_11n() -> RateModel {
+        RateModel {
+            bands: IEEE_802_11N.to_vec(),
+        }
+    }
+
+    /// Bands given as (upper distance share, rate in Mbit/s), nearest first
+    pub fn new(bands: Vec<(f64, f64)>) -> Result<RateModel, String> {
+        if bands.is_empty() {
+            return Err("a rate model needs at least one band".to_string());
+        }
+        let mut previous = 0.0;
+        for &(range, rate) in &bands {
+            if !(range > previous && range.is_finite()) {
+                return Err(format!(
+                    "rate bands need finite, positive and increasing ranges, got {} after {}",
+                    range, previous
+                ));
+            }
+            if !(rate >= 0.0 && rate.is_finite()) {
+                return Err(format!(
+                    "rates must be finite and non-negative, got {}",
+                    rate
+                ));
+            }
+            previous = range;
+        }
+        Ok(RateModel { bands })
+    }
+
+    /// Rate in Mbit/s of a client `distance` from a router covering up to `radius`
+    pub fn rate(&self, distance: f64, radius: f64) -> f64 {
+        let share = distance / radius;
+        self.bands
+            .iter()
+            .find(|&&(range, _)| share <= range)
+            .map_or(0.0, |&(_, rate)| rate)
+    }
+}
+
+impl FromStr for RateModel {
+    type Err = String;
+
+    /// `802.11n`, or bands as `<range share>:<Mbit/s>` separated by commas
+    fn from_str(value: &str) -> Result<RateModel, String> {
+        if value.trim() == "802.11n" {
+            return Ok(RateModel::ieee_802_11n());
+        }
+        let bands = value
+            .split(',')
+            .map(|band| {
+                let parse = |number: &str| number.trim().parse::<f64>().ok();
+                band.split_once(':')
+                    .and_then(|(range, rate)| Some((parse(range)?, parse(rate)?)))
+                    .ok_or_else(|| {
+                        format!(
+                            "invalid rate band {}, expected <range share>:<Mbit/s>",
+                            band
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        RateModel::new(bands)
+    }
+}
+
+impl fmt::Display for RateModel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.bands == IEEE_802_11N {
+            return write!(f, "802.11n");
+        }
+        let bands: Vec<String> = self
+            .bands
+            .iter()
+            .map(|(range, rate)| format!("{}:{}", range, rate))
+            .collect();
+        write!(f, "{}", bands.join(","))
+    }
+}
+
+// Written in scenario files the same way as on the command line
+impl<'de> Deserialize<'de> for RateModel {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<RateModel, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
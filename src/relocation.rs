@@ -0,0 +1,190 @@
+use crate::metrics::ncmc;
+use crate::radio::Radio;
+use crate::{ClientSet, DIMENSIONS, FireflyConfig, distance, layout_distance, match_layouts};
+use serde_json::json;
+
+/// Cost of moving routers away from an installed layout.
+///
+/// When re-planning a live network, every router that moves has to be reinstalled, so
+/// the fitness of a layout drops by `penalty` per unit of mean distance between its
+/// routers and the matched routers of the `anchor` layout.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Relocation {
+    /// The installed layout, with as many routers as the configuration
+    pub anchor: Vec<[f64; DIMENSIONS]>,
+    /// Fitness penalty per unit of mean router displacement
+    pub penalty: f64,
+}
+
+impl Relocation {
+    /// Mean distance the routers of `routers` moved from the anchor layout
+    pub fn displacement(&self, routers: &[[f64; DIMENSIONS]]) -> f64 {
+        layout_distance(&self.anchor, routers)
+    }
+
+    /// Fitness penalty of a mean router displacement
+    pub fn cost(&self, displacement: f64) -> f64 {
+        self.penalty * displacement
+    }
+}
+
+/// Routers displaced by less than this share of the communication distance count as
+/// staying put
+pub const MOVE_TOLERANCE: f64 = 0.05;
+
+/// One router of a relocation plan moved to its new position
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelocationMove {
+    /// Index of the router in the installed layout
+    pub router: usize,
+    pub from: [f64; DIMENSIONS],
+    pub to: [f64; DIMENSIONS],
+    pub distance: f64,
+    /// Coverage share while the router is switched off for the move
+    pub coverage_during: f64,
+    /// Coverage share once the router is back on at its new position
+    pub coverage_after: f64,
+}
+
+/// Order in which to move the routers of an installed layout to a new one, with the
+/// coverage left at every point of the field work
+#[derive(Clone, Debug, PartialEq)]
+pub struct RelocationPlan {
+    /// Lowest coverage share the plan aims to keep
+    pub coverage_floor: f64,
+    /// Coverage share of the installed layout
+    pub initial_coverage: f64,
+    pub moves: Vec<RelocationMove>,
+}
+
+impl RelocationPlan {
+    /// Summed distance of every move
+    pub fn total_distance(&self) -> f64 {
+        self.moves.iter().map(|step| step.distance).sum()
+    }
+
+    /// Lowest coverage share during the plan
+    pub fn lowest_coverage(&self) -> f64 {
+        self.moves
+            .iter()
+            .map(|step| step.coverage_during.min(step.coverage_after))
+            .fold(self.initial_coverage, f64::min)
+    }
+
+    /// Whether the coverage never drops below the floor
+    pub fn keeps_floor(&self) -> bool {
+        self.lowest_coverage() >= self.coverage_floor
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "coverage_floor": self.coverage_floor,
+            "initial_coverage": self.initial_coverage,
+            "lowest_coverage": self.lowest_coverage(),
+            "keeps_floor": self.keeps_floor(),
+            "total_distance": self.total_distance(),
+            "moves": self
+                .moves
+                .iter()
+                .map(|step| json!({
+                    "router": step.router,
+                    "from": step.from,
+                    "to": step.to,
+                    "distance": step.distance,
+                    "coverage_during": step.coverage_during,
+                    "coverage_after": step.coverage_after
+                }))
+                .collect::<Vec<_>>()
+        })
+    }
+}
+
+// Coverage share of `routers` without the router `off`, each router keeping its radius
+fn coverage(
+    config: &FireflyConfig,
+    routers: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    off: Option<usize>,
+) -> f64 {
+    let on: Vec<usize> = (0..routers.len()).filter(|&i| Some(i) != off).collect();
+    let model = config.radio.subset(config.max_communication_distance, &on);
+    let radio = Radio::with_model(config.max_communication_distance, &model);
+    let layout: Vec<[f64; DIMENSIONS]> = on.iter().map(|&i| routers[i]).collect();
+    clients.coverage_share(ncmc(&layout, clients, &radio))
+}
+
+/// Plan moving the routers of `installed` to the layout `target` of the same size one
+/// router at a time, under the radio model of `config`.
+///
+/// Routers are matched to the target positions as in [`layout_distance()`], and those
+/// displaced by less than [`MOVE_TOLERANCE`] of the communication distance stay put.
+/// Every step moves the router that leaves the highest coverage share once back on,
+/// then the shortest move, among those keeping `coverage_floor` both while the router
+/// is off and after. When no move keeps the floor, the plan goes on with the move
+/// whose lowest coverage is highest, and [`RelocationPlan::keeps_floor`] is false.
+pub fn relocation_plan(
+    config: &FireflyConfig,
+    installed: &[[f64; DIMENSIONS]],
+    target: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    coverage_floor: f64,
+) -> RelocationPlan {
+    let matching = match_layouts(installed, target);
+    let mut pending: Vec<usize> = (0..installed.len())
+        .filter(|&i| {
+            distance(&installed[i], &target[matching.assignment[i]])
+                > MOVE_TOLERANCE * config.max_communication_distance
+        })
+        .collect();
+    let mut routers = installed.to_vec();
+    let initial_coverage = coverage(config, &routers, clients, None);
+    let mut moves = Vec::with_capacity(pending.len());
+    while !pending.is_empty() {
+        let candidates: Vec<RelocationMove> = pending
+            .iter()
+            .map(|&router| {
+                let to = target[matching.assignment[router]];
+                let mut moved = routers.clone();
+                moved[router] = to;
+                RelocationMove {
+                    router,
+                    from: routers[router],
+                    to,
+                    distance: distance(&routers[router], &to),
+                    coverage_during: coverage(config, &routers, clients, Some(router)),
+                    coverage_after: coverage(config, &moved, clients, None),
+                }
+            })
+            .collect();
+        let lowest = |step: &RelocationMove| step.coverage_during.min(step.coverage_after);
+        let feasible = candidates
+            .iter()
+            .filter(|step| lowest(step) >= coverage_floor)
+            .reduce(|best, step| {
+                let better = step.coverage_after > best.coverage_after
+                    || (step.coverage_after == best.coverage_after
+                        && step.distance < best.distance);
+                if better { step } else { best }
+            });
+        let next = feasible
+            .or_else(|| {
+                candidates.iter().reduce(|best, step| {
+                    if lowest(step) > lowest(best) {
+                        step
+                    } else {
+                        best
+                    }
+                })
+            })
+            .expect("a pending move")
+            .clone();
+        routers[next.router] = next.to;
+        pending.retain(|&router| router != next.router);
+        moves.push(next);
+    }
+    RelocationPlan {
+        coverage_floor,
+        initial_coverage,
+        moves,
+    }
+}
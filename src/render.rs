@@ -0,0 +1,343 @@
+use crate::radio::Radio;
+use crate::{ClientSet, ConnectivityGraph, DIMENSIONS, FireflyConfig, distance};
+use flate2::Crc;
+use flate2::write::ZlibEncoder;
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+// Pixels per unit of the deployment area, unless that would exceed the largest size
+const SCALE: f64 = 20.0;
+// Largest width and height of a plot in pixels
+const MAX_SIZE: f64 = 2000.0;
+// Blank border around the deployment area in pixels
+const MARGIN: f64 = 10.0;
+
+#[derive(Clone, Copy)]
+struct Color {
+    name: &'static str,
+    rgb: [u8; 3],
+}
+
+const WHITE: Color = Color {
+    name: "white",
+    rgb: [255, 255, 255],
+};
+const BLACK: Color = Color {
+    name: "black",
+    rgb: [0, 0, 0],
+};
+const PINK: Color = Color {
+    name: "pink",
+    rgb: [255, 192, 203],
+};
+const GREEN: Color = Color {
+    name: "green",
+    rgb: [0, 128, 0],
+};
+const RED: Color = Color {
+    name: "red",
+    rgb: [255, 0, 0],
+};
+const BLUE: Color = Color {
+    name: "blue",
+    rgb: [0, 0, 255],
+};
+const BROWN: Color = Color {
+    name: "saddlebrown",
+    rgb: [139, 69, 19],
+};
+
+// Drawing primitive in pixel coordinates, shared by the SVG and PNG output
+enum Shape {
+    // The deployment area: a white square with a black outline
+    Area {
+        corner: f64,
+        size: f64,
+    },
+    Disk {
+        center: [f64; 2],
+        radius: f64,
+        color: Color,
+        opacity: f64,
+    },
+    Line {
+        from: [f64; 2],
+        to: [f64; 2],
+        color: Color,
+    },
+}
+
+/// Picture of a router layout: the deployment area, the routers' coverage disks, links
+/// between routers in range, walls, the routers, and the clients, uncovered ones in red.
+///
+/// Only the first two coordinates are drawn.
+pub struct Plot<'a> {
+    routers: &'a [[f64; DIMENSIONS]],
+    clients: &'a ClientSet,
+    config: &'a FireflyConfig,
+}
+
+impl<'a> Plot<'a> {
+    pub fn new(
+        routers: &'a [[f64; DIMENSIONS]],
+        clients: &'a ClientSet,
+        config: &'a FireflyConfig,
+    ) -> Plot<'a> {
+        Plot {
+            routers,
+            clients,
+            config,
+        }
+    }
+
+    /// Width and height of the plot in pixels
+    pub fn size(&self) -> f64 {
+        self.scale() * self.config.extent() + 2.0 * MARGIN
+    }
+
+    fn scale(&self) -> f64 {
+        SCALE.min((MAX_SIZE - 2.0 * MARGIN) / self.config.extent())
+    }
+
+    // Everything to draw, back to front
+    fn shapes(&self) -> Vec<Shape> {
+        let (scale, size) = (self.scale(), self.size());
+        let lower_bound = self.config.lower_bound;
+        // Image y axes point down, so flip it to keep the origin at the bottom left
+        let pixel = |point: &[f64; DIMENSIONS]| {
+            [
+                MARGIN + (point[0] - lower_bound) * scale,
+                size - MARGIN - (point[1] - lower_bound) * scale,
+            ]
+        };
+        let radio = Radio::new(self.config);
+
+        let mut shapes = vec![Shape::Area {
+            corner: MARGIN,
+            size: size - 2.0 * MARGIN,
+        }];
+        for (i, router) in self.routers.iter().enumerate() {
+            shapes.push(Shape::Disk {
+                center: pixel(router),
+                radius: radio.coverage_radius(i) * scale,
+                color: PINK,
+                opacity: 0.4,
+            });
+        }
+        let graph = ConnectivityGraph::new(self.routers, &radio);
+        for i in 0..graph.len() {
+            for &j in graph.neighbors(i).iter().filter(|&&j| j > i) {
+                shapes.push(Shape::Line {
+                    from: pixel(&self.routers[i]),
+                    to: pixel(&self.routers[j]),
+                    color: BLACK,
+                });
+            }
+        }
+        for wall in &radio.model().walls {
+            shapes.push(Shape::Line {
+                from: pixel(&wall.from),
+                to: pixel(&wall.to),
+                color: BROWN,
+            });
+        }
+        for client in &self.clients.positions {
+            let covered = self.routers.iter().enumerate().any(|(i, router)| {
+                radio.covers(i, distance(router, client)) && !radio.blocked(router, client)
+            });
+            shapes.push(Shape::Disk {
+                center: pixel(client),
+                radius: 3.0,
+                color: if covered { GREEN } else { RED },
+                opacity: 1.0,
+            });
+        }
+        for router in self.routers {
+            shapes.push(Shape::Disk {
+                center: pixel(router),
+                radius: 4.0,
+                color: BLUE,
+                opacity: 1.0,
+            });
+        }
+        shapes
+    }
+
+    pub fn svg(&self) -> String {
+        let size = self.size();
+        let mut svg = String::new();
+        writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+            size
+        )
+        .unwrap();
+        for shape in self.shapes() {
+            match shape {
+                Shape::Area { corner, size } => writeln!(
+                    svg,
+                    r#"<rect x="{0}" y="{0}" width="{1}" height="{1}" fill="white" stroke="black"/>"#,
+                    corner, size
+                ),
+                Shape::Disk {
+                    center,
+                    radius,
+                    color,
+                    opacity,
+                } if opacity < 1.0 => writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}" fill-opacity="{}"/>"#,
+                    center[0], center[1], radius, color.name, opacity
+                ),
+                Shape::Disk {
+                    center,
+                    radius,
+                    color,
+                    ..
+                } => writeln!(
+                    svg,
+                    r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+                    center[0], center[1], radius, color.name
+                ),
+                Shape::Line { from, to, color } => writeln!(
+                    svg,
+                    r#"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="{}" stroke-width="1"/>"#,
+                    from[0], from[1], to[0], to[1], color.name
+                ),
+            }
+            .unwrap();
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// The plot as an RGB PNG image
+    pub fn png(&self) -> Vec<u8> {
+        let mut canvas = Canvas::new(self.size().ceil() as usize);
+        for shape in self.shapes() {
+            match shape {
+                Shape::Area { corner, size } => canvas.area(corner, size),
+                Shape::Disk {
+                    center,
+                    radius,
+                    color,
+                    opacity,
+                } => canvas.disk(center, radius, color, opacity),
+                Shape::Line { from, to, color } => canvas.line(from, to, color),
+            }
+        }
+        canvas.encode_png()
+    }
+
+    /// Write the plot as PNG to `.png` paths and as SVG to any other path
+    pub fn save(&self, path: &str) -> std::io::Result<()> {
+        if path.to_ascii_lowercase().ends_with(".png") {
+            std::fs::write(path, self.png())
+        } else {
+            std::fs::write(path, self.svg())
+        }
+    }
+}
+
+// Square RGB raster with white pixels to start with
+struct Canvas {
+    size: usize,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Canvas {
+    fn new(size: usize) -> Canvas {
+        Canvas {
+            size,
+            pixels: vec![WHITE.rgb; size * size],
+        }
+    }
+
+    // Blend `color` into the pixel at column `x` and row `y`, if it is on the canvas
+    fn blend(&mut self, x: i64, y: i64, color: Color, opacity: f64) {
+        if x < 0 || y < 0 || x >= self.size as i64 || y >= self.size as i64 {
+            return;
+        }
+        let pixel = &mut self.pixels[y as usize * self.size + x as usize];
+        for (channel, target) in pixel.iter_mut().zip(color.rgb) {
+            let blended = *channel as f64 * (1.0 - opacity) + target as f64 * opacity;
+            *channel = blended.round() as u8;
+        }
+    }
+
+    // Pixels whose centers lie in the disk
+    fn disk(&mut self, center: [f64; 2], radius: f64, color: Color, opacity: f64) {
+        let (first_x, last_x) = (
+            (center[0] - radius).floor() as i64,
+            (center[0] + radius).ceil() as i64,
+        );
+        let (first_y, last_y) = (
+            (center[1] - radius).floor() as i64,
+            (center[1] + radius).ceil() as i64,
+        );
+        for y in first_y..=last_y {
+            for x in first_x..=last_x {
+                let (dx, dy) = (x as f64 + 0.5 - center[0], y as f64 + 0.5 - center[1]);
+                if dx * dx + dy * dy <= radius * radius {
+                    self.blend(x, y, color, opacity);
+                }
+            }
+        }
+    }
+
+    // One pixel per step along the longer axis
+    fn line(&mut self, from: [f64; 2], to: [f64; 2], color: Color) {
+        let steps = (to[0] - from[0]).abs().max((to[1] - from[1]).abs()).ceil() as usize;
+        for step in 0..=steps {
+            let t = step as f64 / steps.max(1) as f64;
+            let x = from[0] + (to[0] - from[0]) * t;
+            let y = from[1] + (to[1] - from[1]) * t;
+            self.blend(x.floor() as i64, y.floor() as i64, color, 1.0);
+        }
+    }
+
+    // The square outline of the deployment area
+    fn area(&mut self, corner: f64, size: f64) {
+        let (first, last) = (corner, corner + size);
+        for [from, to] in [
+            [[first, first], [last, first]],
+            [[last, first], [last, last]],
+            [[last, last], [first, last]],
+            [[first, last], [first, first]],
+        ] {
+            self.line(from, to, BLACK);
+        }
+    }
+
+    fn encode_png(&self) -> Vec<u8> {
+        let mut scanlines = Vec::with_capacity(self.size * (3 * self.size + 1));
+        for row in self.pixels.chunks_exact(self.size) {
+            // Filter type 0: the row is stored as is
+            scanlines.push(0);
+            scanlines.extend(row.iter().flatten());
+        }
+        let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(&scanlines)
+            .expect("writing to memory cannot fail");
+        let data = encoder.finish().expect("writing to memory cannot fail");
+
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.size as u32).to_be_bytes());
+        header.extend((self.size as u32).to_be_bytes());
+        // 8 bits per channel, RGB, deflate, adaptive filtering, no interlacing
+        header.extend([8, 2, 0, 0, 0]);
+
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        for (kind, contents) in [(b"IHDR", &header), (b"IDAT", &data), (b"IEND", &Vec::new())] {
+            png.extend((contents.len() as u32).to_be_bytes());
+            png.extend(kind);
+            png.extend(contents);
+            let mut crc = Crc::new();
+            crc.update(kind);
+            crc.update(contents);
+            png.extend(crc.sum().to_be_bytes());
+        }
+        png
+    }
+}
@@ -0,0 +1,232 @@
+use crate::clients::ClientSet;
+use crate::history::IterationRecord;
+use crate::metrics::{MetricTimings, Metrics, SgcMode};
+use crate::{DIMENSIONS, FireflyConfig, compare_fitness};
+use serde_json::json;
+use std::time::Duration;
+
+// Average fraction of fireflies hitting the bounds per iteration worth warning about
+const BOUND_HIT_WARNING_FRACTION: f64 = 0.2;
+// Alpha as a fraction of the domain extent worth warning about
+const ALPHA_WARNING_FRACTION: f64 = 0.1;
+
+/// Fidelity reduction made by the iteration watchdog
+pub struct FidelityChange {
+    pub iteration: usize,
+    pub elapsed: Duration,
+    pub sgc_mode: SgcMode,
+}
+
+impl FidelityChange {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "iteration": self.iteration,
+            "elapsed_ms": self.elapsed.as_secs_f64() * 1000.0,
+            "sgc_mode": self.sgc_mode.describe()
+        })
+    }
+}
+
+/// Snap every router coordinate to a multiple of `precision`, staying inside the bounds
+pub fn snap_layout(
+    routers: &[[f64; DIMENSIONS]],
+    precision: f64,
+    lower_bound: f64,
+    upper_bound: f64,
+) -> Vec<[f64; DIMENSIONS]> {
+    routers
+        .iter()
+        .map(|router| {
+            router.map(|coord| {
+                ((coord / precision).round() * precision).clamp(lower_bound, upper_bound)
+            })
+        })
+        .collect()
+}
+
+/// Metrics of the layout before snapping, kept to report what rounding cost
+pub struct Snapping {
+    pub precision: f64,
+    pub unsnapped: Metrics,
+}
+
+impl Snapping {
+    pub fn degraded(&self, snapped: &Metrics) -> bool {
+        compare_fitness(snapped.fitness, self.unsnapped.fitness).is_lt()
+            || snapped.sgc < self.unsnapped.sgc
+            || snapped.ncmc < self.unsnapped.ncmc
+    }
+
+    pub fn to_json(&self, snapped: &Metrics) -> serde_json::Value {
+        json!({
+            "precision": self.precision,
+            "unsnapped": self.unsnapped.to_json(),
+            "degraded": self.degraded(snapped)
+        })
+    }
+
+    pub fn print(&self, snapped: &Metrics) {
+        println!("Snapped router coordinates to a {} grid:", self.precision);
+        println!(
+            "  Fitness {} -> {}",
+            self.unsnapped.fitness, snapped.fitness
+        );
+        println!("  SGC     {} -> {}", self.unsnapped.sgc, snapped.sgc);
+        println!("  NCMC    {} -> {}", self.unsnapped.ncmc, snapped.ncmc);
+        println!("  NCMCpR  {} -> {}", self.unsnapped.ncmcpr, snapped.ncmcpr);
+        println!(
+            "  Overlap {} -> {}",
+            self.unsnapped.overlap, snapped.overlap
+        );
+    }
+}
+
+// Hostname of the machine producing the results
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Build and host information embedded in every result file
+pub fn provenance() -> serde_json::Value {
+    json!({
+        "crate_version": env!("CARGO_PKG_VERSION"),
+        "git_commit": env!("FIREFLY_GIT_COMMIT"),
+        "build_profile": env!("FIREFLY_BUILD_PROFILE"),
+        "hostname": hostname()
+    })
+}
+
+/// Score of the best layout at the fidelity the search ended with, when that was not exact
+pub struct SearchFidelity {
+    pub sgc_mode: SgcMode,
+    pub fitness: f64,
+    pub sgc: usize,
+}
+
+impl SearchFidelity {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "sgc_mode": self.sgc_mode.describe(),
+            "best_fitness": self.fitness,
+            "sgc": self.sgc
+        })
+    }
+
+    pub fn print(&self, exact: &Metrics) {
+        println!(
+            "Search used {}; the best layout was re-evaluated exactly:",
+            self.sgc_mode.describe()
+        );
+        println!(
+            "  Fitness {} (search) -> {} (exact)",
+            self.fitness, exact.fitness
+        );
+        println!("  SGC     {} (search) -> {} (exact)", self.sgc, exact.sgc);
+    }
+}
+
+/// One layout of a Pareto front with its exact metrics
+pub struct ParetoLayout {
+    pub routers: Vec<[f64; DIMENSIONS]>,
+    pub metrics: Metrics,
+}
+
+impl ParetoLayout {
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut data = self.metrics.to_json();
+        data["mesh_routers"] = json!(self.routers);
+        data
+    }
+}
+
+/// Everything a run reports besides the layout itself
+pub struct Report {
+    pub metrics: Metrics,
+    pub search_fidelity: Option<SearchFidelity>,
+    pub timings: MetricTimings,
+    pub snapping: Option<Snapping>,
+    pub fidelity_changes: Vec<FidelityChange>,
+    pub warnings: Vec<String>,
+    /// Per-iteration convergence history, when it is appended to the report
+    pub history: Option<Vec<IterationRecord>>,
+    /// Non-dominated layouts of a Pareto search, before snapping, by descending SGC
+    pub pareto_front: Option<Vec<ParetoLayout>>,
+    /// Iterations run when early stopping ended the search before the last one
+    pub stopped_after: Option<usize>,
+    /// Iterations that moved the fireflies without evaluating them
+    pub movement_only_iterations: usize,
+    /// Fitness of the final layout under the configured robustness, scored anew after
+    /// the search
+    pub robust_fitness: Option<f64>,
+}
+
+// Pathologies of a finished run worth flagging to the user
+pub(crate) fn run_warnings(
+    config: &FireflyConfig,
+    clients: &ClientSet,
+    metrics: &Metrics,
+    snapping: Option<&Snapping>,
+    fidelity_changes: &[FidelityChange],
+    bound_hit_fraction: f64,
+) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if metrics.sgc < config.routers {
+        warnings.push(format!(
+            "best layout is not fully connected: the giant component holds {} of {} routers",
+            metrics.sgc, config.routers
+        ));
+    }
+    let total_weight = clients.total_weight();
+    if metrics.ncmc < total_weight {
+        warnings.push(if clients.is_weighted() {
+            format!(
+                "uncovered mesh clients carry {} of the total client weight {}",
+                total_weight - metrics.ncmc,
+                total_weight
+            )
+        } else {
+            format!(
+                "{} of {} mesh clients are not covered",
+                total_weight - metrics.ncmc,
+                total_weight
+            )
+        });
+    }
+    if bound_hit_fraction > BOUND_HIT_WARNING_FRACTION {
+        warnings.push(format!(
+            "{:.0}% of fireflies hit the bounds per iteration on average",
+            bound_hit_fraction * 100.0
+        ));
+    }
+    let extent = config.extent();
+    if config
+        .alpha
+        .iter()
+        .any(|&alpha| alpha > ALPHA_WARNING_FRACTION * extent)
+    {
+        warnings.push(format!(
+            "alpha {:?} is likely too large for a domain of extent {}",
+            config.alpha, extent
+        ));
+    }
+    if let Some(snapping) = snapping
+        && snapping.degraded(metrics)
+    {
+        warnings.push(format!(
+            "snapping to a {} grid degraded the solution",
+            snapping.precision
+        ));
+    }
+    if let Some(last) = fidelity_changes.last() {
+        warnings.push(format!(
+            "iterations exceeded the time budget; the search ended using {}",
+            last.sgc_mode.describe()
+        ));
+    }
+    warnings
+}
@@ -0,0 +1,103 @@
+use serde_json::json;
+
+/// Disturbances the search scores every layout against, so that it prefers layouts
+/// that stay connected and keep covering clients when routers go down or clients move.
+///
+/// Only the connectivity and coverage terms of the fitness see the disturbances; overlap,
+/// separation and relocation are properties of where the routers stand. An objective
+/// plugin replaces the fitness, failures included.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Robustness {
+    /// Expected SGC, NCMC and NCMCpR when every router fails independently with
+    /// `failure_probability`, estimated at every evaluation from `samples` failure
+    /// patterns drawn anew
+    Expected {
+        failure_probability: f64,
+        samples: usize,
+    },
+    /// The lowest fitness over the intact layout and, with `router_failure`, the layout
+    /// without any one router, counting only the clients that stay covered wherever
+    /// they move up to `client_shift`
+    WorstCase {
+        client_shift: f64,
+        router_failure: bool,
+    },
+}
+
+impl Robustness {
+    /// Failure patterns per evaluation when none is given
+    pub const DEFAULT_SAMPLES: usize = 16;
+
+    pub fn validate(&self) -> Result<(), String> {
+        match *self {
+            Robustness::Expected {
+                failure_probability,
+                samples,
+            } => {
+                if !(0.0..1.0).contains(&failure_probability) {
+                    return Err(format!(
+                        "the failure probability must be in [0, 1), got {}",
+                        failure_probability
+                    ));
+                }
+                if samples == 0 {
+                    return Err("robust fitness needs at least one failure sample".to_string());
+                }
+            }
+            Robustness::WorstCase { client_shift, .. } => {
+                if !(client_shift >= 0.0 && client_shift.is_finite()) {
+                    return Err(format!(
+                        "the client shift must be finite and non-negative, got {}",
+                        client_shift
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        match *self {
+            Robustness::Expected {
+                failure_probability,
+                samples,
+            } => json!({
+                "mode": "expected",
+                "failure_probability": failure_probability,
+                "samples": samples
+            }),
+            Robustness::WorstCase {
+                client_shift,
+                router_failure,
+            } => json!({
+                "mode": "worst-case",
+                "client_shift": client_shift,
+                "router_failure": router_failure
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for Robustness {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Robustness::Expected {
+                failure_probability,
+                ..
+            } => write!(
+                f,
+                "every router failing with probability {}, expected",
+                failure_probability
+            ),
+            Robustness::WorstCase {
+                client_shift,
+                router_failure,
+            } => {
+                if *router_failure {
+                    write!(f, "the worst single router failure and ")?;
+                }
+                write!(f, "clients moving up to {}, worst case", client_shift)
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// What to do when a firefly's position or fitness turns NaN or infinite
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum NonFinitePolicy {
+    /// Log the firefly and re-randomize it
+    #[default]
+    Repair,
+    /// Log the firefly and stop the run with an error
+    Abort,
+}
+
+impl FromStr for NonFinitePolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<NonFinitePolicy, String> {
+        match value {
+            "repair" => Ok(NonFinitePolicy::Repair),
+            "abort" => Ok(NonFinitePolicy::Abort),
+            _ => Err(format!(
+                "unknown non-finite policy {}, expected repair or abort",
+                value
+            )),
+        }
+    }
+}
+
+/// A firefly found with a non-finite position or fitness after an iteration
+#[derive(Clone, Debug, PartialEq)]
+pub struct NonFinite {
+    /// Iteration after which it was found, `None` right after initialization
+    pub iteration: Option<usize>,
+    /// Index of the firefly, `None` when a whole router layout has a non-finite fitness
+    pub firefly: Option<usize>,
+    pub position: Vec<f64>,
+    pub fitness: f64,
+}
+
+impl NonFinite {
+    /// Describe the firefly if its position or fitness is not finite
+    pub fn detect(
+        iteration: Option<usize>,
+        firefly: Option<usize>,
+        position: &[f64],
+        fitness: f64,
+    ) -> Option<NonFinite> {
+        if position.iter().all(|coord| coord.is_finite()) && fitness.is_finite() {
+            return None;
+        }
+        Some(NonFinite {
+            iteration,
+            firefly,
+            position: position.to_vec(),
+            fitness,
+        })
+    }
+}
+
+impl fmt::Display for NonFinite {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.iteration {
+            Some(iteration) => write!(f, "after iteration {}, ", iteration)?,
+            None => write!(f, "after initialization, ")?,
+        }
+        match self.firefly {
+            Some(firefly) => write!(f, "firefly {}", firefly)?,
+            None => write!(f, "the layout")?,
+        }
+        write!(
+            f,
+            " has position {:?} and fitness {}",
+            self.position, self.fitness
+        )
+    }
+}
+
+impl std::error::Error for NonFinite {}
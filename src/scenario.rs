@@ -0,0 +1,582 @@
+use crate::calibration::{self, Calibration};
+use crate::radio::Wall;
+use crate::rate::RateModel;
+use crate::{
+    AlphaSchedule, ArchivePruning, BrightnessTransform, ClientSet, DIMENSIONS, EarlyStopping,
+    FireflyConfig, Goal, Multiresolution, NonFinitePolicy, PathLoss, Robustness, SearchMode,
+    Termination, TieBreak, Tiling, heuristics,
+};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// A single alpha for every dimension or one alpha per dimension
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ScenarioAlpha {
+    Uniform(f64),
+    PerDimension([f64; DIMENSIONS]),
+}
+
+/// Fitness weights of a scenario; missing weights keep their current value
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioWeights {
+    pub sgc: Option<f64>,
+    pub ncmc: Option<f64>,
+    pub ncmcpr: Option<f64>,
+    pub overlap: Option<f64>,
+}
+
+/// The keyword `auto`
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Auto {
+    Auto,
+}
+
+/// Firefly count of a population search, or `"auto"` to size it from the dimensionality
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(untagged, expecting = "a firefly count or \"auto\"")]
+pub enum ScenarioPopulation {
+    Fireflies(usize),
+    Auto(Auto),
+}
+
+/// Convergence history settings of a scenario; missing settings keep their current value
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioHistory {
+    pub file: Option<String>,
+    pub in_report: Option<bool>,
+    pub positions: Option<bool>,
+    pub parameters: Option<bool>,
+}
+
+/// Path-loss settings of a scenario; missing values keep the [`PathLoss`] defaults
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioPathLoss {
+    pub tx_power: Option<f64>,
+    pub reference_loss: Option<f64>,
+    pub exponent: Option<f64>,
+    pub sensitivity: Option<f64>,
+}
+
+/// Radio model settings of a scenario; missing settings keep their current value
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioRadio {
+    /// Transmit radius of each router by index
+    pub radii: Option<Vec<f64>>,
+    pub min_separation: Option<f64>,
+    pub separation_penalty: Option<f64>,
+    /// Cover clients by received signal strength
+    pub path_loss: Option<ScenarioPathLoss>,
+    /// Obstacles as `[x0, y0, x1, y1]`, blocking links and coverage that cross them
+    pub walls: Option<Vec<[f64; 4]>>,
+}
+
+/// Divide-and-conquer settings of a scenario; tiling is enabled by giving `tiles`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioTiling {
+    pub tiles: Option<usize>,
+    /// Iterations of the stitching pass, the run's iterations by default
+    pub stitch_iterations: Option<usize>,
+}
+
+/// How a scenario scores layouts against disturbances
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum RobustnessMode {
+    /// The expected fitness under random router failures
+    Expected,
+    /// The fitness under the worst single router failure and client shift
+    WorstCase,
+}
+
+/// Robust-fitness settings of a scenario; the expected mode is enabled by giving
+/// `failure_probability`, the worst-case mode by choosing it
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioRobustness {
+    pub mode: Option<RobustnessMode>,
+    pub failure_probability: Option<f64>,
+    /// Failure patterns drawn per evaluation
+    pub samples: Option<usize>,
+    /// Distance clients may move in the worst case, 0 by default
+    pub client_shift: Option<f64>,
+    /// Whether the worst case loses a router, true by default
+    pub router_failure: Option<bool>,
+}
+
+/// Target shares of a scenario's reference-point search, enabled by giving either one
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioGoal {
+    pub sgc: Option<f64>,
+    pub coverage: Option<f64>,
+}
+
+/// Coarse-to-fine settings of a scenario; multiresolution is enabled by giving `levels`
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioMultiresolution {
+    pub levels: Option<usize>,
+    pub client_fraction: Option<f64>,
+    pub radius_factor: Option<f64>,
+    /// Iterations of every coarse level, the run's iterations by default
+    pub coarse_iterations: Option<usize>,
+}
+
+/// Parameter set loaded from a TOML scenario file.
+///
+/// Every field is optional and overrides the matching [`FireflyConfig`] value:
+///
+/// ```toml
+/// name = "corridor"
+/// routers = 24
+/// clients_file = "clients.csv"
+/// survey_file = "rssi.csv"
+/// iterations = 200
+/// lower_bound = 0.0
+/// upper_bound = 48.0
+/// max_comm_distance = 6.0
+/// alpha = [0.5, 0.1]
+/// alpha_schedule = "linear:0.1"
+/// patience = 30
+/// termination = "any(all(stall:50,coverage:0.9),fitness:9.5)"
+/// evaluation_budget = 5000
+/// evaluation_interval = 1
+/// brightness = "rank"
+/// tie_break = "prefer-newer"
+/// rates = "802.11n"
+/// seed = 42
+///
+/// [weights]
+/// sgc = 0.6
+/// ncmc = 0.3
+/// ncmcpr = 0.1
+/// overlap = 0.05
+///
+/// [radio]
+/// radii = [6.0, 6.0, 4.5]
+/// min_separation = 1.5
+/// separation_penalty = 0.5
+/// walls = [[24.0, 0.0, 24.0, 20.0]]
+///
+/// [radio.path_loss]
+/// exponent = 3.5
+/// sensitivity = -45.0
+///
+/// [history]
+/// file = "convergence.csv"
+/// positions = false
+/// parameters = true
+///
+/// [tiling]
+/// tiles = 2
+/// stitch_iterations = 50
+///
+/// [robustness]
+/// mode = "worst-case"
+/// client_shift = 1.0
+/// router_failure = true
+///
+/// [goal]
+/// sgc = 1.0
+/// coverage = 0.95
+///
+/// [tags]
+/// site = "campus"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub name: Option<String>,
+    pub routers: Option<usize>,
+    pub clients: Option<usize>,
+    /// Client positions to load instead of generating `clients` random ones, relative
+    /// to the scenario file
+    pub clients_file: Option<String>,
+    /// Site-survey RSSI samples to fit the path-loss model to, relative to the scenario
+    /// file; enables the path-loss model
+    pub survey_file: Option<String>,
+    pub iterations: Option<usize>,
+    pub alpha: Option<ScenarioAlpha>,
+    /// `constant`, `linear:<final fraction>` or `geometric:<factor>`
+    pub alpha_schedule: Option<AlphaSchedule>,
+    pub elitism: Option<bool>,
+    /// Iterations without improvement before the search stops early
+    pub patience: Option<usize>,
+    /// Smallest gain in the best fitness that counts as an improvement toward `patience`
+    pub tolerance: Option<f64>,
+    /// Stopping rule such as `any(all(stall:50,coverage:0.9),time:600)`, see
+    /// [`Termination`]
+    pub termination: Option<Termination>,
+    /// Iterations between fitness evaluations; the swarm only moves in between
+    pub evaluation_interval: Option<usize>,
+    pub beta0: Option<f64>,
+    pub gamma: Option<f64>,
+    /// `identity`, `log` or `rank`, see [`BrightnessTransform`]
+    pub brightness: Option<BrightnessTransform>,
+    pub lower_bound: Option<f64>,
+    pub upper_bound: Option<f64>,
+    pub max_comm_distance: Option<f64>,
+    #[serde(default)]
+    pub weights: ScenarioWeights,
+    #[serde(default)]
+    pub radio: ScenarioRadio,
+    pub tie_break: Option<TieBreak>,
+    pub approx_metrics: Option<bool>,
+    pub soft_bounds: Option<bool>,
+    pub snap: Option<f64>,
+    /// Grid precision of the router positions during the search
+    pub fixed_point: Option<f64>,
+    pub iteration_budget_ms: Option<f64>,
+    pub sharing_radius: Option<f64>,
+    /// Fireflies of a population search over whole layouts
+    pub population: Option<ScenarioPopulation>,
+    /// Return the Pareto front of the population search instead of a weighted optimum
+    pub pareto: Option<bool>,
+    /// Layouts kept on the Pareto front
+    pub pareto_archive: Option<usize>,
+    /// Keep one layout per box of this size over the objectives of the Pareto front
+    pub pareto_epsilon: Option<f64>,
+    /// Fitness evaluations to spend, used to choose the iterations when they are not given
+    pub evaluation_budget: Option<usize>,
+    pub non_finite: Option<NonFinitePolicy>,
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub history: ScenarioHistory,
+    #[serde(default)]
+    pub tiling: ScenarioTiling,
+    #[serde(default)]
+    pub multiresolution: ScenarioMultiresolution,
+    #[serde(default)]
+    pub robustness: ScenarioRobustness,
+    /// Rate-vs-distance model to report client data rates with: `802.11n` or
+    /// `<range share>:<Mbit/s>` bands separated by commas
+    pub rates: Option<RateModel>,
+    #[serde(default)]
+    pub goal: ScenarioGoal,
+    #[serde(default)]
+    pub tags: BTreeMap<String, String>,
+}
+
+impl Scenario {
+    pub fn from_toml(contents: &str) -> Result<Scenario, String> {
+        toml::from_str(contents).map_err(|err| err.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Scenario, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read scenario {}: {}", path, err))?;
+        let mut scenario = Scenario::from_toml(&contents)
+            .map_err(|err| format!("Invalid scenario {}: {}", path, err))?;
+        if let Some(directory) = Path::new(path).parent() {
+            for file in [&mut scenario.clients_file, &mut scenario.survey_file]
+                .into_iter()
+                .flatten()
+            {
+                *file = directory.join(&*file).to_string_lossy().into_owned();
+            }
+        }
+        Ok(scenario)
+    }
+
+    /// This scenario with every value set in `overrides` replaced; a client count in
+    /// `overrides` also replaces this scenario's client file
+    pub fn merge(self, overrides: Scenario) -> Scenario {
+        let clients_file = if overrides.clients.is_some() || overrides.clients_file.is_some() {
+            overrides.clients_file
+        } else {
+            self.clients_file
+        };
+        let mut tags = self.tags;
+        tags.extend(overrides.tags);
+        Scenario {
+            name: overrides.name.or(self.name),
+            routers: overrides.routers.or(self.routers),
+            clients: overrides.clients.or(self.clients),
+            clients_file,
+            survey_file: overrides.survey_file.or(self.survey_file),
+            iterations: overrides.iterations.or(self.iterations),
+            alpha: overrides.alpha.or(self.alpha),
+            alpha_schedule: overrides.alpha_schedule.or(self.alpha_schedule),
+            elitism: overrides.elitism.or(self.elitism),
+            patience: overrides.patience.or(self.patience),
+            tolerance: overrides.tolerance.or(self.tolerance),
+            termination: overrides.termination.or(self.termination),
+            evaluation_interval: overrides.evaluation_interval.or(self.evaluation_interval),
+            beta0: overrides.beta0.or(self.beta0),
+            gamma: overrides.gamma.or(self.gamma),
+            brightness: overrides.brightness.or(self.brightness),
+            lower_bound: overrides.lower_bound.or(self.lower_bound),
+            upper_bound: overrides.upper_bound.or(self.upper_bound),
+            max_comm_distance: overrides.max_comm_distance.or(self.max_comm_distance),
+            weights: ScenarioWeights {
+                sgc: overrides.weights.sgc.or(self.weights.sgc),
+                ncmc: overrides.weights.ncmc.or(self.weights.ncmc),
+                ncmcpr: overrides.weights.ncmcpr.or(self.weights.ncmcpr),
+                overlap: overrides.weights.overlap.or(self.weights.overlap),
+            },
+            radio: ScenarioRadio {
+                radii: overrides.radio.radii.or(self.radio.radii),
+                min_separation: overrides.radio.min_separation.or(self.radio.min_separation),
+                separation_penalty: overrides
+                    .radio
+                    .separation_penalty
+                    .or(self.radio.separation_penalty),
+                path_loss: overrides.radio.path_loss.or(self.radio.path_loss),
+                walls: overrides.radio.walls.or(self.radio.walls),
+            },
+            tie_break: overrides.tie_break.or(self.tie_break),
+            approx_metrics: overrides.approx_metrics.or(self.approx_metrics),
+            soft_bounds: overrides.soft_bounds.or(self.soft_bounds),
+            snap: overrides.snap.or(self.snap),
+            fixed_point: overrides.fixed_point.or(self.fixed_point),
+            iteration_budget_ms: overrides.iteration_budget_ms.or(self.iteration_budget_ms),
+            sharing_radius: overrides.sharing_radius.or(self.sharing_radius),
+            population: overrides.population.or(self.population),
+            pareto: overrides.pareto.or(self.pareto),
+            pareto_archive: overrides.pareto_archive.or(self.pareto_archive),
+            pareto_epsilon: overrides.pareto_epsilon.or(self.pareto_epsilon),
+            evaluation_budget: overrides.evaluation_budget.or(self.evaluation_budget),
+            non_finite: overrides.non_finite.or(self.non_finite),
+            seed: overrides.seed.or(self.seed),
+            history: ScenarioHistory {
+                file: overrides.history.file.or(self.history.file),
+                in_report: overrides.history.in_report.or(self.history.in_report),
+                positions: overrides.history.positions.or(self.history.positions),
+                parameters: overrides.history.parameters.or(self.history.parameters),
+            },
+            tiling: ScenarioTiling {
+                tiles: overrides.tiling.tiles.or(self.tiling.tiles),
+                stitch_iterations: overrides
+                    .tiling
+                    .stitch_iterations
+                    .or(self.tiling.stitch_iterations),
+            },
+            multiresolution: ScenarioMultiresolution {
+                levels: overrides
+                    .multiresolution
+                    .levels
+                    .or(self.multiresolution.levels),
+                client_fraction: overrides
+                    .multiresolution
+                    .client_fraction
+                    .or(self.multiresolution.client_fraction),
+                radius_factor: overrides
+                    .multiresolution
+                    .radius_factor
+                    .or(self.multiresolution.radius_factor),
+                coarse_iterations: overrides
+                    .multiresolution
+                    .coarse_iterations
+                    .or(self.multiresolution.coarse_iterations),
+            },
+            robustness: ScenarioRobustness {
+                mode: overrides.robustness.mode.or(self.robustness.mode),
+                failure_probability: overrides
+                    .robustness
+                    .failure_probability
+                    .or(self.robustness.failure_probability),
+                samples: overrides.robustness.samples.or(self.robustness.samples),
+                client_shift: overrides
+                    .robustness
+                    .client_shift
+                    .or(self.robustness.client_shift),
+                router_failure: overrides
+                    .robustness
+                    .router_failure
+                    .or(self.robustness.router_failure),
+            },
+            rates: overrides.rates.or(self.rates),
+            goal: ScenarioGoal {
+                sgc: overrides.goal.sgc.or(self.goal.sgc),
+                coverage: overrides.goal.coverage.or(self.goal.coverage),
+            },
+            tags,
+        }
+    }
+
+    /// Clients listed in `clients_file`, if any
+    pub fn load_clients(&self) -> Result<Option<ClientSet>, String> {
+        self.clients_file
+            .as_deref()
+            .map(ClientSet::load)
+            .transpose()
+    }
+
+    /// Fit the path-loss model of `config` to the samples of `survey_file`, if any,
+    /// enabling the model with its default parameters if needed
+    pub fn calibrate(&self, config: &mut FireflyConfig) -> Result<Option<Calibration>, String> {
+        let Some(path) = &self.survey_file else {
+            return Ok(None);
+        };
+        let model = config.radio.path_loss.unwrap_or_default();
+        let calibration = Calibration::fit(&calibration::load_samples(path)?, &model)
+            .map_err(|err| format!("Unable to calibrate to {}: {}", path, err))?;
+        config.radio.path_loss = Some(calibration.apply(model));
+        Ok(Some(calibration))
+    }
+
+    /// Override the values of `config` set by this scenario; `clients_file` is loaded
+    /// separately with [`Scenario::load_clients`] and `survey_file` is fitted with
+    /// [`Scenario::calibrate`]
+    pub fn apply(self, mut config: FireflyConfig) -> FireflyConfig {
+        fn set<T>(field: &mut T, value: Option<T>) {
+            if let Some(value) = value {
+                *field = value;
+            }
+        }
+        set(&mut config.run.name, self.name.map(Some));
+        set(&mut config.routers, self.routers);
+        set(&mut config.clients, self.clients);
+        set(&mut config.iterations, self.iterations);
+        set(
+            &mut config.alpha,
+            self.alpha.map(|alpha| match alpha {
+                ScenarioAlpha::Uniform(alpha) => [alpha; DIMENSIONS],
+                ScenarioAlpha::PerDimension(alpha) => alpha,
+            }),
+        );
+        set(&mut config.alpha_schedule, self.alpha_schedule);
+        set(&mut config.elitism, self.elitism);
+        if let Some(patience) = self.patience {
+            config.early_stopping = Some(EarlyStopping {
+                patience,
+                ..config
+                    .early_stopping
+                    .unwrap_or(EarlyStopping::new(patience))
+            });
+        }
+        if let (Some(stopping), Some(tolerance)) = (&mut config.early_stopping, self.tolerance) {
+            stopping.tolerance = tolerance;
+        }
+        set(&mut config.termination, self.termination.map(Some));
+        set(&mut config.evaluation_interval, self.evaluation_interval);
+        set(&mut config.beta0, self.beta0);
+        set(&mut config.gamma, self.gamma);
+        set(&mut config.brightness, self.brightness.map(Some));
+        set(&mut config.lower_bound, self.lower_bound);
+        set(&mut config.upper_bound, self.upper_bound);
+        set(
+            &mut config.max_communication_distance,
+            self.max_comm_distance,
+        );
+        set(&mut config.weights.sgc, self.weights.sgc);
+        set(&mut config.weights.ncmc, self.weights.ncmc);
+        set(&mut config.weights.ncmcpr, self.weights.ncmcpr);
+        set(&mut config.weights.overlap, self.weights.overlap);
+        set(&mut config.radio.radii, self.radio.radii);
+        set(&mut config.radio.min_separation, self.radio.min_separation);
+        set(
+            &mut config.radio.separation_penalty,
+            self.radio.separation_penalty,
+        );
+        set(
+            &mut config.radio.walls,
+            self.radio
+                .walls
+                .map(|walls| walls.into_iter().map(Wall::from_ends).collect()),
+        );
+        if let Some(path_loss) = self.radio.path_loss {
+            let defaults = config.radio.path_loss.unwrap_or_default();
+            config.radio.path_loss = Some(PathLoss {
+                tx_power: path_loss.tx_power.unwrap_or(defaults.tx_power),
+                reference_loss: path_loss.reference_loss.unwrap_or(defaults.reference_loss),
+                exponent: path_loss.exponent.unwrap_or(defaults.exponent),
+                sensitivity: path_loss.sensitivity.unwrap_or(defaults.sensitivity),
+            });
+        }
+        set(&mut config.tie_break, self.tie_break);
+        set(&mut config.approx_metrics, self.approx_metrics);
+        set(&mut config.soft_bounds, self.soft_bounds);
+        set(&mut config.snap, self.snap.map(Some));
+        set(&mut config.fixed_point, self.fixed_point.map(Some));
+        set(
+            &mut config.iteration_budget,
+            self.iteration_budget_ms
+                .map(|millis| Some(Duration::from_secs_f64(millis.max(0.0) / 1000.0))),
+        );
+        set(&mut config.sharing_radius, self.sharing_radius.map(Some));
+        set(
+            &mut config.search,
+            self.population.map(|population| SearchMode::Population {
+                fireflies: match population {
+                    ScenarioPopulation::Fireflies(fireflies) => fireflies,
+                    ScenarioPopulation::Auto(_) => {
+                        heuristics::population_size(config.routers * DIMENSIONS)
+                    }
+                },
+            }),
+        );
+        set(&mut config.pareto, self.pareto);
+        set(&mut config.pareto_archive, self.pareto_archive);
+        set(
+            &mut config.pareto_pruning,
+            self.pareto_epsilon
+                .map(|epsilon| ArchivePruning::EpsilonGrid { epsilon }),
+        );
+        if let Some(tiles) = self.tiling.tiles {
+            config.tiling = Some(Tiling {
+                tiles,
+                stitch_iterations: self.tiling.stitch_iterations.unwrap_or(config.iterations),
+            });
+        }
+        if let Some(levels) = self.multiresolution.levels {
+            let defaults = Multiresolution::new(
+                levels,
+                self.multiresolution
+                    .coarse_iterations
+                    .unwrap_or(config.iterations),
+            );
+            config.multiresolution = Some(Multiresolution {
+                client_fraction: self
+                    .multiresolution
+                    .client_fraction
+                    .unwrap_or(defaults.client_fraction),
+                radius_factor: self
+                    .multiresolution
+                    .radius_factor
+                    .unwrap_or(defaults.radius_factor),
+                ..defaults
+            });
+        }
+        let robustness = self.robustness;
+        match (robustness.mode, robustness.failure_probability) {
+            (Some(RobustnessMode::WorstCase), _) => {
+                config.robustness = Some(Robustness::WorstCase {
+                    client_shift: robustness.client_shift.unwrap_or(0.0),
+                    router_failure: robustness.router_failure.unwrap_or(true),
+                })
+            }
+            (_, Some(failure_probability)) => {
+                config.robustness = Some(Robustness::Expected {
+                    failure_probability,
+                    samples: robustness.samples.unwrap_or(Robustness::DEFAULT_SAMPLES),
+                })
+            }
+            (_, None) => {}
+        }
+        set(&mut config.rates, self.rates.map(Some));
+        if self.goal.sgc.is_some() || self.goal.coverage.is_some() {
+            config.goal = Some(Goal {
+                sgc: self.goal.sgc,
+                coverage: self.goal.coverage,
+            });
+        }
+        set(&mut config.non_finite, self.non_finite);
+        set(&mut config.seed, self.seed.map(Some));
+        set(&mut config.history.file, self.history.file.map(Some));
+        set(&mut config.history.in_report, self.history.in_report);
+        set(&mut config.history.positions, self.history.positions);
+        set(&mut config.history.parameters, self.history.parameters);
+        config.run.tags.extend(self.tags);
+        config
+    }
+}
@@ -0,0 +1,156 @@
+use crate::compare_fitness;
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// How the random step size alpha changes over a run
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AlphaSchedule {
+    /// The configured alpha throughout
+    #[default]
+    Constant,
+    /// Falling linearly to `final_fraction` of the configured alpha by the last iteration
+    Linear { final_fraction: f64 },
+    /// Multiplied by `factor` after every iteration
+    Geometric { factor: f64 },
+}
+
+impl AlphaSchedule {
+    /// Multiplier of the configured alpha in `iteration` of `iterations`
+    pub fn scale(self, iteration: usize, iterations: usize) -> f64 {
+        match self {
+            AlphaSchedule::Constant => 1.0,
+            AlphaSchedule::Linear { final_fraction } => {
+                let progress = iteration as f64 / iterations.saturating_sub(1).max(1) as f64;
+                1.0 - (1.0 - final_fraction) * progress.min(1.0)
+            }
+            AlphaSchedule::Geometric { factor } => factor.powi(iteration as i32),
+        }
+    }
+
+    /// Whether the schedule's parameter is usable: a fraction or factor in [0, 1]
+    pub fn is_valid(self) -> bool {
+        match self {
+            AlphaSchedule::Constant => true,
+            AlphaSchedule::Linear {
+                final_fraction: value,
+            }
+            | AlphaSchedule::Geometric { factor: value } => (0.0..=1.0).contains(&value),
+        }
+    }
+}
+
+impl FromStr for AlphaSchedule {
+    type Err = String;
+
+    /// `constant`, `linear:<final fraction>` or `geometric:<factor>`
+    fn from_str(value: &str) -> Result<AlphaSchedule, String> {
+        let (kind, parameter) = match value.split_once(':') {
+            Some((kind, parameter)) => (kind, Some(parameter)),
+            None => (value, None),
+        };
+        let number = || -> Result<f64, String> {
+            let parameter = parameter.ok_or_else(|| format!("{} needs a parameter", kind))?;
+            parameter
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid alpha schedule parameter {}", parameter))
+        };
+        match kind.trim() {
+            "constant" if parameter.is_none() => Ok(AlphaSchedule::Constant),
+            "linear" => Ok(AlphaSchedule::Linear {
+                final_fraction: number()?,
+            }),
+            "geometric" => Ok(AlphaSchedule::Geometric { factor: number()? }),
+            _ => Err(format!(
+                "unknown alpha schedule {}, expected constant, linear:<final fraction> or geometric:<factor>",
+                value
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AlphaSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AlphaSchedule::Constant => write!(f, "constant"),
+            AlphaSchedule::Linear { final_fraction } => write!(f, "linear:{}", final_fraction),
+            AlphaSchedule::Geometric { factor } => write!(f, "geometric:{}", factor),
+        }
+    }
+}
+
+// Written in scenario files the same way as on the command line
+impl<'de> Deserialize<'de> for AlphaSchedule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<AlphaSchedule, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+/// Stop a search once its best fitness stops improving
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EarlyStopping {
+    /// Iterations without improvement before stopping
+    pub patience: usize,
+    /// Smallest gain in the best fitness that counts as an improvement
+    pub tolerance: f64,
+}
+
+impl EarlyStopping {
+    /// Stop after `patience` iterations without any improvement
+    pub fn new(patience: usize) -> EarlyStopping {
+        EarlyStopping {
+            patience,
+            tolerance: 0.0,
+        }
+    }
+}
+
+// Iterations since the best fitness last improved by more than the tolerance
+pub(crate) struct Stall {
+    stopping: Option<EarlyStopping>,
+    best: f64,
+    iterations: usize,
+}
+
+impl Stall {
+    pub(crate) fn new(stopping: Option<EarlyStopping>, best: f64) -> Stall {
+        Stall {
+            stopping,
+            best,
+            iterations: 0,
+        }
+    }
+
+    // Record the best fitness after an iteration, returning whether to stop
+    pub(crate) fn update(&mut self, best: f64) -> bool {
+        let Some(stopping) = self.stopping else {
+            return false;
+        };
+        // A NaN best is beaten by any number
+        if compare_fitness(best, self.best + stopping.tolerance).is_gt() {
+            self.best = best;
+            self.iterations = 0;
+        } else {
+            self.iterations += 1;
+        }
+        self.iterations >= stopping.patience
+    }
+
+    // Start counting afresh from a rescored best fitness
+    pub(crate) fn reset(&mut self, best: f64) {
+        self.best = best;
+        self.iterations = 0;
+    }
+
+    // Record an iteration that improved or did not, for searches without a scalar best
+    pub(crate) fn update_improved(&mut self, improved: bool) -> bool {
+        let Some(stopping) = self.stopping else {
+            return false;
+        };
+        self.iterations = if improved { 0 } else { self.iterations + 1 };
+        self.iterations >= stopping.patience
+    }
+}
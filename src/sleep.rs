@@ -0,0 +1,132 @@
+//! Sleep scheduling of a placed layout for low-demand periods.
+//!
+//! Routers that carry little traffic at night or over a weekend can be powered down
+//! as long as the ones still awake stay connected and cover enough clients. The
+//! schedule puts routers to sleep one at a time, each time choosing the router whose
+//! absence loses the least coverage, so that any prefix of it is a valid sleep set:
+//! operators pick how deep to go by how low demand is.
+
+use crate::metrics::{ConnectivityGraph, ncmc, sgc};
+use crate::radio::Radio;
+use crate::{ClientSet, DIMENSIONS, FireflyConfig};
+use serde_json::json;
+
+/// One router put to sleep, with the service of the routers still awake
+#[derive(Clone, Debug, PartialEq)]
+pub struct SleepStep {
+    /// Index of the router in the layout
+    pub router: usize,
+    /// Routers still awake
+    pub awake: usize,
+    /// Size of the giant component of the awake routers
+    pub sgc: usize,
+    /// Share of the client weight the awake routers cover
+    pub coverage: f64,
+}
+
+/// Order in which the routers of a layout can go to sleep
+#[derive(Clone, Debug, PartialEq)]
+pub struct SleepSchedule {
+    /// Lowest coverage share any step may leave
+    pub coverage_floor: f64,
+    /// Size of the giant component with every router awake
+    pub full_sgc: usize,
+    /// Coverage share with every router awake
+    pub full_coverage: f64,
+    pub steps: Vec<SleepStep>,
+}
+
+impl SleepSchedule {
+    /// Routers asleep once the whole schedule is applied
+    pub fn asleep(&self) -> Vec<usize> {
+        self.steps.iter().map(|step| step.router).collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "coverage_floor": self.coverage_floor,
+            "full_sgc": self.full_sgc,
+            "full_coverage": self.full_coverage,
+            "steps": self
+                .steps
+                .iter()
+                .map(|step| json!({
+                    "router": step.router,
+                    "awake": step.awake,
+                    "sgc": step.sgc,
+                    "coverage": step.coverage
+                }))
+                .collect::<Vec<_>>()
+        })
+    }
+}
+
+// Connected components and coverage share of the routers of `awake` alone
+fn service(
+    config: &FireflyConfig,
+    routers: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    awake: &[usize],
+) -> (usize, usize, f64) {
+    let model = config
+        .radio
+        .subset(config.max_communication_distance, awake);
+    let radio = Radio::with_model(config.max_communication_distance, &model);
+    let layout: Vec<[f64; DIMENSIONS]> = awake.iter().map(|&i| routers[i]).collect();
+    let graph = ConnectivityGraph::new(&layout, &radio);
+    let coverage = clients.coverage_share(ncmc(&layout, clients, &radio));
+    (graph.components(), sgc(&graph), coverage)
+}
+
+/// Greedy sleep schedule of `routers` under the radio model of `config`.
+///
+/// Every step puts to sleep the router whose absence leaves the highest coverage share,
+/// the lowest index on ties, among those that neither disconnect two awake routers
+/// linked through it nor leave less than `coverage_floor` of the client weight
+/// covered. The schedule ends when no router qualifies; one router
+/// always stays awake. A layout covering less than the floor to begin with gets an
+/// empty schedule.
+pub fn sleep_schedule(
+    config: &FireflyConfig,
+    routers: &[[f64; DIMENSIONS]],
+    clients: &ClientSet,
+    coverage_floor: f64,
+) -> SleepSchedule {
+    let mut awake: Vec<usize> = (0..routers.len()).collect();
+    let (mut components, full_sgc, full_coverage) = service(config, routers, clients, &awake);
+    let mut steps = Vec::new();
+    while awake.len() > 1 {
+        let mut best: Option<(usize, usize, usize, f64)> = None;
+        for position in 0..awake.len() {
+            let mut candidate = awake.clone();
+            candidate.remove(position);
+            let (candidate_components, sgc, coverage) =
+                service(config, routers, clients, &candidate);
+            // A router whose absence splits its component leaves more components;
+            // an isolated one leaves fewer
+            if candidate_components <= components
+                && coverage >= coverage_floor
+                && best.is_none_or(|(_, _, _, best)| coverage > best)
+            {
+                best = Some((position, candidate_components, sgc, coverage));
+            }
+        }
+        let Some((position, candidate_components, sgc, coverage)) = best else {
+            break;
+        };
+        components = candidate_components;
+        let router = awake.remove(position);
+        steps.push(SleepStep {
+            router,
+            awake: awake.len(),
+            sgc,
+            coverage,
+        });
+    }
+    SleepSchedule {
+        coverage_floor,
+        full_sgc,
+        full_coverage,
+        steps,
+    }
+}
@@ -0,0 +1,259 @@
+use crate::history::IterationRecord;
+use crate::radio::{Radio, RadioModel};
+use crate::render::Plot;
+use crate::report::{FidelityChange, ParetoLayout, Report, SearchFidelity, provenance};
+use crate::{ClientSet, ConnectivityGraph, DIMENSIONS, FireflyConfig, distance, migrate};
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// User-supplied metadata identifying a run
+#[derive(Clone, Debug, Default)]
+pub struct RunInfo {
+    pub name: Option<String>,
+    pub tags: BTreeMap<String, String>,
+    pub objective_plugin: Option<String>,
+    pub clients_file: Option<String>,
+}
+
+/// Final layout returned by the optimizer, with helpers to analyze it
+pub struct Solution {
+    pub routers: Vec<[f64; DIMENSIONS]>,
+    pub clients: ClientSet,
+    /// Configuration the layout was optimized with
+    pub config: FireflyConfig,
+    pub report: Report,
+}
+
+impl Solution {
+    fn is_covered(&self, client: &[f64; DIMENSIONS]) -> bool {
+        (0..self.routers.len()).any(|router| self.covers(router, client))
+    }
+
+    /// Weighted fraction of mesh clients within range of at least one router
+    pub fn coverage(&self) -> f64 {
+        let total = self.clients.total_weight();
+        if total == 0.0 {
+            return 1.0;
+        }
+        let covered: f64 = self
+            .clients
+            .positions
+            .iter()
+            .zip(&self.clients.weights)
+            .filter(|(client, _)| self.is_covered(client))
+            .map(|(_, weight)| weight)
+            .sum();
+        covered / total
+    }
+
+    /// How far the layout falls short of the configured goal, zero or less once met
+    pub fn goal_shortfall(&self) -> Option<f64> {
+        let sgc = self.report.metrics.sgc as f64 / self.routers.len() as f64;
+        self.config
+            .goal
+            .map(|goal| goal.shortfall(sgc, self.coverage()))
+    }
+
+    pub fn connectivity_graph(&self) -> ConnectivityGraph {
+        ConnectivityGraph::new(&self.routers, &Radio::new(&self.config))
+    }
+
+    /// Positions of the mesh clients no router covers
+    pub fn uncovered_clients(&self) -> Vec<[f64; DIMENSIONS]> {
+        self.clients
+            .positions
+            .iter()
+            .filter(|client| !self.is_covered(client))
+            .copied()
+            .collect()
+    }
+
+    /// Indices of the clients each router covers
+    pub fn router_clients(&self) -> Vec<Vec<usize>> {
+        (0..self.routers.len())
+            .map(|router| {
+                (0..self.clients.len())
+                    .filter(|&client| self.covers(router, &self.clients.positions[client]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Indices of the routers covering each client
+    pub fn client_routers(&self) -> Vec<Vec<usize>> {
+        (0..self.clients.len())
+            .map(|client| {
+                (0..self.routers.len())
+                    .filter(|&router| self.covers(router, &self.clients.positions[client]))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Data rate of every client from its fastest covering router under the configured
+    /// rate model, 0 for uncovered clients
+    pub fn client_rates(&self) -> Option<Vec<f64>> {
+        let model = self.config.rates.as_ref()?;
+        let radio = Radio::new(&self.config);
+        let rates = self
+            .clients
+            .positions
+            .iter()
+            .map(|client| {
+                (0..self.routers.len())
+                    .filter(|&router| self.covers(router, client))
+                    .map(|router| {
+                        model.rate(
+                            distance(&self.routers[router], client),
+                            radio.coverage_radius(router),
+                        )
+                    })
+                    .fold(0.0, f64::max)
+            })
+            .collect();
+        Some(rates)
+    }
+
+    fn covers(&self, router: usize, client: &[f64; DIMENSIONS]) -> bool {
+        let radio = Radio::new(&self.config);
+        radio.covers(router, distance(&self.routers[router], client))
+            && !radio.blocked(&self.routers[router], client)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let (run, report) = (&self.config.run, &self.report);
+        let mut data = json!({
+            "schema_version": migrate::SCHEMA_VERSION,
+            "run": {
+                "name": run.name,
+                "tags": run.tags,
+                "objective_plugin": run.objective_plugin,
+                "clients_file": run.clients_file,
+                "seed": self.config.seed
+            },
+            "mesh_routers": self.routers,
+            "mesh_clients": self.clients.positions,
+            "mesh_client_weights": self.clients.weights,
+            "router_clients": self.router_clients(),
+            "client_routers": self.client_routers(),
+            "best_fitness": report.metrics.fitness,
+            "sgc": report.metrics.sgc,
+            "ncmc": report.metrics.ncmc,
+            "ncmcpr": report.metrics.ncmcpr,
+            "coverage_overlap": report.metrics.overlap,
+            "search_fidelity": report.search_fidelity.as_ref().map(SearchFidelity::to_json),
+            // Wall-clock timings would make the files of seeded runs differ
+            "metric_timings": match self.config.seed {
+                Some(_) => serde_json::Value::Null,
+                None => report.timings.to_json(),
+            },
+            "fidelity_changes": report
+                .fidelity_changes
+                .iter()
+                .map(FidelityChange::to_json)
+                .collect::<Vec<_>>(),
+            "warnings": report.warnings,
+            "provenance": provenance()
+        });
+        if self.config.radio != RadioModel::default() {
+            data["radio"] = radio_json(&self.config.radio);
+        }
+        if let Some(violations) = report.metrics.separation_violations {
+            data["separation_violations"] = json!(violations);
+        }
+        if let Some(distance) = report.metrics.relocation_distance {
+            data["relocation_distance"] = json!(distance);
+        }
+        if let Some(precision) = self.config.fixed_point {
+            data["fixed_point"] = json!(precision);
+        }
+        if let Some(snapping) = &report.snapping {
+            data["snapping"] = snapping.to_json(&report.metrics);
+        }
+        if let Some(history) = &report.history {
+            data["history"] = history.iter().map(IterationRecord::to_json).collect();
+        }
+        if let Some(robustness) = &self.config.robustness {
+            data["robustness"] = robustness.to_json();
+            data["robustness"]["fitness"] = json!(report.robust_fitness);
+        }
+        if let (Some(goal), Some(shortfall)) = (&self.config.goal, self.goal_shortfall()) {
+            data["goal"] = goal.to_json();
+            data["goal"]["shortfall"] = json!(shortfall);
+        }
+        if let (Some(model), Some(rates)) = (&self.config.rates, self.client_rates()) {
+            data["rates"] = json!({
+                "model": model.to_string(),
+                "client_rates": rates,
+                "network_rate": rates.iter().sum::<f64>()
+            });
+        }
+        if let Some(transform) = self.config.brightness {
+            data["brightness"] = json!(transform.to_string());
+        }
+        if let Some(termination) = &self.config.termination {
+            data["termination"] = json!(termination.to_string());
+        }
+        if self.config.evaluation_interval > 1 {
+            data["evaluation_interval"] = json!(self.config.evaluation_interval);
+            data["movement_only_iterations"] = json!(report.movement_only_iterations);
+        }
+        if let Some(iterations) = report.stopped_after {
+            data["stopped_after"] = json!(iterations);
+        }
+        if let Some(front) = &report.pareto_front {
+            data["pareto_front"] = front.iter().map(ParetoLayout::to_json).collect();
+        }
+        data
+    }
+
+    /// Picture of the layout and its clients, see [`Plot`]
+    pub fn plot(&self) -> Plot<'_> {
+        Plot::new(&self.routers, &self.clients, &self.config)
+    }
+
+    /// Render the deployment area, coverage disks, router links, routers and clients as SVG
+    pub fn plot_svg(&self) -> String {
+        self.plot().svg()
+    }
+}
+
+// Radio model of a run, for result files
+fn radio_json(radio: &RadioModel) -> serde_json::Value {
+    let mut data = json!({
+        "radii": radio.radii,
+        "min_separation": radio.min_separation,
+        "separation_penalty": radio.separation_penalty,
+        "path_loss": radio.path_loss.map(|path_loss| json!({
+            "tx_power": path_loss.tx_power,
+            "reference_loss": path_loss.reference_loss,
+            "exponent": path_loss.exponent,
+            "sensitivity": path_loss.sensitivity
+        }))
+    });
+    if !radio.walls.is_empty() {
+        data["walls"] = radio
+            .walls
+            .iter()
+            .map(|wall| [wall.from[0], wall.from[1], wall.to[0], wall.to[1]])
+            .collect();
+    }
+    data
+}
+
+/// Read a list of points such as "mesh_routers" from a result file
+pub fn points_from_json(
+    data: &serde_json::Value,
+    key: &str,
+) -> Result<Vec<[f64; DIMENSIONS]>, String> {
+    serde_json::from_value(data[key].clone()).map_err(|err| format!("Invalid {}: {}", key, err))
+}
+
+/// Read a router layout that is either a list of positions or a result file
+pub fn routers_from_json(data: &serde_json::Value) -> Result<Vec<[f64; DIMENSIONS]>, String> {
+    if data.is_array() {
+        return serde_json::from_value(data.clone())
+            .map_err(|err| format!("Invalid router layout: {}", err));
+    }
+    points_from_json(data, "mesh_routers")
+}
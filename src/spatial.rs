@@ -0,0 +1,147 @@
+use crate::{DIMENSIONS, distance};
+
+// Upper bound on grid cells per point, so sparse point sets do not allocate huge grids
+const MAX_CELLS_PER_POINT: usize = 4;
+
+/// Uniform grid over a set of points for finding the points within a fixed radius.
+///
+/// Cells are at least as wide as the search radius, so a query only scans the cell
+/// holding the query point and its direct neighbours instead of every point. Points
+/// with non-finite coordinates are left out, as they are never within any radius.
+pub struct SpatialGrid<'a> {
+    points: &'a [[f64; DIMENSIONS]],
+    radius: f64,
+    origin: [f64; DIMENSIONS],
+    cell_size: f64,
+    shape: [usize; DIMENSIONS],
+    // Points of cell `c` are `entries[cell_start[c]..cell_start[c + 1]]`, in index order
+    cell_start: Vec<usize>,
+    entries: Vec<usize>,
+}
+
+impl<'a> SpatialGrid<'a> {
+    pub fn new(points: &'a [[f64; DIMENSIONS]], radius: f64) -> SpatialGrid<'a> {
+        let finite = |point: &[f64; DIMENSIONS]| point.iter().all(|coord| coord.is_finite());
+        let mut origin = [f64::INFINITY; DIMENSIONS];
+        let mut extent = [f64::NEG_INFINITY; DIMENSIONS];
+        for point in points.iter().filter(|point| finite(point)) {
+            for axis in 0..DIMENSIONS {
+                origin[axis] = origin[axis].min(point[axis]);
+                extent[axis] = extent[axis].max(point[axis]);
+            }
+        }
+        let mut grid = SpatialGrid {
+            points,
+            radius,
+            origin,
+            // Any positive width works for a zero radius, which only matches equal points
+            cell_size: if radius > 0.0 { radius } else { 1.0 },
+            shape: [0; DIMENSIONS],
+            cell_start: vec![0],
+            entries: Vec::new(),
+        };
+        if origin[0] > extent[0] {
+            // No finite points
+            return grid;
+        }
+        for axis in 0..DIMENSIONS {
+            extent[axis] -= origin[axis];
+        }
+
+        let max_cells = points.len().max(1) * MAX_CELLS_PER_POINT;
+        let shape =
+            |cell_size: f64| extent.map(|extent| ((extent / cell_size) as usize).saturating_add(1));
+        while shape(grid.cell_size)
+            .iter()
+            .try_fold(1usize, |cells, &n| cells.checked_mul(n))
+            .is_none_or(|cells| cells > max_cells)
+        {
+            grid.cell_size *= 2.0;
+        }
+        grid.shape = shape(grid.cell_size);
+
+        // Counting sort of the finite points by cell
+        let cells = grid.shape.iter().product();
+        let mut counts = vec![0; cells + 1];
+        let point_cells: Vec<Option<usize>> = points
+            .iter()
+            .map(|point| finite(point).then(|| grid.cell_index(&grid.cell(point))))
+            .collect();
+        for &cell in point_cells.iter().flatten() {
+            counts[cell + 1] += 1;
+        }
+        for cell in 0..cells {
+            counts[cell + 1] += counts[cell];
+        }
+        grid.entries = vec![0; counts[cells]];
+        let mut next = counts.clone();
+        for (i, cell) in point_cells.iter().enumerate() {
+            if let Some(cell) = *cell {
+                grid.entries[next[cell]] = i;
+                next[cell] += 1;
+            }
+        }
+        grid.cell_start = counts;
+        grid
+    }
+
+    // Grid coordinates of the cell holding `point`, which may lie outside the grid
+    fn cell(&self, point: &[f64; DIMENSIONS]) -> [i64; DIMENSIONS] {
+        std::array::from_fn(|axis| {
+            ((point[axis] - self.origin[axis]) / self.cell_size).floor() as i64
+        })
+    }
+
+    fn cell_index(&self, cell: &[i64; DIMENSIONS]) -> usize {
+        cell.iter()
+            .zip(&self.shape)
+            .rev()
+            .fold(0, |index, (&coord, &n)| index * n + coord as usize)
+    }
+
+    // Points in the cell holding `query` and its direct neighbours, 3^DIMENSIONS cells
+    fn neighborhood(&self, query: &[f64; DIMENSIONS]) -> impl Iterator<Item = usize> + '_ {
+        let queryable = !self.entries.is_empty() && query.iter().all(|coord| coord.is_finite());
+        let center = self.cell(query);
+        (0..3usize.pow(DIMENSIONS as u32))
+            .filter(move |_| queryable)
+            .filter_map(move |offset| {
+                let mut cell = center;
+                let mut rest = offset;
+                for (coord, &n) in cell.iter_mut().zip(&self.shape) {
+                    *coord = coord.saturating_add((rest % 3) as i64 - 1);
+                    rest /= 3;
+                    if *coord < 0 || *coord >= n as i64 {
+                        return None;
+                    }
+                }
+                let index = self.cell_index(&cell);
+                Some(&self.entries[self.cell_start[index]..self.cell_start[index + 1]])
+            })
+            .flatten()
+            .copied()
+    }
+
+    /// Points near `query`, a superset of the points within the radius in no particular
+    /// order, for callers testing each point against its own range
+    pub fn candidates(&self, query: &[f64; DIMENSIONS]) -> impl Iterator<Item = usize> + '_ {
+        self.neighborhood(query)
+    }
+
+    /// Indices of the points within the radius of `query`, in ascending order
+    pub fn within(&self, query: &[f64; DIMENSIONS]) -> Vec<usize> {
+        let mut found: Vec<usize> = self
+            .neighborhood(query)
+            .filter(|&i| distance(&self.points[i], query) <= self.radius)
+            .collect();
+        // Sorted so that sums over the result match a brute-force scan
+        found.sort_unstable();
+        found
+    }
+
+    /// Whether any point lies within the radius of `query`
+    pub fn any_within(&self, query: &[f64; DIMENSIONS]) -> bool {
+        self.neighborhood(query)
+            .any(|i| distance(&self.points[i], query) <= self.radius)
+    }
+}
@@ -0,0 +1,329 @@
+use crate::schedule::{EarlyStopping, Stall};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// Rule for stopping a search before its last iteration, combining conditions on the
+/// best layout found so far.
+///
+/// Written as `stall:<patience>[:<tolerance>]`, `coverage:<share>`, `fitness:<value>`
+/// and `time:<seconds>`, combined with `all(...)` and `any(...)`:
+///
+/// ```
+/// use ff_wmn::Termination;
+///
+/// let rule: Termination = "any(all(stall:50,coverage:0.9),time:600)".parse().unwrap();
+/// assert!(matches!(&rule, Termination::Any(rules) if rules.len() == 2));
+/// assert_eq!(rule.to_string(), "any(all(stall:50,coverage:0.9),time:600)");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum Termination {
+    /// The best fitness improved by no more than `tolerance` for `patience` iterations
+    Stall { patience: usize, tolerance: f64 },
+    /// The best layout covers at least this share of the client weight
+    Coverage(f64),
+    /// The best fitness reached this value
+    Fitness(f64),
+    /// The search has run this long, which a seeded run cannot depend on
+    Time(Duration),
+    /// Every rule holds
+    All(Vec<Termination>),
+    /// At least one rule holds
+    Any(Vec<Termination>),
+}
+
+impl Termination {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Termination::Stall {
+                patience,
+                tolerance,
+            } => {
+                if *patience == 0 {
+                    return Err("a stall rule needs a patience of at least one".to_string());
+                }
+                if !(*tolerance >= 0.0 && tolerance.is_finite()) {
+                    return Err(format!(
+                        "the stall tolerance must be finite and non-negative, got {}",
+                        tolerance
+                    ));
+                }
+            }
+            Termination::Coverage(share) => {
+                if !(0.0..=1.0).contains(share) {
+                    return Err(format!(
+                        "the coverage to stop at must be a share in [0, 1], got {}",
+                        share
+                    ));
+                }
+            }
+            Termination::Fitness(fitness) => {
+                if !fitness.is_finite() {
+                    return Err(format!(
+                        "the fitness to stop at must be finite, got {}",
+                        fitness
+                    ));
+                }
+            }
+            Termination::Time(_) => {}
+            Termination::All(rules) | Termination::Any(rules) => {
+                if rules.is_empty() {
+                    return Err("all(...) and any(...) need at least one rule".to_string());
+                }
+                for rule in rules {
+                    rule.validate()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Whether the rule checks a time limit anywhere, so that it depends on wall-clock time
+    pub(crate) fn has_time_limit(&self) -> bool {
+        match self {
+            Termination::Time(_) => true,
+            Termination::All(rules) | Termination::Any(rules) => {
+                rules.iter().any(Termination::has_time_limit)
+            }
+            _ => false,
+        }
+    }
+
+    // Whether checking the rule needs the coverage of the best layout
+    fn needs_coverage(&self) -> bool {
+        match self {
+            Termination::Coverage(_) => true,
+            Termination::All(rules) | Termination::Any(rules) => {
+                rules.iter().any(Termination::needs_coverage)
+            }
+            _ => false,
+        }
+    }
+
+    // Early-stopping parameters of every stall rule, in the order they are written
+    fn stalls(&self, stalls: &mut Vec<EarlyStopping>) {
+        match self {
+            Termination::Stall {
+                patience,
+                tolerance,
+            } => stalls.push(EarlyStopping {
+                patience: *patience,
+                tolerance: *tolerance,
+            }),
+            Termination::All(rules) | Termination::Any(rules) => {
+                for rule in rules {
+                    rule.stalls(stalls);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Whether the rule holds, taking the verdict of every stall rule from `stalled` in
+    // the order they are written
+    fn holds(
+        &self,
+        stalled: &mut impl Iterator<Item = bool>,
+        best: f64,
+        coverage: Option<f64>,
+        elapsed: Duration,
+    ) -> bool {
+        match self {
+            Termination::Stall { .. } => stalled.next().expect("a verdict per stall rule"),
+            Termination::Coverage(share) => coverage.is_some_and(|coverage| coverage >= *share),
+            Termination::Fitness(fitness) => best >= *fitness,
+            Termination::Time(limit) => elapsed >= *limit,
+            Termination::All(rules) | Termination::Any(rules) => {
+                // Every rule is checked so that the stall verdicts stay in order
+                let verdicts: Vec<bool> = rules
+                    .iter()
+                    .map(|rule| rule.holds(stalled, best, coverage, elapsed))
+                    .collect();
+                if matches!(self, Termination::All(_)) {
+                    verdicts.into_iter().all(|holds| holds)
+                } else {
+                    verdicts.into_iter().any(|holds| holds)
+                }
+            }
+        }
+    }
+}
+
+// Split a list of rules at the commas outside parentheses
+fn split_rules(list: &str) -> Result<Vec<&str>, String> {
+    let mut rules = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in list.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth = depth
+                    .checked_sub(1)
+                    .ok_or_else(|| format!("unbalanced parentheses in {}", list))?;
+            }
+            ',' if depth == 0 => {
+                rules.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        return Err(format!("unbalanced parentheses in {}", list));
+    }
+    rules.push(&list[start..]);
+    Ok(rules)
+}
+
+impl FromStr for Termination {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Termination, String> {
+        let value = value.trim();
+        for name in ["all", "any"] {
+            if let Some(list) = value
+                .strip_prefix(name)
+                .and_then(|rest| rest.trim_start().strip_prefix('('))
+                .and_then(|rest| rest.strip_suffix(')'))
+            {
+                let rules = split_rules(list)?
+                    .into_iter()
+                    .map(str::parse)
+                    .collect::<Result<Vec<_>, String>>()?;
+                return Ok(if name == "all" {
+                    Termination::All(rules)
+                } else {
+                    Termination::Any(rules)
+                });
+            }
+        }
+
+        let (kind, parameters) = value.split_once(':').unwrap_or((value, ""));
+        let parameters = parameters
+            .split(':')
+            .filter(|parameter| !parameter.trim().is_empty())
+            .map(|parameter| {
+                parameter
+                    .trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid parameter {} of {}", parameter, kind))
+            })
+            .collect::<Result<Vec<f64>, String>>()?;
+        let rule = match (kind.trim(), parameters.as_slice()) {
+            ("stall", [patience] | [patience, _]) => {
+                if !(patience.fract() == 0.0 && *patience >= 0.0) {
+                    return Err(format!(
+                        "the stall patience must be a count, got {}",
+                        patience
+                    ));
+                }
+                Termination::Stall {
+                    patience: *patience as usize,
+                    tolerance: parameters.get(1).copied().unwrap_or(0.0),
+                }
+            }
+            ("coverage", [share]) => Termination::Coverage(*share),
+            ("fitness", [fitness]) => Termination::Fitness(*fitness),
+            ("time", [seconds]) => {
+                Termination::Time(Duration::try_from_secs_f64(*seconds).map_err(|_| {
+                    format!(
+                        "the time limit must be a number of seconds, got {}",
+                        seconds
+                    )
+                })?)
+            }
+            _ => {
+                return Err(format!(
+                    "invalid stopping rule {}, expected stall:<patience>[:<tolerance>], coverage:<share>, fitness:<value>, time:<seconds>, all(...) or any(...)",
+                    value
+                ));
+            }
+        };
+        Ok(rule)
+    }
+}
+
+impl fmt::Display for Termination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let list = |rules: &[Termination]| {
+            rules
+                .iter()
+                .map(Termination::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        match self {
+            Termination::Stall {
+                patience,
+                tolerance,
+            } if *tolerance == 0.0 => write!(f, "stall:{}", patience),
+            Termination::Stall {
+                patience,
+                tolerance,
+            } => write!(f, "stall:{}:{}", patience, tolerance),
+            Termination::Coverage(share) => write!(f, "coverage:{}", share),
+            Termination::Fitness(fitness) => write!(f, "fitness:{}", fitness),
+            Termination::Time(limit) => write!(f, "time:{}", limit.as_secs_f64()),
+            Termination::All(rules) => write!(f, "all({})", list(rules)),
+            Termination::Any(rules) => write!(f, "any({})", list(rules)),
+        }
+    }
+}
+
+// Written in scenario files the same way as on the command line
+impl<'de> Deserialize<'de> for Termination {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Termination, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+// Progress of a search toward its stopping rule
+pub(crate) struct Stopper<'a> {
+    rule: &'a Termination,
+    // One per stall rule, in the order they are written
+    stalls: Vec<Stall>,
+    start: Instant,
+}
+
+impl<'a> Stopper<'a> {
+    pub(crate) fn new(rule: &'a Termination, best: f64) -> Stopper<'a> {
+        let mut stalls = Vec::new();
+        rule.stalls(&mut stalls);
+        Stopper {
+            rule,
+            stalls: stalls
+                .into_iter()
+                .map(|stopping| Stall::new(Some(stopping), best))
+                .collect(),
+            start: Instant::now(),
+        }
+    }
+
+    // Record the best fitness after an iteration, returning whether to stop;
+    // `coverage` gives the coverage share of the best layout when the rule needs it
+    pub(crate) fn update(&mut self, best: f64, coverage: impl FnOnce() -> f64) -> bool {
+        let stalled: Vec<bool> = self
+            .stalls
+            .iter_mut()
+            .map(|stall| stall.update(best))
+            .collect();
+        let coverage = self.rule.needs_coverage().then(coverage);
+        self.rule.holds(
+            &mut stalled.into_iter(),
+            best,
+            coverage,
+            self.start.elapsed(),
+        )
+    }
+
+    // Start the stall rules afresh from a rescored best fitness
+    pub(crate) fn reset(&mut self, best: f64) {
+        for stall in &mut self.stalls {
+            stall.reset(best);
+        }
+    }
+}
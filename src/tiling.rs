@@ -0,0 +1,134 @@
+use crate::sanity::NonFinite;
+use crate::{ClientSet, DIMENSIONS, FireflyConfig, FireflyOptimizer};
+use rand::Rng;
+use rayon::prelude::*;
+
+/// Divide-and-conquer decomposition of a large deployment area.
+///
+/// The area is split into `tiles` × `tiles` square tiles, each optimized on its own
+/// with a share of the routers proportional to the client weight it holds; a
+/// stitching pass over the whole area then starts from the combined layout to repair
+/// connectivity across tile boundaries.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tiling {
+    /// Tiles along every axis
+    pub tiles: usize,
+    /// Iterations of the stitching pass over the whole area
+    pub stitch_iterations: usize,
+}
+
+// Tile holding `point`, with points outside the bounds in the nearest edge tile
+fn tile_of(point: &[f64; DIMENSIONS], config: &FireflyConfig, tiles: usize) -> usize {
+    let width = config.extent() / tiles as f64;
+    point.iter().rev().fold(0, |index, coord| {
+        let cell = ((coord - config.lower_bound) / width).floor().max(0.0) as usize;
+        index * tiles + cell.min(tiles - 1)
+    })
+}
+
+// Lower corner of a tile
+fn tile_origin(tile: usize, config: &FireflyConfig, tiles: usize) -> [f64; DIMENSIONS] {
+    let width = config.extent() / tiles as f64;
+    let mut rest = tile;
+    std::array::from_fn(|_| {
+        let cell = rest % tiles;
+        rest /= tiles;
+        config.lower_bound + cell as f64 * width
+    })
+}
+
+/// Share `routers` among tiles in proportion to their client weight, by largest
+/// remainder; tiles share them evenly when no client carries weight
+pub fn allocate_routers(routers: usize, weights: &[f64]) -> Vec<usize> {
+    let total: f64 = weights.iter().sum();
+    let quotas: Vec<f64> = weights
+        .iter()
+        .map(|&weight| {
+            if total > 0.0 {
+                routers as f64 * weight / total
+            } else {
+                routers as f64 / weights.len() as f64
+            }
+        })
+        .collect();
+    let mut allocation: Vec<usize> = quotas.iter().map(|quota| quota.floor() as usize).collect();
+    let mut by_remainder: Vec<usize> = (0..weights.len()).collect();
+    by_remainder.sort_by(|&i, &j| {
+        (quotas[j] - quotas[j].floor()).total_cmp(&(quotas[i] - quotas[i].floor()))
+    });
+    let remaining = routers.saturating_sub(allocation.iter().sum());
+    for &tile in by_remainder.iter().take(remaining) {
+        allocation[tile] += 1;
+    }
+    allocation
+}
+
+// Optimize every tile in parallel and combine the tiles' best layouts
+pub(crate) fn tile_layout(
+    config: &FireflyConfig,
+    tiling: Tiling,
+    clients: &ClientSet,
+    rng: &mut impl Rng,
+) -> Result<Vec<[f64; DIMENSIONS]>, NonFinite> {
+    let tiles = tiling.tiles;
+    let count = tiles.pow(DIMENSIONS as u32);
+    let mut tile_clients = vec![(Vec::new(), Vec::new()); count];
+    for (client, &weight) in clients.positions.iter().zip(&clients.weights) {
+        let (positions, weights) = &mut tile_clients[tile_of(client, config, tiles)];
+        positions.push(*client);
+        weights.push(weight);
+    }
+    let tile_weights: Vec<f64> = tile_clients
+        .iter()
+        .map(|(_, weights)| weights.iter().sum())
+        .collect();
+    let allocation = allocate_routers(config.routers, &tile_weights);
+
+    // Every tile is searched in its own coordinates, from 0 to the tile width
+    let width = config.extent() / tiles as f64;
+    let runs: Vec<_> = tile_clients
+        .into_iter()
+        .zip(allocation)
+        .enumerate()
+        .filter(|(_, (_, routers))| *routers > 0)
+        .map(|(tile, ((positions, weights), routers))| {
+            let origin = tile_origin(tile, config, tiles);
+            let positions: Vec<[f64; DIMENSIONS]> = positions
+                .iter()
+                .map(|client| std::array::from_fn(|axis| client[axis] - origin[axis]))
+                .collect();
+            let clients = ClientSet::with_weights(positions, weights)
+                .expect("tile clients come from a valid client set");
+            let tile_config = FireflyConfig {
+                routers,
+                lower_bound: 0.0,
+                upper_bound: width,
+                snap: None,
+                tiling: None,
+                multiresolution: None,
+                relocation: None,
+                history: Default::default(),
+                seed: config.seed.map(|_| rng.r#gen()),
+                run: Default::default(),
+                ..config.clone()
+            };
+            (origin, tile_config, clients)
+        })
+        .collect();
+
+    let layouts = runs
+        .into_par_iter()
+        .map(|(origin, tile_config, clients)| {
+            let solution = FireflyOptimizer::new(tile_config)
+                .expect("tiles inherit a valid configuration")
+                .with_clients(clients)
+                .try_run()?;
+            Ok(solution
+                .routers
+                .iter()
+                .map(|router| std::array::from_fn(|axis| router[axis] + origin[axis]))
+                .collect::<Vec<[f64; DIMENSIONS]>>())
+        })
+        .collect::<Result<Vec<_>, NonFinite>>()?;
+    Ok(layouts.concat())
+}
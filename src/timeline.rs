@@ -0,0 +1,381 @@
+//! Scripted changes to a deployment while it is being optimized.
+//!
+//! A timeline lists events on a simulation clock counted in iterations: clients
+//! appear or leave, routers fail and come back, walls go up. The optimizer runs from
+//! one event to the next, every phase starting from the layout the previous one left,
+//! so the timeline shows how much an event costs and how much of it the search wins
+//! back before the next one.
+
+use crate::metrics::Metrics;
+use crate::radio::Wall;
+use crate::sanity::NonFinite;
+use crate::{ClientSet, DIMENSIONS, FireflyConfig, FireflyOptimizer};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// A change to the problem being optimized
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case", deny_unknown_fields)]
+pub enum TimelineEvent {
+    /// New clients, of weight 1 unless weights are given
+    AddClients {
+        positions: Vec<[f64; DIMENSIONS]>,
+        weights: Option<Vec<f64>>,
+    },
+    /// Every client inside the rectangle `[x0, y0, x1, y1]` leaves
+    RemoveClients { area: [f64; 4] },
+    /// A router stops working; the others have to make up for it
+    FailRouter { router: usize },
+    /// A failed router works again where it stood
+    RestoreRouter { router: usize },
+    /// An obstacle from `[x0, y0]` to `[x1, y1]` blocks links and coverage crossing it
+    AddWall { wall: [f64; 4] },
+}
+
+impl std::fmt::Display for TimelineEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TimelineEvent::AddClients { positions, .. } => {
+                write!(f, "{} clients appear", positions.len())
+            }
+            TimelineEvent::RemoveClients { area } => write!(
+                f,
+                "clients leave ({}, {})..({}, {})",
+                area[0], area[1], area[2], area[3]
+            ),
+            TimelineEvent::FailRouter { router } => write!(f, "router {} fails", router),
+            TimelineEvent::RestoreRouter { router } => write!(f, "router {} is restored", router),
+            TimelineEvent::AddWall { wall } => write!(
+                f,
+                "wall from ({}, {}) to ({}, {})",
+                wall[0], wall[1], wall[2], wall[3]
+            ),
+        }
+    }
+}
+
+/// An event and the iteration of the simulation clock it happens at
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct ScheduledEvent {
+    pub at: usize,
+    #[serde(flatten)]
+    pub event: TimelineEvent,
+}
+
+/// Events to play while optimizing, loaded from TOML:
+///
+/// ```toml
+/// iterations = 600
+///
+/// [[events]]
+/// at = 200
+/// kind = "fail_router"
+/// router = 3
+///
+/// [[events]]
+/// at = 300
+/// kind = "add_clients"
+/// positions = [[12.0, 40.5], [13.5, 41.0]]
+///
+/// [[events]]
+/// at = 400
+/// kind = "add_wall"
+/// wall = [24.0, 0.0, 24.0, 30.0]
+/// ```
+///
+/// The other kinds are `remove_clients` with an `area = [x0, y0, x1, y1]` and
+/// `restore_router`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Timeline {
+    /// Length of the simulation in iterations, the configured iterations by default
+    pub iterations: Option<usize>,
+    #[serde(default)]
+    pub events: Vec<ScheduledEvent>,
+}
+
+/// Metrics of a layout with the number of clients and their total weight
+#[derive(Clone, Debug)]
+pub struct TimelineSnapshot {
+    pub metrics: Metrics,
+    pub client_weight: f64,
+}
+
+impl TimelineSnapshot {
+    /// Weighted fraction of the clients covered
+    pub fn coverage(&self) -> f64 {
+        if self.client_weight == 0.0 {
+            1.0
+        } else {
+            self.metrics.ncmc / self.client_weight
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let mut data = self.metrics.to_json();
+        data["coverage"] = json!(self.coverage());
+        data
+    }
+}
+
+/// The iterations between two events
+#[derive(Clone, Debug)]
+pub struct TimelinePhase {
+    /// First iteration of the phase, when its events happen
+    pub start: usize,
+    /// Iteration the next phase starts at
+    pub end: usize,
+    pub events: Vec<TimelineEvent>,
+    /// Routers that work during the phase, by index
+    pub active: Vec<usize>,
+    pub clients: usize,
+    pub walls: usize,
+    /// The previous phase's layout right after the events, None for a first phase
+    /// starting from a random layout
+    pub impact: Option<TimelineSnapshot>,
+    /// The best layout at the end of the phase
+    pub result: TimelineSnapshot,
+    /// Position of every router at the end of the phase, failed ones where they stand
+    pub routers: Vec<[f64; DIMENSIONS]>,
+}
+
+impl TimelinePhase {
+    pub fn to_json(&self) -> serde_json::Value {
+        json!({
+            "start": self.start,
+            "end": self.end,
+            "events": self.events.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "active_routers": self.active,
+            "clients": self.clients,
+            "walls": self.walls,
+            "impact": self.impact.as_ref().map(TimelineSnapshot::to_json),
+            "result": self.result.to_json(),
+            "mesh_routers": self.routers
+        })
+    }
+}
+
+impl Timeline {
+    pub fn from_toml(contents: &str) -> Result<Timeline, String> {
+        toml::from_str(contents).map_err(|err| err.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Timeline, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("Unable to read timeline {}: {}", path, err))?;
+        Timeline::from_toml(&contents).map_err(|err| format!("Invalid timeline {}: {}", path, err))
+    }
+
+    // Events grouped by the iteration they happen at
+    fn schedule(&self) -> BTreeMap<usize, Vec<TimelineEvent>> {
+        let mut schedule: BTreeMap<usize, Vec<TimelineEvent>> = BTreeMap::new();
+        for scheduled in &self.events {
+            schedule
+                .entry(scheduled.at)
+                .or_default()
+                .push(scheduled.event.clone());
+        }
+        schedule
+    }
+
+    /// Check that every event fits the simulation of `config`: it happens before the
+    /// end, names an existing router, never fails the last working one and holds
+    /// finite coordinates
+    pub fn validate(&self, config: &FireflyConfig) -> Result<(), String> {
+        let iterations = self.iterations.unwrap_or(config.iterations);
+        let mut failed = vec![false; config.routers];
+        for (at, events) in self.schedule() {
+            if at >= iterations {
+                return Err(format!(
+                    "an event at iteration {} is past the end of the {} iterations",
+                    at, iterations
+                ));
+            }
+            for event in events {
+                let finite = |values: &[f64]| values.iter().all(|value| value.is_finite());
+                match event {
+                    TimelineEvent::AddClients { positions, weights } => {
+                        if !finite(positions.as_flattened()) {
+                            return Err(format!(
+                                "iteration {}: client positions must be finite",
+                                at
+                            ));
+                        }
+                        if let Some(weights) = &weights
+                            && (weights.len() != positions.len()
+                                || !weights
+                                    .iter()
+                                    .all(|&weight| weight.is_finite() && weight >= 0.0))
+                        {
+                            return Err(format!(
+                                "iteration {}: expected a finite non-negative weight per client",
+                                at
+                            ));
+                        }
+                    }
+                    TimelineEvent::RemoveClients { area }
+                    | TimelineEvent::AddWall { wall: area } => {
+                        if !finite(&area) {
+                            return Err(format!("iteration {}: coordinates must be finite", at));
+                        }
+                    }
+                    TimelineEvent::FailRouter { router }
+                    | TimelineEvent::RestoreRouter { router }
+                        if router >= config.routers =>
+                    {
+                        return Err(format!(
+                            "iteration {}: there is no router {} among {}",
+                            at, router, config.routers
+                        ));
+                    }
+                    TimelineEvent::FailRouter { router } => failed[router] = true,
+                    TimelineEvent::RestoreRouter { router } => failed[router] = false,
+                }
+            }
+            if failed.iter().all(|&failed| failed) {
+                return Err(format!("every router has failed by iteration {}", at));
+            }
+        }
+        Ok(())
+    }
+
+    /// Optimize for `clients`, or random ones, while playing the events, starting from
+    /// `initial` or a random layout. Every phase runs the configured search with its
+    /// own seed derived from the configured one; only the router swarm carries its
+    /// layout from one phase to the next, a population search starts every phase anew.
+    pub fn run(
+        &self,
+        config: &FireflyConfig,
+        clients: Option<ClientSet>,
+        initial: Option<Vec<[f64; DIMENSIONS]>>,
+    ) -> Result<Vec<TimelinePhase>, NonFinite> {
+        let iterations = self.iterations.unwrap_or(config.iterations);
+        let mut rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut clients = clients.unwrap_or_else(|| {
+            ClientSet::random(
+                config.clients,
+                config.lower_bound,
+                config.upper_bound,
+                &mut rng,
+            )
+        });
+        let mut routers: Option<Vec<[f64; DIMENSIONS]>> = initial;
+        let mut failed = vec![false; config.routers];
+        let mut walls = config.radio.walls.clone();
+
+        let mut schedule = self.schedule();
+        let mut starts: Vec<usize> = schedule.keys().copied().collect();
+        if starts.first() != Some(&0) {
+            starts.insert(0, 0);
+        }
+        let mut phases = Vec::new();
+        for (phase, &start) in starts.iter().enumerate() {
+            let end = starts.get(phase + 1).copied().unwrap_or(iterations);
+            let events = schedule.remove(&start).unwrap_or_default();
+            for event in &events {
+                match event {
+                    TimelineEvent::AddClients { positions, weights } => {
+                        let mut all_positions = clients.positions.clone();
+                        all_positions.extend(positions);
+                        let mut all_weights = clients.weights.clone();
+                        match weights {
+                            Some(weights) => all_weights.extend(weights),
+                            None => all_weights.extend(vec![1.0; positions.len()]),
+                        }
+                        clients = ClientSet::with_weights(all_positions, all_weights)
+                            .expect("validated clients");
+                    }
+                    TimelineEvent::RemoveClients { area } => {
+                        let inside = |client: &[f64; DIMENSIONS]| {
+                            (0..2).all(|axis| {
+                                let (low, high) = (area[axis], area[axis + 2]);
+                                low.min(high) <= client[axis] && client[axis] <= low.max(high)
+                            })
+                        };
+                        let (positions, weights) = clients
+                            .positions
+                            .iter()
+                            .zip(&clients.weights)
+                            .filter(|(client, _)| !inside(client))
+                            .unzip();
+                        clients = ClientSet::with_weights(positions, weights)
+                            .expect("a subset of valid clients is valid");
+                    }
+                    TimelineEvent::FailRouter { router } => failed[*router] = true,
+                    TimelineEvent::RestoreRouter { router } => failed[*router] = false,
+                    TimelineEvent::AddWall { wall } => walls.push(Wall::from_ends(*wall)),
+                }
+            }
+
+            // The phase optimizes the working routers alone, keeping their radii
+            let active: Vec<usize> = (0..config.routers).filter(|&i| !failed[i]).collect();
+            let mut phase_config = FireflyConfig {
+                routers: active.len(),
+                iterations: end - start,
+                seed: config.seed.map(|seed| seed.wrapping_add(phase as u64)),
+                history: Default::default(),
+                relocation: None,
+                ..config.clone()
+            };
+            phase_config.radio = config
+                .radio
+                .subset(config.max_communication_distance, &active);
+            phase_config.radio.walls = walls.clone();
+            let layout = routers.as_ref().map(|routers| {
+                active
+                    .iter()
+                    .map(|&i| routers[i])
+                    .collect::<Vec<[f64; DIMENSIONS]>>()
+            });
+            let impact = layout.as_ref().map(|layout| TimelineSnapshot {
+                metrics: Metrics::evaluate(layout, &clients, &phase_config, None),
+                client_weight: clients.total_weight(),
+            });
+
+            let mut optimizer = FireflyOptimizer::new(phase_config)
+                .expect("phases inherit a valid configuration")
+                .with_clients(clients.clone());
+            if let Some(layout) = layout.filter(|_| optimizer.config().starts_from_layout()) {
+                optimizer = optimizer
+                    .with_initial_layout(layout)
+                    .expect("the layout holds the working routers");
+            }
+            let solution = optimizer.try_run()?;
+
+            // Routers failed from the start stand somewhere random until restored
+            let positions = routers.get_or_insert_with(|| {
+                (0..config.routers)
+                    .map(|_| {
+                        std::array::from_fn(|_| {
+                            rng.gen_range(config.lower_bound..config.upper_bound)
+                        })
+                    })
+                    .collect()
+            });
+            for (&i, &position) in active.iter().zip(&solution.routers) {
+                positions[i] = position;
+            }
+            phases.push(TimelinePhase {
+                start,
+                end,
+                events,
+                active,
+                clients: clients.len(),
+                walls: walls.len(),
+                impact,
+                result: TimelineSnapshot {
+                    metrics: solution.report.metrics,
+                    client_weight: clients.total_weight(),
+                },
+                routers: positions.clone(),
+            });
+        }
+        Ok(phases)
+    }
+}
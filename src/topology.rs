@@ -0,0 +1,239 @@
+//! Import of a live mesh's topology from batman-adv originator tables (`batctl o`) and
+//! OpenWrt `iw dev <interface> station dump` output.
+//!
+//! Nodes are identified by MAC address. Together with the installed positions of the
+//! nodes, the observed links give the current router layout and a transmit radius per
+//! router measured on site rather than predicted by a propagation model.
+
+use crate::{DIMENSIONS, distance};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One node hearing another, as reported by the receiving node
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObservedLink {
+    /// MAC address of the node reporting the link
+    pub from: String,
+    /// MAC address of the neighbor it hears
+    pub to: String,
+    /// batman-adv IV transmit quality as a fraction of 255
+    pub quality: Option<f64>,
+    /// batman-adv V throughput estimate in Mbit/s
+    pub throughput: Option<f64>,
+    /// Received signal strength in dBm
+    pub signal: Option<f64>,
+}
+
+/// Smallest quality and signal of a link the mesh can rely on; measurements a link
+/// does not report are not checked
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LinkThreshold {
+    pub quality: f64,
+    pub signal: f64,
+}
+
+impl Default for LinkThreshold {
+    fn default() -> LinkThreshold {
+        LinkThreshold {
+            quality: 0.5,
+            signal: -80.0,
+        }
+    }
+}
+
+impl LinkThreshold {
+    pub fn usable(&self, link: &ObservedLink) -> bool {
+        link.quality.is_none_or(|quality| quality >= self.quality)
+            && link.signal.is_none_or(|signal| signal >= self.signal)
+    }
+}
+
+/// Links observed across the mesh
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshTopology {
+    pub links: Vec<ObservedLink>,
+}
+
+// Whether a token is a MAC address such as 02:11:22:33:44:55
+fn is_mac(token: &str) -> bool {
+    token.len() == 17
+        && token.split(':').count() == 6
+        && token
+            .split(':')
+            .all(|byte| byte.len() == 2 && byte.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+// The MAC address of a token, lowercased
+fn mac(token: &str) -> Option<String> {
+    is_mac(token).then(|| token.to_ascii_lowercase())
+}
+
+// The first number in a text, e.g. -57 in "-57 [-60, -59] dBm"
+fn leading_number(text: &str) -> Option<f64> {
+    text.split(|c: char| c.is_whitespace() || c == '[' || c == ',')
+        .find(|token| !token.is_empty())?
+        .parse()
+        .ok()
+}
+
+impl MeshTopology {
+    /// Parse the output of `batctl o` or of `iw dev <interface> station dump`,
+    /// detecting which it is. A station dump does not name the node it was taken on:
+    /// prepend the output of `iw dev <interface> info` or give the node's MAC address.
+    pub fn parse(text: &str, node: Option<&str>) -> Result<MeshTopology, String> {
+        if text.contains("B.A.T.M.A.N.") || text.contains("Originator") {
+            MeshTopology::parse_batman(text)
+        } else if text.lines().any(|line| line.starts_with("Station ")) {
+            MeshTopology::parse_station_dump(text, node)
+        } else {
+            Err("expected the output of batctl o or iw dev <interface> station dump".to_string())
+        }
+    }
+
+    /// Parse the originator table printed by `batctl o`, with its header. Only
+    /// originators that are their own next hop are neighbors of the node.
+    ///
+    /// ```
+    /// use ff_wmn::topology::MeshTopology;
+    ///
+    /// let table = "\
+    /// [B.A.T.M.A.N. adv 2023.1, MainIF/MAC: mesh0/02:00:00:00:00:01 (bat0/aa:00:00:00:00:01 BATMAN_IV)]
+    ///    Originator        last-seen (#/255) Nexthop           [outgoingIF]
+    ///  * 02:00:00:00:00:02    0.560s   (255) 02:00:00:00:00:02 [     mesh0]
+    ///  * 02:00:00:00:00:03    1.120s   (198) 02:00:00:00:00:02 [     mesh0]
+    /// ";
+    /// let topology = MeshTopology::parse_batman(table).unwrap();
+    /// assert_eq!(topology.links.len(), 1);
+    /// assert_eq!(topology.links[0].to, "02:00:00:00:00:02");
+    /// assert_eq!(topology.links[0].quality, Some(1.0));
+    /// ```
+    pub fn parse_batman(text: &str) -> Result<MeshTopology, String> {
+        let header = text
+            .lines()
+            .find(|line| line.contains("MainIF/MAC:"))
+            .ok_or("no MainIF/MAC in the header, run batctl o without -H")?;
+        let from = header
+            .split(|c: char| c.is_whitespace() || c == '/' || c == ',')
+            .find_map(mac)
+            .ok_or("no MAC address in the MainIF/MAC header")?;
+        let batman_v = header.contains("BATMAN_V");
+
+        let mut links = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let macs: Vec<String> = line.split_whitespace().filter_map(mac).collect();
+            if line.contains("MainIF/MAC:") || macs.is_empty() {
+                continue;
+            }
+            let invalid = || format!("line {}: unexpected originator entry", number + 1);
+            let [originator, next_hop] = macs.as_slice() else {
+                return Err(invalid());
+            };
+            if originator != next_hop {
+                continue;
+            }
+            let metric = line
+                .split_once('(')
+                .and_then(|(_, rest)| rest.split_once(')'))
+                .and_then(|(metric, _)| metric.trim().parse::<f64>().ok())
+                .ok_or_else(invalid)?;
+            links.push(ObservedLink {
+                from: from.clone(),
+                to: originator.clone(),
+                quality: (!batman_v).then_some(metric / 255.0),
+                throughput: batman_v.then_some(metric),
+                signal: None,
+            });
+        }
+        Ok(MeshTopology { links })
+    }
+
+    /// Parse `iw dev <interface> station dump` taken on `node`, or on the node whose
+    /// `addr` is given by `iw dev <interface> info` output in the same text. Peers whose
+    /// mesh peer link is not established are left out.
+    pub fn parse_station_dump(text: &str, node: Option<&str>) -> Result<MeshTopology, String> {
+        let from = match node {
+            Some(node) => mac(node).ok_or_else(|| format!("{} is not a MAC address", node))?,
+            None => text
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("addr ").and_then(mac))
+                .ok_or("the station dump does not name its node, give its MAC address")?,
+        };
+
+        let mut links = Vec::new();
+        let mut station: Option<(ObservedLink, bool)> = None;
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("Station ") {
+                links.extend(
+                    station
+                        .take()
+                        .and_then(|(link, established)| established.then_some(link)),
+                );
+                let to = rest
+                    .split_whitespace()
+                    .next()
+                    .and_then(mac)
+                    .ok_or_else(|| format!("invalid station line: {}", line))?;
+                let link = ObservedLink {
+                    from: from.clone(),
+                    to,
+                    quality: None,
+                    throughput: None,
+                    signal: None,
+                };
+                station = Some((link, true));
+                continue;
+            }
+            let Some((link, established)) = &mut station else {
+                continue;
+            };
+            let Some((key, value)) = line.trim().split_once(':') else {
+                continue;
+            };
+            match key.trim() {
+                // The average is steadier than the last frame's signal
+                "signal avg" => link.signal = leading_number(value),
+                "signal" if link.signal.is_none() => link.signal = leading_number(value),
+                "mesh plink" => *established = value.trim() == "ESTAB",
+                _ => {}
+            }
+        }
+        links.extend(station.and_then(|(link, established)| established.then_some(link)));
+        Ok(MeshTopology { links })
+    }
+
+    /// Add the links of another node's dump
+    pub fn extend(&mut self, other: MeshTopology) {
+        self.links.extend(other.links);
+    }
+
+    /// Every node reporting or reported in a link, in MAC address order
+    pub fn nodes(&self) -> BTreeSet<&str> {
+        self.links
+            .iter()
+            .flat_map(|link| [link.from.as_str(), link.to.as_str()])
+            .collect()
+    }
+
+    /// Transmit radius of every node with a position: the length of its longest
+    /// usable link to another node with a position. Two routers are linked when
+    /// each is within the other's radius, so a link counts for both of its nodes.
+    /// Nodes without such a link are left out.
+    pub fn radii(
+        &self,
+        positions: &BTreeMap<String, [f64; DIMENSIONS]>,
+        threshold: &LinkThreshold,
+    ) -> BTreeMap<String, f64> {
+        let mut radii = BTreeMap::new();
+        for link in self.links.iter().filter(|link| threshold.usable(link)) {
+            let (Some(from), Some(to)) = (positions.get(&link.from), positions.get(&link.to))
+            else {
+                continue;
+            };
+            let length = distance(from, to);
+            for node in [&link.from, &link.to] {
+                let radius = radii.entry(node.clone()).or_insert(0.0f64);
+                *radius = radius.max(length);
+            }
+        }
+        radii
+    }
+}